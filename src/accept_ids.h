@@ -0,0 +1,55 @@
+#ifndef OSM_TRANSFORM_ACCEPT_IDS_H
+#define OSM_TRANSFORM_ACCEPT_IDS_H
+
+#include <istream>
+#include <ostream>
+#include <stdexcept>
+#include <string>
+#include <utility>
+
+#include <osmium/index/id_set.hpp>
+#include <osmium/index/nwr_array.hpp>
+#include <osmium/osm/entity_bits.hpp>
+#include <osmium/osm/item_type.hpp>
+#include <osmium/osm/types_from_string.hpp>
+
+// Reads ids in the format of `osmium getid -i`: one id per line, prefixed with n, w or r for its type, nodes if there
+// is no prefix. Empty lines and anything after whitespace or a # are ignored, so files with windows line endings work.
+// Unlike osmium, negative ids are rejected, they can't be stored in the id sets.
+inline void read_accept_ids(std::istream &in, const std::string &name, osmium::nwr_array<osmium::index::IdSetDense<osmium::unsigned_object_id_type>> &ids) {
+    std::string line;
+    unsigned long line_number = 0;
+    while (std::getline(in, line)) {
+        line_number++;
+        line = line.substr(0, line.find_first_of(" \t\r#"));
+        if (line.empty()) {
+            continue;
+        }
+        std::pair<osmium::item_type, osmium::object_id_type> parsed{osmium::item_type::undefined, 0};
+        try {
+            parsed = osmium::string_to_object_id(line.c_str(), osmium::osm_entity_bits::nwr, osmium::item_type::node);
+        } catch (const std::exception &) {
+            // reported with the line number below
+        }
+        const auto [type, id] = parsed;
+        if (type == osmium::item_type::undefined || id <= 0) {
+            throw std::runtime_error("invalid id in " + name + " line " + std::to_string(line_number) + ": " + line);
+        }
+        ids(type).set(static_cast<osmium::unsigned_object_id_type>(id));
+    }
+    if (in.bad()) {
+        throw std::runtime_error("could not read " + name);
+    }
+}
+
+// writes the ids in the format read by read_accept_ids, always with the type prefix
+inline void write_accept_ids(std::ostream &out, const osmium::nwr_array<osmium::index::IdSetDense<osmium::unsigned_object_id_type>> &ids) {
+    for (const auto type: {osmium::item_type::node, osmium::item_type::way, osmium::item_type::relation}) {
+        const char prefix = osmium::item_type_to_char(type);
+        for (const auto id: ids(type)) {
+            out << prefix << id << '\n';
+        }
+    }
+}
+
+#endif//OSM_TRANSFORM_ACCEPT_IDS_H
@@ -0,0 +1,59 @@
+#ifndef OSM_TRANSFORM_ANY_TAG_VALUE_FILTER_H
+#define OSM_TRANSFORM_ANY_TAG_VALUE_FILTER_H
+
+#include <string>
+
+#include <boost/algorithm/string.hpp>
+#include <boost/regex.hpp>
+
+#include <osmium/osm/entity_bits.hpp>
+#include <osmium/osm/object.hpp>
+
+// Accepts or rejects elements depending on whether the value of any of their tags matches a regex. Elements of
+// types not selected are always accepted.
+class AnyTagValueFilter {
+    boost::regex regex_;
+    bool accept_matching_;
+    osmium::osm_entity_bits::type types_;
+
+public:
+    AnyTagValueFilter(const std::string &regex, const bool accept_matching, const osmium::osm_entity_bits::type types) :
+        regex_(regex), accept_matching_(accept_matching), types_(types) {
+    }
+
+    // parses a comma separated list of element types ("node", "way", "relation"), false on unknown types
+    static bool parse_types(const std::string &list, osmium::osm_entity_bits::type &types) {
+        std::vector<std::string> names;
+        boost::split(names, list, boost::is_any_of(","));
+        types = osmium::osm_entity_bits::nothing;
+        for (auto &name: names) {
+            boost::trim(name);
+            if (name == "node") {
+                types |= osmium::osm_entity_bits::node;
+            } else if (name == "way") {
+                types |= osmium::osm_entity_bits::way;
+            } else if (name == "relation") {
+                types |= osmium::osm_entity_bits::relation;
+            } else {
+                return false;
+            }
+        }
+        return true;
+    }
+
+    bool accept(const osmium::OSMObject &object) const {
+        if ((osmium::osm_entity_bits::from_item_type(object.type()) & types_) == osmium::osm_entity_bits::nothing) {
+            return true;
+        }
+        bool matches = false;
+        for (const auto &tag: object.tags()) {
+            if (boost::regex_search(tag.value(), regex_)) {
+                matches = true;
+                break;
+            }
+        }
+        return matches == accept_matching_;
+    }
+};
+
+#endif//OSM_TRANSFORM_ANY_TAG_VALUE_FILTER_H
@@ -0,0 +1,49 @@
+#ifndef OSM_TRANSFORM_BATCHED_WRITER_H
+#define OSM_TRANSFORM_BATCHED_WRITER_H
+
+#include <cstddef>
+#include <iterator>
+#include <utility>
+
+#include <osmium/io/writer.hpp>
+#include <osmium/memory/buffer.hpp>
+
+// Collects output buffers until they contain at least batch_size elements before passing them to the writer, so that
+// small input blocks result in fewer and larger writes. A batch size of 0 passes every buffer on directly.
+class BatchedWriter {
+    static constexpr std::size_t kInitialCapacity = 1024 * 1024;
+
+    osmium::io::Writer &writer_;
+    std::size_t batch_size_;
+    osmium::memory::Buffer pending_{kInitialCapacity, osmium::memory::Buffer::auto_grow::yes};
+    std::size_t pending_elements_ = 0;
+
+public:
+    BatchedWriter(osmium::io::Writer &writer, const std::size_t batch_size) : writer_(writer), batch_size_(batch_size) {
+    }
+
+    void operator()(osmium::memory::Buffer &&buffer) {
+        if (batch_size_ == 0) {
+            writer_(std::move(buffer));
+            return;
+        }
+        pending_elements_ += std::distance(buffer.begin(), buffer.end());
+        pending_.add_buffer(buffer);
+        pending_.commit();
+        if (pending_elements_ >= batch_size_) {
+            flush();
+        }
+    }
+
+    // writes the remaining elements, has to be called before the writer is closed
+    void flush() {
+        if (pending_elements_ == 0) {
+            return;
+        }
+        writer_(std::move(pending_));
+        pending_ = osmium::memory::Buffer{kInitialCapacity, osmium::memory::Buffer::auto_grow::yes};
+        pending_elements_ = 0;
+    }
+};
+
+#endif//OSM_TRANSFORM_BATCHED_WRITER_H
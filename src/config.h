@@ -1,12 +1,31 @@
 #ifndef OSM_TRANSFORM_CONFIG_H
 #define OSM_TRANSFORM_CONFIG_H
 
+#include <array>
+#include <cstdio>
 #include <iostream>
+#include <map>
 #include <filesystem>
+#include <ostream>
+#include <set>
+#include <stdexcept>
+#include <string>
+#include <type_traits>
+#include <unordered_set>
+#include <boost/algorithm/string.hpp>
 #include <boost/program_options.hpp>
 
+#include <osmium/osm/metadata_options.hpp>
+#include <osmium/osm/types.hpp>
+
+#include "any_tag_value_filter.h"
+#include "json.h"
+
 struct Config {
+    // the input file currently processed, one of filenames
     std::string filename;
+    std::vector<std::string> filenames;
+    std::string output_dir;
     std::string remove_tag_regex_str;
     std::vector<std::string> geo_tiff_folders;
     bool add_elevation = true;
@@ -14,32 +33,143 @@ struct Config {
     bool debug_mode = false;
     std::uint32_t cache_limit;
     std::float_t interpolate_threshold;
+    std::int64_t generated_id_base;
+    std::string elevation_profile_mode;
+    std::string decimal_separator;
     std::string index_type;
-    std::string area_mapping;
+    std::string node_cache_spill_dir;
+    std::vector<std::string> area_mapping;
     std::uint16_t area_mapping_id_col;
     std::uint16_t area_mapping_geo_col;
     std::string area_mapping_geo_type;
     bool area_mapping_has_header;
     std::string area_mapping_processed_file_prefix;
+    bool area_mapping_rebuild_on_mismatch = false;
+    bool area_mapping_verify_index = false;
+    bool area_mapping_compress_index = false;
+    std::string area_mapping_grid_bbox_str;
+    std::uint32_t area_mapping_progress_interval;
+    std::uint32_t area_mapping_index_threads;
+    std::array<int, 4> area_mapping_grid_bbox{-180, -90, 180, 90};
+    std::string elevation_bbox_str;
+    std::vector<std::string> elevation_tiff_priority;
+    bool elevation_seam_blend = false;
+    std::array<double, 4> elevation_bbox{};
     bool download_srtm = false;
     bool download_gmted = false;
+    bool normalize_oneway_direction = false;
+    std::vector<osmium::changeset_id_type> exclude_changeset_ids;
+    std::unordered_set<osmium::changeset_id_type> exclude_changesets;
+    bool drop_invisible = false;
+    std::uint32_t max_way_nodes;
+    std::uint32_t max_relation_members;
+    bool output_only_modified = false;
+    bool stats_only = false;
+    bool count_only = false;
+    bool merge_duplicate_ways = false;
+    std::string node_cache_file;
+    bool annotate_utm_zone = false;
+    std::string remove_metadata_from_str;
+    std::set<std::string> remove_metadata_from;
+    std::vector<std::string> keep_metadata_field_names;
+    // the fields of keep_metadata_field_names
+    osmium::metadata_options keep_metadata_fields{"none"};
+    std::uint32_t tiff_open_retries;
+    std::uint32_t tiff_open_backoff;
+    bool tiff_open_strict = false;
+    bool elevation_strict_indexing = false;
+    std::string elevation_csv;
+    std::string country_points_csv;
+    bool no_ele_tag = false;
+    std::string preserve_original_ele_as;
+    bool elevation_histogram = false;
+    std::string elevation_histogram_csv;
+    double elevation_histogram_bin;
+    std::vector<std::string> normalize_multivalue_keys;
+    bool dedup_tags = false;
+    bool normalize_units = false;
+    std::vector<std::string> normalize_units_keys;
+    bool lowercase_keys = false;
+    std::vector<std::string> lowercase_keys_keys;
+    std::vector<std::string> lowercase_keys_exclude;
+    std::string dedup_tags_keep;
+    bool report_stripped_elements = false;
+    std::uint32_t pixel_cache_limit;
+    bool output_raw = false;
+    std::string output_source;
+    std::size_t output_batch_size;
+    unsigned long long max_output_elements;
+    unsigned long long max_output_elements_per_type;
+    std::uint32_t output_shards;
+    std::size_t output_shard_chunk_size;
+    std::string boundary_way_policy;
+    std::string filter_script;
+    std::string config_file;
+    std::string config_sidecar;
+    bool summary_only = false;
+    std::string summary_file;
+    double min_node_spacing;
+    bool annotate_highway_rank = false;
+    bool elevation_only_tagged = false;
+    bool elevation_relation_labels = false;
+    bool normalize_ele_units = false;
+    std::string ele_default_unit;
+    std::uint32_t prefetch;
+    bool sort_output = false;
+    std::string output_order;
+    double min_way_length;
+    std::string accept_ids_file;
+    bool filter_only = false;
+    std::string export_accept_ids;
+    bool tag_elevation_resolution = false;
+    bool tag_elevation_status = false;
+    bool debug_elevation_raw = false;
+    bool drop_relation_member_ways = false;
+    bool annotate_way_grade = false;
+    bool annotate_sinuosity = false;
+    bool annotate_bearing = false;
+    bool normalize_winding = false;
+    bool annotate_node_role = false;
+    bool annotate_node_degree = false;
+    std::uint16_t annotate_node_degree_max;
+    bool validate_refs = false;
+    std::vector<std::string> lift_node_tags;
+    bool connectivity_report = false;
+    std::string tile_density_csv;
+    std::string export_way_index;
+    bool report_tag_typos = false;
+    bool relation_role_report = false;
+    std::string warnings_file;
+    std::uint32_t warnings_limit = 1000;
+    bool annotate_node_count = false;
+    std::string invalid_coordinate_mode;
+    double elevation_split_distance;
+    std::string split_node_tag_mode;
+    std::vector<std::string> elevation_split_distance_highway_overrides;
+    std::map<std::string, double> elevation_split_distances_by_highway;
+    double elevation_scale;
+    double elevation_offset;
+    std::string any_value_regex;
+    std::string any_value_mode;
+    std::string any_value_types;
+    osmium::osm_entity_bits::type any_value_entities = osmium::osm_entity_bits::nothing;
+    std::string require_keys_str;
+    std::vector<std::string> require_keys;
+    std::string require_keys_types;
+    osmium::osm_entity_bits::type require_keys_entities = osmium::osm_entity_bits::nothing;
+    std::string country_attributes;
+    std::vector<std::string> country_attribute_names;
+    bool tag_country_confidence = false;
+    bool annotate_tag_hash = false;
+    std::vector<std::string> highway_rank_overrides;
+    std::map<std::string, int> highway_ranks;
 
-    auto cmd(int argc, char **argv) {
-
+    // the options of the command line and the config file, bound to the members of this config
+    boost::program_options::options_description options() {
         namespace po = boost::program_options;
-        std::string config_file_path;
-
-        // Declare a group of options that will be
-        // allowed only on command line
-        po::options_description generic("Generic options");
-        generic.add_options()
-                ("version,v", "print version string")
-                ("help,h", "produce help message");
-
         po::options_description config("Configuration");
-
         config.add_options()
-                ("osm_pbf,p", po::value<std::vector<std::string>>(), "path to osm pbf file to process")
+                ("osm_pbf,p", po::value<std::vector<std::string>>()->composing(), "path to osm pbf file to process (repeatable), several files are processed one after another and need an output_dir")
                 ("skip_elevation,e", "skip elevation data merge")
                 ("srtm", "fetch SRTM tiles and exit")
                 ("gmted", "fetch GMTED tiles and exit")
@@ -48,18 +178,150 @@ struct Config {
                 ("geo_tiff_folders,F", po::value<std::vector<std::string>>(&geo_tiff_folders)->multitoken()->default_value(std::vector<std::string>{"tiffs", "srtmdata", "gmteddata"}, "tiffs, srtmdata, gmteddata"), "paths to geotiff folders")
                 ("cache_limit,S", po::value<std::uint32_t>(&cache_limit)->default_value(1073741824), "maximum memory used to store tiles in cache")
                 ("threshold,t", po::value<std::float_t>(&interpolate_threshold)->default_value(0.5), "only used in combination with interpolation, threshold for elevation")
-                ("area_mapping,a", po::value<std::string>(&area_mapping), "path to area mapping file to use")
+                ("decimal_separator", po::value<std::string>(&decimal_separator)->default_value("."), "decimal separator of ele tags, only change for locale sensitive consumers as osm expects '.'")
+                ("generated_id_base", po::value<std::int64_t>(&generated_id_base)->default_value(1000000000), "only used in combination with interpolation, id of the first generated node, has to be above all node ids of the input")
+                ("elevation_profile_mode", po::value<std::string>(&elevation_profile_mode)->default_value("split"), "only used in combination with interpolation, 'split' inserts intermediate nodes, 'annotate' tags ways with their elevation profile instead, 'both' does both")
+                ("elevation_split_distance", po::value<double>(&elevation_split_distance)->default_value(0), "only used in combination with interpolation, distance in meters between the sampled points, 0 samples at the pixel size of the geotiffs")
+                ("split_node_tag_mode", po::value<std::string>(&split_node_tag_mode)->default_value("ele"), "only used in combination with interpolation, tags of the inserted nodes: 'ele' (default), 'marker' adds _generated=yes, 'none' writes them without tags")
+                ("elevation_split_distance_highway", po::value<std::vector<std::string>>(&elevation_split_distance_highway_overrides)->composing(), "elevation_split_distance for ways with this highway value, e.g. motorway=10 (repeatable)")
+                ("elevation_scale", po::value<double>(&elevation_scale)->default_value(1), "factor applied to the geotiff values, e.g. 0.3048 for DEMs in feet")
+                ("elevation_offset", po::value<double>(&elevation_offset)->default_value(0), "meters added to the scaled geotiff values, e.g. for a geoid correction")
+                ("elevation_bbox", po::value<std::string>(&elevation_bbox_str), "only index geotiffs intersecting this extent (min_lon,min_lat,max_lon,max_lat), all geotiffs are indexed by default")
+                ("elevation_tiff_priority", po::value<std::vector<std::string>>(&elevation_tiff_priority)->composing(), "prefer geotiffs whose path contains this text over others regardless of their pixel size, earlier ones first (repeatable)")
+                ("elevation_seam_blend", "blend the values of adjacent geotiffs within one pixel of their edges to avoid steps at the seams")
+                ("area_mapping,a", po::value<std::vector<std::string>>(&area_mapping)->composing(), "path to area mapping file to use, the areas of several files with the same columns are merged into one index (repeatable)")
                 ("area_mapping_id_col", po::value<std::uint16_t>(&area_mapping_id_col)->default_value(0), "column number (zero-based) in area mapping file of area id")
                 ("area_mapping_geo_col", po::value<std::uint16_t>(&area_mapping_geo_col)->default_value(1), "column number (zero-based) in area mapping file of area geometry")
                 ("area_mapping_geo_type", po::value<std::string>(&area_mapping_geo_type)->default_value("wkt"), "type of geometry string in area mapping file (possible values: 'wkt' (default), 'geojson')")
                 ("area_mapping_has_header", po::value<bool>(&area_mapping_has_header)->default_value(true), "area mapping file has header row")
                 ("area_mapping_processed_file_prefix", po::value<std::string>(&area_mapping_processed_file_prefix)->default_value("mapping_"), "file prefix for processed mapping files")
-                ("config_file,f", po::value<std::string>(&config_file_path), "path to config file to use")
+                ("area_mapping_rebuild_on_mismatch", "rebuild processed mapping files from the area mapping file if they were built with different settings")
+                ("area_mapping_verify_index", "read the processed mapping files back after building them and report differences to the built index")
+                ("area_mapping_compress_index", "write the processed mapping files gzip compressed")
+                ("area_mapping_grid_bbox", po::value<std::string>(&area_mapping_grid_bbox_str)->default_value("-180,-90,180,90"), "extent of the area mapping grid in full degrees (min_lon,min_lat,max_lon,max_lat), nodes outside get no area")
+                ("area_mapping_progress_interval", po::value<std::uint32_t>(&area_mapping_progress_interval)->default_value(10), "report progress every given number of areas while building the area mapping index (0 = disabled)")
+                ("area_mapping_index_threads", po::value<std::uint32_t>(&area_mapping_index_threads)->default_value(1), "number of threads testing the grid tiles against the areas while building the area mapping index")
+                ("country_attributes", po::value<std::string>(&country_attributes), "csv file with attributes per area id of the area mapping, which are added as tags to the nodes in the area")
+                ("country_attribute", po::value<std::vector<std::string>>(&country_attribute_names)->composing(), "name of an attribute of the country_attributes file to add, all if not given (repeatable)")
+                ("tag_country_confidence", "tag nodes with a country with country:confidence=certain if their grid tile lies in one area and ambiguous if it intersects several")
+                ("config_file,f", po::value<std::string>(&config_file), "path to config file to use")
                 ("index_type", po::value<std::string>(&index_type)->default_value("flex_mem"), "index type for locations, needed for interpolate. see https://docs.osmcode.org/osmium/latest/osmium-index-types.html")
+                ("node_cache_spill_dir", po::value<std::string>(&node_cache_spill_dir), "directory for a file backed location index that is paged out to disk when memory gets short, overrides index_type")
+                ("normalize_oneway_direction", "reverse ways tagged oneway=-1 and retag them as oneway=yes, this changes the geometry direction")
+                ("exclude_changeset", po::value<std::vector<osmium::changeset_id_type>>(&exclude_changeset_ids)->composing(), "drop all elements last modified in the given changeset (repeatable)")
+                ("max_way_nodes", po::value<std::uint32_t>(&max_way_nodes)->default_value(0), "split ways with more nodes into consecutive ways sharing the split node (0 = no splitting)")
+                ("max_relation_members", po::value<std::uint32_t>(&max_relation_members)->default_value(0), "drop relations with more members (0 = no limit)")
+                ("output_only_modified", "only write nodes and ways modified by the enrichment (elevation, country, interpolation, splitting)")
+                ("stats_only", "only filter and count elements, skip elevation, area mapping and interpolation even if configured")
+                ("count_only", "only count the nodes, ways and relations of the input in a single pass, without filtering or output")
+                ("merge_duplicate_ways", "merge the tags of ways with identical node sequences into the way with the lowest id and drop the others")
+                ("node_cache_file", po::value<std::string>(&node_cache_file), "file to reuse node elevations from, it is (re)written if missing or created for a different input file or other elevation settings")
+                ("annotate_utm_zone", "tag nodes with their UTM zone and hemisphere, e.g. _utm=32N")
+                ("remove_metadata_from", po::value<std::string>(&remove_metadata_from_str)->default_value("node,way,relation"), "comma separated element types to remove metadata (version, changeset, timestamp, user) from")
+                ("keep_metadata_field", po::value<std::vector<std::string>>(&keep_metadata_field_names)->composing(), "metadata field (version, changeset, timestamp, uid, user) to keep for the element types in remove_metadata_from (repeatable)")
+                ("tiff_open_retries", po::value<std::uint32_t>(&tiff_open_retries)->default_value(0), "number of retries when opening a geotiff fails")
+                ("tiff_open_backoff", po::value<std::uint32_t>(&tiff_open_backoff)->default_value(100), "initial wait time in ms before retrying to open a geotiff, doubled with each retry")
+                ("tiff_open_strict", "abort if a geotiff can not be opened after all retries instead of skipping it")
+                ("elevation_strict_indexing", "abort if any geotiff can not be read while building the geotiff index at startup, e.g. because it is corrupt, instead of skipping it")
+                ("elevation_csv", po::value<std::string>(&elevation_csv), "write id, lon, lat and elevation of all nodes with elevation to a csv file")
+                ("no_ele_tag", "only write elevations to the elevation_csv file, don't add or replace ele tags")
+                ("preserve_original_ele_as", po::value<std::string>(&preserve_original_ele_as), "keep the value of existing ele tags replaced by the DEM elevation under this key, e.g. ele:osm")
+                ("elevation_histogram", "print the number of nodes per elevation bin after the second pass")
+                ("elevation_histogram_csv", po::value<std::string>(&elevation_histogram_csv), "write the number of nodes per elevation bin to a csv file")
+                ("elevation_histogram_bin", po::value<double>(&elevation_histogram_bin)->default_value(100), "size of the elevation histogram bins in meters")
+                ("country_points_csv", po::value<std::string>(&country_points_csv), "write id, lon, lat and country of all nodes with a country to a csv file")
+                ("drop_invisible", "drop elements marked as not visible (deleted), only relevant for files derived from history files")
+                ("normalize_multivalue_key", po::value<std::vector<std::string>>(&normalize_multivalue_keys)->composing(), "sort and deduplicate the semicolon separated values of this tag key (repeatable)")
+                ("normalize_units", "convert imperial values like 10'6\" or 30 mph of some tag keys to metric numbers, i.e. meters and km/h")
+                ("normalize_units_key", po::value<std::vector<std::string>>(&normalize_units_keys)->composing(), "only used in combination with normalize_units, tag key whose values are converted (repeatable, default: maxspeed, maxheight, maxwidth, width)")
+                ("lowercase_keys", "lowercase the tag keys, e.g. Highway to highway, and merge the values of tags whose keys are equal afterwards")
+                ("lowercase_keys_key", po::value<std::vector<std::string>>(&lowercase_keys_keys)->composing(), "only used in combination with lowercase_keys, lowercase key which is normalized, all keys if not given (repeatable)")
+                ("lowercase_keys_exclude", po::value<std::vector<std::string>>(&lowercase_keys_exclude)->composing(), "only used in combination with lowercase_keys, case-sensitive key which is kept as it is (repeatable)")
+                ("dedup_tags", "remove tags whose key occurs more than once on an element except for one")
+                ("dedup_tags_keep", po::value<std::string>(&dedup_tags_keep)->default_value("first"), "only used in combination with dedup_tags, which of the tags with the same key is kept (possible values: 'first' (default), 'last')")
+                ("report_stripped_elements", "count the accepted ways and relations which have tags, but none left after removing the tags matching remove_tag")
+                ("pixel_cache_limit", po::value<std::uint32_t>(&pixel_cache_limit)->default_value(1024), "number of pixel values cached per opened geotiff to avoid repeated reads (0 = disabled)")
+                ("output_raw", "write uncompressed pbf blocks, faster but considerably larger output")
+                ("output_dir", po::value<std::string>(&output_dir), "directory for the output files, default is the working directory")
+                ("output_batch_size", po::value<std::size_t>(&output_batch_size)->default_value(0), "collect at least this many output elements before passing them to the writer (0 = write the output of each input block)")
+                ("max_output_elements", po::value<unsigned long long>(&max_output_elements)->default_value(0), "stop writing after this many elements, the passes and statistics still cover all elements (0 = no limit)")
+                ("max_output_elements_per_type", po::value<unsigned long long>(&max_output_elements_per_type)->default_value(0), "stop writing nodes, ways and relations after this many elements of the type each (0 = no limit)")
+                ("output_shards", po::value<std::uint32_t>(&output_shards)->default_value(0), "split the output into this many files of about the same number of elements, named like the output with .000, .001, ... before .pbf (0 = one output file)")
+                ("output_shard_chunk_size", po::value<std::size_t>(&output_shard_chunk_size)->default_value(8000), "only used in combination with output_shards, number of consecutive elements written to a shard before the next one")
+                ("boundary_way_policy", po::value<std::string>(&boundary_way_policy)->default_value("none"), "only used in combination with output_shards, how ways are distributed (possible values: 'none' (default) distributes them like all other elements, 'duplicate', 'assign-to-first' and 'clip' write them to the shards of their nodes and ways crossing shards to all of them, to the first one or to all with only the nodes in each)")
+                ("output_source", po::value<std::string>(&output_source), "source of the data written to the header of the output file, e.g. 'OpenStreetMap contributors, SRTM'")
+                ("filter_script", po::value<std::string>(&filter_script), "Lua script defining a function filter(type, id, tags) returning false for elements to drop, requires a build with WITH_LUA")
+                ("write_config_sidecar", po::value<std::string>(&config_sidecar), "write the effective configuration as json to the given file")
+                ("summary_only", "suppress all log output and progress bars, only print a single json summary line at the end")
+                ("summary_file", po::value<std::string>(&summary_file), "write the json summary line to this file")
+                ("min_node_spacing", po::value<double>(&min_node_spacing)->default_value(0), "drop untagged way nodes closer than this many meters to the previous kept node of their way, way endpoints and shared nodes are kept (0 = disabled)")
+                ("annotate_highway_rank", "tag ways with a numeric rank of their highway value, e.g. _highway_rank=1 for motorways")
+                ("highway_rank", po::value<std::vector<std::string>>(&highway_rank_overrides)->composing(), "override or add the rank of a highway value for annotate_highway_rank, e.g. track=9 (repeatable)")
+                ("elevation_only_tagged", "only add elevation to nodes with tags, untagged way nodes keep no ele tag")
+                ("elevation_relation_labels", "always add elevation to label and admin_centre member nodes of accepted relations, even with elevation_only_tagged")
+                ("normalize_ele_units", "convert existing ele tags with unit suffixes (m, ft) to plain meters, only relevant if they are kept, i.e. with skip_elevation")
+                ("ele_default_unit", po::value<std::string>(&ele_default_unit)->default_value("m"), "unit of existing ele tags without suffix for normalize_ele_units (possible values: 'm', 'ft')")
+                ("prefetch", po::value<std::uint32_t>(&prefetch)->default_value(0), "number of input blocks read and decoded ahead in the background while elements are processed (0 = libosmium default of 20)")
+                ("sort_output", "sort the output by type and id, needs memory for the whole output file")
+                ("output_order", po::value<std::string>(&output_order)->default_value("nodes-first"), "order of the element types in the output (possible values: 'nodes-first' (default), 'relations-first'), relations-first needs memory for the whole output file")
+                ("annotate_tag_hash", "tag all elements with a hash of their kept input tags, e.g. _tag_hash=8a1f0c2e5b7d3940")
+                ("min_way_length", po::value<double>(&min_way_length)->default_value(0), "drop ways shorter than this many meters, their nodes are still written (0 = disabled)")
+                ("accept_ids_file", po::value<std::string>(&accept_ids_file), "skip the first pass and only keep the elements listed in this file, one id per line prefixed with its type, e.g. n123, w456, r789")
+                ("filter_only", "only run the first pass and print the number of accepted elements, no output is written")
+                ("export_accept_ids", po::value<std::string>(&export_accept_ids), "write the ids accepted by the first pass to this file in the format of accept_ids_file")
+                ("tag_elevation_resolution", "tag nodes with the approximate pixel size in meters of the geotiff their elevation is taken from, e.g. ele:resolution=30")
+                ("tag_elevation_status", "tag nodes without elevation with ele:status=nodata if no elevation was found or ele:status=skipped if the lookup was skipped by elevation_only_tagged")
+                ("drop_relation_member_ways", "drop ways without routing tags (highway, route, platforms, piers) that are members of a kept relation")
+                ("annotate_way_grade", "tag ways with the elevation of their first and last node (ele:start, ele:end) and the grade in percent between them")
+                ("annotate_sinuosity", "tag ways with their length divided by the distance of their endpoints, e.g. _sinuosity=1.250")
+                ("annotate_bearing", "tag ways with the bearing from their first to their last node in degrees clockwise from north, e.g. _bearing=45.0")
+                ("any_value_regex", po::value<std::string>(&any_value_regex), "regex searched in the values of all tags of an element, see any_value_mode")
+                ("any_value_mode", po::value<std::string>(&any_value_mode)->default_value("accept"), "accept: keep only elements with a tag value matching any_value_regex, remove: drop them")
+                ("any_value_types", po::value<std::string>(&any_value_types)->default_value("way,relation"), "comma separated element types (node, way, relation) any_value_regex is applied to")
+                ("require_keys", po::value<std::string>(&require_keys_str), "comma separated tag keys, keep only elements having all of them")
+                ("require_keys_types", po::value<std::string>(&require_keys_types)->default_value("way"), "comma separated element types (node, way, relation) require_keys is applied to")
+                ("normalize_winding", "reverse closed ways with clockwise node order to counterclockwise, except oneways and roundabouts")
+                ("annotate_node_role", "tag nodes of accepted ways with _on_way=yes and nodes shared by several of them with _junction=yes")
+                ("annotate_node_degree", "tag nodes of accepted ways with the number of accepted ways referencing them as _degree, needs ca. 8 bytes per additional way of a shared node")
+                ("annotate_node_degree_max", po::value<std::uint16_t>(&annotate_node_degree_max)->default_value(0), "only used in combination with annotate_node_degree, higher degrees are written as this value (0 = no limit)")
+                ("validate_refs", "drop node refs of ways which are not positive or, with interpolate, not below generated_id_base, and ways with less than two valid refs")
+                ("lift_node_tag", po::value<std::vector<std::string>>(&lift_node_tags)->composing(), "copy the tag with this key from the nodes of a way to the way (repeatable)")
+                ("connectivity_report", "report the number of connected components of the accepted ways and the size of the largest one, needs ca. 50 bytes per way node")
+                ("tile_density_csv", po::value<std::string>(&tile_density_csv), "write the number of nodes and ways per 1x1 degree tile to a csv file")
+                ("export_way_index", po::value<std::string>(&export_way_index), "write the bounding boxes of the written ways to a binary file for spatial lookups")
+                ("report_tag_typos", "report tag keys of the input which are probably misspelled common keys, e.g. hihgway, with their number and an example element")
+                ("relation_role_report", "report the number of relations per type and of their members per role, e.g. forward, backward and stop of route relations")
+                ("warnings_file", po::value<std::string>(&warnings_file), "write all warnings and element level issues like missing elevations as JSON lines to this file")
+                ("warnings_limit", po::value<std::uint32_t>(&warnings_limit)->default_value(1000), "only used in combination with warnings_file, number of warnings written per category, further ones are only counted (0 = no limit)")
+                ("annotate_node_count", "tag ways with the number of their node refs after interpolation and splitting, e.g. _node_count=12")
+                ("invalid_coordinate_mode", po::value<std::string>(&invalid_coordinate_mode)->default_value("keep"), "nodes with missing or out of range coordinates: 'keep' writes them without elevation and area, 'drop' removes them, 'clamp' moves them into range")
                 ("debug_mode,d", "debug_mode");
+        return config;
+    }
+
+    boost::program_options::options_description hidden_options() {
+        namespace po = boost::program_options;
         // Hidden options, will be allowed both on command line and
         // in config file, but will not be shown to the user.
         po::options_description hidden("Hidden options");
+        hidden.add_options()
+                ("debug_elevation_raw", "tag nodes with the pixel coordinate and the unscaled value of the geotiff their elevation is taken from, e.g. ele:px=1201,388 and ele:raw=231.000000");
+        return hidden;
+    }
+
+    auto cmd(int argc, char **argv) {
+
+        namespace po = boost::program_options;
+
+        // Declare a group of options that will be
+        // allowed only on command line
+        po::options_description generic("Generic options");
+        generic.add_options()
+                ("version,v", "print version string")
+                ("help,h", "produce help message");
+
+        po::options_description config = options();
+        po::options_description hidden = hidden_options();
 
         po::options_description cmdline_options;
         cmdline_options.add(generic).add(config).add(hidden);
@@ -83,12 +345,12 @@ struct Config {
             exit(1);
         }
         try {
-            if (std::filesystem::exists(config_file_path)) {
-                po::store(po::parse_config_file(config_file_path.c_str(), config_file_options, false), vm);
+            if (std::filesystem::exists(config_file)) {
+                po::store(po::parse_config_file(config_file.c_str(), config_file_options, false), vm);
                 po::notify(vm);
             }
         } catch (boost::program_options::unknown_option &e) {
-            std::cerr << e.what() << "  in config file " << config_file_path << std::endl;
+            std::cerr << e.what() << "  in config file " << config_file << std::endl;
             std::cout << config << "\n";
             exit(1);
         }
@@ -119,11 +381,14 @@ struct Config {
             exit(1);
         }
 
-        filename = vm["osm_pbf"].as<std::vector<std::string>>()[0];
+        filenames = vm["osm_pbf"].as<std::vector<std::string>>();
+        filename = filenames[0];
 
-        if (!std::filesystem::exists(filename)) {
-            std::cerr << "osm_pbf does not exist " << filename << std::endl;
-            exit(1);
+        for (const auto &f: filenames) {
+            if (!std::filesystem::exists(f)) {
+                std::cerr << "osm_pbf does not exist " << f << std::endl;
+                exit(1);
+            }
         }
 
         if (vm.contains("interpolate")) {
@@ -133,6 +398,425 @@ struct Config {
         if (vm.contains("skip_elevation")) {
             add_elevation = false;
         }
+
+        stats_only = vm.contains("stats_only");
+        count_only = vm.contains("count_only");
+        filter_only = vm.contains("filter_only");
+        if (stats_only) {
+            add_elevation = false;
+            interpolate = false;
+            area_mapping.clear();
+        }
+
+        normalize_oneway_direction = vm.contains("normalize_oneway_direction");
+        output_only_modified = vm.contains("output_only_modified");
+        merge_duplicate_ways = vm.contains("merge_duplicate_ways");
+        annotate_utm_zone = vm.contains("annotate_utm_zone");
+        tiff_open_strict = vm.contains("tiff_open_strict");
+        elevation_strict_indexing = vm.contains("elevation_strict_indexing");
+        elevation_seam_blend = vm.contains("elevation_seam_blend");
+        drop_invisible = vm.contains("drop_invisible");
+        exclude_changesets.insert(exclude_changeset_ids.begin(), exclude_changeset_ids.end());
+        output_raw = vm.contains("output_raw");
+        summary_only = vm.contains("summary_only");
+        annotate_highway_rank = vm.contains("annotate_highway_rank");
+        elevation_only_tagged = vm.contains("elevation_only_tagged");
+        elevation_relation_labels = vm.contains("elevation_relation_labels");
+        normalize_ele_units = vm.contains("normalize_ele_units");
+        sort_output = vm.contains("sort_output");
+        annotate_tag_hash = vm.contains("annotate_tag_hash");
+        tag_elevation_resolution = vm.contains("tag_elevation_resolution");
+        tag_country_confidence = vm.contains("tag_country_confidence");
+        tag_elevation_status = vm.contains("tag_elevation_status");
+        debug_elevation_raw = vm.contains("debug_elevation_raw");
+        drop_relation_member_ways = vm.contains("drop_relation_member_ways");
+        annotate_way_grade = vm.contains("annotate_way_grade");
+        annotate_sinuosity = vm.contains("annotate_sinuosity");
+        annotate_bearing = vm.contains("annotate_bearing");
+        normalize_winding = vm.contains("normalize_winding");
+        annotate_node_role = vm.contains("annotate_node_role");
+        annotate_node_degree = vm.contains("annotate_node_degree");
+        validate_refs = vm.contains("validate_refs");
+        connectivity_report = vm.contains("connectivity_report");
+        report_tag_typos = vm.contains("report_tag_typos");
+        relation_role_report = vm.contains("relation_role_report");
+        annotate_node_count = vm.contains("annotate_node_count");
+        no_ele_tag = vm.contains("no_ele_tag");
+        elevation_histogram = vm.contains("elevation_histogram");
+        if (elevation_histogram_bin <= 0) {
+            std::cerr << "elevation_histogram_bin must be positive" << std::endl;
+            exit(1);
+        }
+        if (area_mapping_index_threads == 0) {
+            std::cerr << "area_mapping_index_threads must be positive" << std::endl;
+            exit(1);
+        }
+        if (!country_points_csv.empty() && area_mapping.empty()) {
+            std::cerr << "country_points_csv requires area_mapping" << std::endl;
+            exit(1);
+        }
+        if (!preserve_original_ele_as.empty() && (preserve_original_ele_as == "ele" || !add_elevation || no_ele_tag)) {
+            std::cerr << "preserve_original_ele_as needs a key other than ele and can't be combined with skip_elevation or no_ele_tag, which keep the ele tags anyway" << std::endl;
+            exit(1);
+        }
+        if (no_ele_tag && elevation_csv.empty()) {
+            std::cerr << "no_ele_tag requires elevation_csv, otherwise the elevations are not written at all" << std::endl;
+            exit(1);
+        }
+        if (ele_default_unit != "m" && ele_default_unit != "ft") {
+            std::cerr << "invalid ele_default_unit " << ele_default_unit << ", expected m or ft" << std::endl;
+            exit(1);
+        }
+
+#ifndef OSM_TRANSFORM_WITH_LUA
+        if (!filter_script.empty()) {
+            std::cerr << "filter_script requires osm-transform to be built with WITH_LUA" << std::endl;
+            exit(1);
+        }
+#endif
+
+        std::vector<std::string> types;
+        boost::split(types, remove_metadata_from_str, boost::is_any_of(","), boost::token_compress_on);
+        for (const auto &type: types) {
+            if (type.empty()) continue;
+            if (type != "node" && type != "way" && type != "relation") {
+                std::cerr << "invalid element type in remove_metadata_from: " << type << std::endl;
+                exit(1);
+            }
+            remove_metadata_from.insert(type);
+        }
+        for (const auto &field: keep_metadata_field_names) {
+            if (field != "version" && field != "changeset" && field != "timestamp" && field != "uid" && field != "user") {
+                std::cerr << "invalid metadata field in keep_metadata_field: " << field << std::endl;
+                exit(1);
+            }
+        }
+        if (!keep_metadata_field_names.empty()) {
+            keep_metadata_fields = osmium::metadata_options{boost::algorithm::join(keep_metadata_field_names, "+")};
+        }
+
+        for (const auto &rank: highway_rank_overrides) {
+            const auto separator = rank.find('=');
+            try {
+                if (separator == std::string::npos || separator == 0) throw std::invalid_argument(rank);
+                const auto value = rank.substr(separator + 1);
+                std::size_t parsed;
+                // std::stoi stops at the first invalid character, e.g. at the x of 9x
+                highway_ranks[rank.substr(0, separator)] = std::stoi(value, &parsed);
+                if (parsed != value.size()) throw std::invalid_argument(rank);
+            } catch (const std::logic_error &) {
+                std::cerr << "invalid highway_rank " << rank << ", expected value=rank" << std::endl;
+                exit(1);
+            }
+        }
+
+        if (any_value_mode != "accept" && any_value_mode != "remove") {
+            std::cerr << "invalid any_value_mode " << any_value_mode << ", expected accept or remove" << std::endl;
+            exit(1);
+        }
+        if (!AnyTagValueFilter::parse_types(any_value_types, any_value_entities)) {
+            std::cerr << "invalid element type in any_value_types: " << any_value_types << std::endl;
+            exit(1);
+        }
+        if (!require_keys_str.empty()) {
+            boost::split(require_keys, require_keys_str, boost::is_any_of(","), boost::token_compress_on);
+            for (auto &key: require_keys) {
+                boost::trim(key);
+            }
+            std::erase(require_keys, "");
+        }
+        if (!AnyTagValueFilter::parse_types(require_keys_types, require_keys_entities)) {
+            std::cerr << "invalid element type in require_keys_types: " << require_keys_types << std::endl;
+            exit(1);
+        }
+
+        if (elevation_split_distance < 0) {
+            std::cerr << "elevation_split_distance must not be negative" << std::endl;
+            exit(1);
+        }
+        if (split_node_tag_mode != "ele" && split_node_tag_mode != "marker" && split_node_tag_mode != "none") {
+            std::cerr << "invalid split_node_tag_mode " << split_node_tag_mode << ", expected ele, marker or none" << std::endl;
+            exit(1);
+        }
+        for (const auto &distance: elevation_split_distance_highway_overrides) {
+            const auto separator = distance.find('=');
+            try {
+                if (separator == std::string::npos || separator == 0) throw std::invalid_argument(distance);
+                std::size_t parsed;
+                const auto value = std::stod(distance.substr(separator + 1), &parsed);
+                if (value < 0 || parsed != distance.size() - separator - 1) throw std::invalid_argument(distance);
+                elevation_split_distances_by_highway[distance.substr(0, separator)] = value;
+            } catch (const std::logic_error &) {
+                std::cerr << "invalid elevation_split_distance_highway " << distance << ", expected value=meters" << std::endl;
+                exit(1);
+            }
+        }
+
+        normalize_units = vm.contains("normalize_units");
+        if (normalize_units && normalize_units_keys.empty()) {
+            normalize_units_keys = {"maxspeed", "maxheight", "maxwidth", "width"};
+        }
+        lowercase_keys = vm.contains("lowercase_keys");
+        dedup_tags = vm.contains("dedup_tags");
+        report_stripped_elements = vm.contains("report_stripped_elements");
+        if (dedup_tags_keep != "first" && dedup_tags_keep != "last") {
+            std::cerr << "invalid dedup_tags_keep " << dedup_tags_keep << ", expected first or last" << std::endl;
+            exit(1);
+        }
+        if (invalid_coordinate_mode != "keep" && invalid_coordinate_mode != "drop" && invalid_coordinate_mode != "clamp") {
+            std::cerr << "invalid invalid_coordinate_mode " << invalid_coordinate_mode << ", expected keep, drop or clamp" << std::endl;
+            exit(1);
+        }
+
+        if (generated_id_base <= 0) {
+            std::cerr << "generated_id_base must be positive" << std::endl;
+            exit(1);
+        }
+
+        if (decimal_separator.size() != 1) {
+            std::cerr << "decimal_separator must be a single character" << std::endl;
+            exit(1);
+        }
+
+        if (output_order != "nodes-first" && output_order != "relations-first") {
+            std::cerr << "invalid output_order " << output_order << ", expected nodes-first or relations-first" << std::endl;
+            exit(1);
+        }
+
+        if (elevation_profile_mode != "split" && elevation_profile_mode != "annotate" && elevation_profile_mode != "both") {
+            std::cerr << "invalid elevation_profile_mode " << elevation_profile_mode << ", expected split, annotate or both" << std::endl;
+            exit(1);
+        }
+
+        if (!accept_ids_file.empty()) {
+            if (!std::filesystem::exists(accept_ids_file)) {
+                std::cerr << "accept_ids_file does not exist " << accept_ids_file << std::endl;
+                exit(1);
+            }
+            if (merge_duplicate_ways || min_node_spacing > 0 || drop_relation_member_ways || annotate_node_role || annotate_node_degree || max_relation_members > 0 || elevation_relation_labels) {
+                std::cerr << "accept_ids_file can't be combined with merge_duplicate_ways, min_node_spacing, drop_relation_member_ways, annotate_node_role, annotate_node_degree, max_relation_members or elevation_relation_labels, they need the first pass" << std::endl;
+                exit(1);
+            }
+        }
+        if (!output_dir.empty() && !std::filesystem::is_directory(output_dir)) {
+            std::cerr << "output_dir is not a directory " << output_dir << std::endl;
+            exit(1);
+        }
+        if (filenames.size() > 1) {
+            if (output_dir.empty()) {
+                std::cerr << "multiple osm_pbf files need an output_dir" << std::endl;
+                exit(1);
+            }
+            if (count_only || !node_cache_file.empty() || !export_accept_ids.empty() || !elevation_csv.empty() || !country_points_csv.empty() || !tile_density_csv.empty() || !elevation_histogram_csv.empty() || !export_way_index.empty()) {
+                std::cerr << "multiple osm_pbf files can't be combined with count_only, node_cache_file, export_accept_ids, elevation_csv, country_points_csv, tile_density_csv, elevation_histogram_csv or export_way_index" << std::endl;
+                exit(1);
+            }
+            std::set<std::string> stems;
+            for (const auto &f: filenames) {
+                // the output is named after the file name up to the first dot
+                const auto name = std::filesystem::path(f).filename().string();
+                if (!stems.insert(name.substr(0, name.find('.'))).second) {
+                    std::cerr << "osm_pbf files with the same name would be written to the same output file " << f << std::endl;
+                    exit(1);
+                }
+            }
+        }
+        if ((filter_only || !export_accept_ids.empty()) && !accept_ids_file.empty()) {
+            std::cerr << "filter_only and export_accept_ids can't be combined with accept_ids_file, they need the first pass" << std::endl;
+            exit(1);
+        }
+        if (filter_only && (summary_only || !summary_file.empty())) {
+            std::cerr << "filter_only can't be combined with summary_only or summary_file, there is no summary without the second pass" << std::endl;
+            exit(1);
+        }
+        if (!node_cache_spill_dir.empty() && !std::filesystem::is_directory(node_cache_spill_dir)) {
+            std::cerr << "node_cache_spill_dir is not a directory " << node_cache_spill_dir << std::endl;
+            exit(1);
+        }
+        if (!country_attributes.empty() && area_mapping.empty()) {
+            std::cerr << "country_attributes requires area_mapping" << std::endl;
+            exit(1);
+        }
+        if (tag_country_confidence && area_mapping.empty()) {
+            std::cerr << "tag_country_confidence requires area_mapping" << std::endl;
+            exit(1);
+        }
+        if (min_way_length < 0) {
+            std::cerr << "min_way_length must not be negative" << std::endl;
+            exit(1);
+        }
+        if (min_node_spacing < 0) {
+            std::cerr << "min_node_spacing must not be negative" << std::endl;
+            exit(1);
+        }
+        if (output_shards > 0 && output_shard_chunk_size == 0) {
+            std::cerr << "output_shard_chunk_size must be greater than 0" << std::endl;
+            exit(1);
+        }
+        if (boundary_way_policy != "none" && boundary_way_policy != "duplicate" && boundary_way_policy != "assign-to-first" && boundary_way_policy != "clip") {
+            std::cerr << "invalid boundary_way_policy " << boundary_way_policy << ", expected none, duplicate, assign-to-first or clip" << std::endl;
+            exit(1);
+        }
+        if (boundary_way_policy != "none" && output_order == "relations-first") {
+            std::cerr << "boundary_way_policy needs the nodes before the ways and can't be combined with output_order relations-first" << std::endl;
+            exit(1);
+        }
+        if (max_way_nodes == 1) {
+            std::cerr << "max_way_nodes must be at least 2" << std::endl;
+            exit(1);
+        }
+        area_mapping_rebuild_on_mismatch = vm.contains("area_mapping_rebuild_on_mismatch");
+        area_mapping_verify_index = vm.contains("area_mapping_verify_index");
+        area_mapping_compress_index = vm.contains("area_mapping_compress_index");
+
+        auto &b = area_mapping_grid_bbox;
+        if (sscanf(area_mapping_grid_bbox_str.c_str(), "%d,%d,%d,%d", &b[0], &b[1], &b[2], &b[3]) != 4 ||
+            b[0] < -180 || b[1] < -90 || b[2] > 180 || b[3] > 90 || b[0] >= b[2] || b[1] >= b[3]) {
+            std::cerr << "invalid area_mapping_grid_bbox " << area_mapping_grid_bbox_str << std::endl;
+            exit(1);
+        }
+        auto &e = elevation_bbox;
+        if (!elevation_bbox_str.empty() &&
+            (sscanf(elevation_bbox_str.c_str(), "%lf,%lf,%lf,%lf", &e[0], &e[1], &e[2], &e[3]) != 4 ||
+             e[0] < -180 || e[1] < -90 || e[2] > 180 || e[3] > 90 || e[0] >= e[2] || e[1] >= e[3])) {
+            std::cerr << "invalid elevation_bbox " << elevation_bbox_str << std::endl;
+            exit(1);
+        }
+    }
+
+    // writes the effective configuration after all options are resolved
+    void write_json(std::ostream &out) const {
+        out << std::boolalpha << "{\n"
+            << "  \"version\": " << json_string(PROJECT_VERSION) << ",\n"
+            << "  \"osm_pbf\": " << json_array(filenames) << ",\n"
+            << "  \"output_dir\": " << json_string(output_dir) << ",\n"
+            << "  \"config_file\": " << json_string(config_file) << ",\n"
+            << "  \"write_config_sidecar\": " << json_string(config_sidecar) << ",\n"
+            << "  \"summary_only\": " << summary_only << ",\n"
+            << "  \"summary_file\": " << json_string(summary_file) << ",\n"
+            << "  \"srtm\": " << download_srtm << ",\n"
+            << "  \"gmted\": " << download_gmted << ",\n"
+            << "  \"add_elevation\": " << add_elevation << ",\n"
+            << "  \"geo_tiff_folders\": " << json_array(geo_tiff_folders) << ",\n"
+            << "  \"elevation_tiff_priority\": " << json_array(elevation_tiff_priority) << ",\n"
+            << "  \"elevation_seam_blend\": " << elevation_seam_blend << ",\n"
+            << "  \"cache_limit\": " << cache_limit << ",\n"
+            << "  \"pixel_cache_limit\": " << pixel_cache_limit << ",\n"
+            << "  \"tiff_open_retries\": " << tiff_open_retries << ",\n"
+            << "  \"tiff_open_backoff\": " << tiff_open_backoff << ",\n"
+            << "  \"tiff_open_strict\": " << tiff_open_strict << ",\n"
+            << "  \"elevation_strict_indexing\": " << elevation_strict_indexing << ",\n"
+            << "  \"interpolate\": " << interpolate << ",\n"
+            << "  \"threshold\": " << interpolate_threshold << ",\n"
+            << "  \"elevation_split_distance\": " << elevation_split_distance << ",\n"
+            << "  \"split_node_tag_mode\": " << json_string(split_node_tag_mode) << ",\n"
+            << "  \"elevation_split_distance_highway\": " << json_array(elevation_split_distance_highway_overrides) << ",\n"
+            << "  \"elevation_bbox\": " << json_string(elevation_bbox_str) << ",\n"
+            << "  \"elevation_scale\": " << elevation_scale << ",\n"
+            << "  \"elevation_offset\": " << elevation_offset << ",\n"
+            << "  \"generated_id_base\": " << generated_id_base << ",\n"
+            << "  \"elevation_profile_mode\": " << json_string(elevation_profile_mode) << ",\n"
+            << "  \"decimal_separator\": " << json_string(decimal_separator) << ",\n"
+            << "  \"index_type\": " << json_string(index_type) << ",\n"
+            << "  \"node_cache_spill_dir\": " << json_string(node_cache_spill_dir) << ",\n"
+            << "  \"remove_tag\": " << json_string(remove_tag_regex_str) << ",\n"
+            << "  \"area_mapping\": " << json_array(area_mapping) << ",\n"
+            << "  \"area_mapping_id_col\": " << area_mapping_id_col << ",\n"
+            << "  \"area_mapping_geo_col\": " << area_mapping_geo_col << ",\n"
+            << "  \"area_mapping_geo_type\": " << json_string(area_mapping_geo_type) << ",\n"
+            << "  \"area_mapping_has_header\": " << area_mapping_has_header << ",\n"
+            << "  \"area_mapping_processed_file_prefix\": " << json_string(area_mapping_processed_file_prefix) << ",\n"
+            << "  \"area_mapping_rebuild_on_mismatch\": " << area_mapping_rebuild_on_mismatch << ",\n"
+            << "  \"area_mapping_verify_index\": " << area_mapping_verify_index << ",\n"
+            << "  \"area_mapping_compress_index\": " << area_mapping_compress_index << ",\n"
+            << "  \"area_mapping_grid_bbox\": " << json_array(area_mapping_grid_bbox) << ",\n"
+            << "  \"area_mapping_progress_interval\": " << area_mapping_progress_interval << ",\n"
+            << "  \"area_mapping_index_threads\": " << area_mapping_index_threads << ",\n"
+            << "  \"country_attributes\": " << json_string(country_attributes) << ",\n"
+            << "  \"country_attribute\": " << json_array(country_attribute_names) << ",\n"
+            << "  \"tag_country_confidence\": " << tag_country_confidence << ",\n"
+            << "  \"normalize_oneway_direction\": " << normalize_oneway_direction << ",\n"
+            << "  \"exclude_changeset\": " << json_array(exclude_changeset_ids) << ",\n"
+            << "  \"drop_invisible\": " << drop_invisible << ",\n"
+            << "  \"max_way_nodes\": " << max_way_nodes << ",\n"
+            << "  \"max_relation_members\": " << max_relation_members << ",\n"
+            << "  \"output_only_modified\": " << output_only_modified << ",\n"
+            << "  \"stats_only\": " << stats_only << ",\n"
+            << "  \"count_only\": " << count_only << ",\n"
+            << "  \"merge_duplicate_ways\": " << merge_duplicate_ways << ",\n"
+            << "  \"node_cache_file\": " << json_string(node_cache_file) << ",\n"
+            << "  \"annotate_utm_zone\": " << annotate_utm_zone << ",\n"
+            << "  \"remove_metadata_from\": " << json_array(remove_metadata_from) << ",\n"
+            << "  \"keep_metadata_field\": " << json_array(keep_metadata_field_names) << ",\n"
+            << "  \"elevation_csv\": " << json_string(elevation_csv) << ",\n"
+            << "  \"country_points_csv\": " << json_string(country_points_csv) << ",\n"
+            << "  \"no_ele_tag\": " << no_ele_tag << ",\n"
+            << "  \"preserve_original_ele_as\": " << json_string(preserve_original_ele_as) << ",\n"
+            << "  \"elevation_histogram\": " << elevation_histogram << ",\n"
+            << "  \"elevation_histogram_csv\": " << json_string(elevation_histogram_csv) << ",\n"
+            << "  \"elevation_histogram_bin\": " << elevation_histogram_bin << ",\n"
+            << "  \"normalize_multivalue_key\": " << json_array(normalize_multivalue_keys) << ",\n"
+            << "  \"normalize_units\": " << normalize_units << ",\n"
+            << "  \"normalize_units_key\": " << json_array(normalize_units_keys) << ",\n"
+            << "  \"lowercase_keys\": " << lowercase_keys << ",\n"
+            << "  \"lowercase_keys_key\": " << json_array(lowercase_keys_keys) << ",\n"
+            << "  \"lowercase_keys_exclude\": " << json_array(lowercase_keys_exclude) << ",\n"
+            << "  \"dedup_tags\": " << dedup_tags << ",\n"
+            << "  \"dedup_tags_keep\": " << json_string(dedup_tags_keep) << ",\n"
+            << "  \"report_stripped_elements\": " << report_stripped_elements << ",\n"
+            << "  \"output_raw\": " << output_raw << ",\n"
+            << "  \"output_source\": " << json_string(output_source) << ",\n"
+            << "  \"output_batch_size\": " << output_batch_size << ",\n"
+            << "  \"max_output_elements\": " << max_output_elements << ",\n"
+            << "  \"max_output_elements_per_type\": " << max_output_elements_per_type << ",\n"
+            << "  \"output_shards\": " << output_shards << ",\n"
+            << "  \"output_shard_chunk_size\": " << output_shard_chunk_size << ",\n"
+            << "  \"boundary_way_policy\": " << json_string(boundary_way_policy) << ",\n"
+            << "  \"min_node_spacing\": " << min_node_spacing << ",\n"
+            << "  \"annotate_highway_rank\": " << annotate_highway_rank << ",\n"
+            << "  \"highway_rank\": " << json_array(highway_rank_overrides) << ",\n"
+            << "  \"elevation_only_tagged\": " << elevation_only_tagged << ",\n"
+            << "  \"elevation_relation_labels\": " << elevation_relation_labels << ",\n"
+            << "  \"normalize_ele_units\": " << normalize_ele_units << ",\n"
+            << "  \"ele_default_unit\": " << json_string(ele_default_unit) << ",\n"
+            << "  \"prefetch\": " << prefetch << ",\n"
+            << "  \"sort_output\": " << sort_output << ",\n"
+            << "  \"output_order\": " << json_string(output_order) << ",\n"
+            << "  \"min_way_length\": " << min_way_length << ",\n"
+            << "  \"accept_ids_file\": " << json_string(accept_ids_file) << ",\n"
+            << "  \"filter_only\": " << filter_only << ",\n"
+            << "  \"export_accept_ids\": " << json_string(export_accept_ids) << ",\n"
+            << "  \"tag_elevation_resolution\": " << tag_elevation_resolution << ",\n"
+            << "  \"tag_elevation_status\": " << tag_elevation_status << ",\n"
+            << "  \"debug_elevation_raw\": " << debug_elevation_raw << ",\n"
+            << "  \"drop_relation_member_ways\": " << drop_relation_member_ways << ",\n"
+            << "  \"annotate_way_grade\": " << annotate_way_grade << ",\n"
+            << "  \"annotate_sinuosity\": " << annotate_sinuosity << ",\n"
+            << "  \"annotate_bearing\": " << annotate_bearing << ",\n"
+            << "  \"normalize_winding\": " << normalize_winding << ",\n"
+            << "  \"annotate_node_role\": " << annotate_node_role << ",\n"
+            << "  \"annotate_node_degree\": " << annotate_node_degree << ",\n"
+            << "  \"annotate_node_degree_max\": " << annotate_node_degree_max << ",\n"
+            << "  \"validate_refs\": " << validate_refs << ",\n"
+            << "  \"lift_node_tag\": " << json_array(lift_node_tags) << ",\n"
+            << "  \"connectivity_report\": " << connectivity_report << ",\n"
+            << "  \"tile_density_csv\": " << json_string(tile_density_csv) << ",\n"
+            << "  \"export_way_index\": " << json_string(export_way_index) << ",\n"
+            << "  \"report_tag_typos\": " << report_tag_typos << ",\n"
+            << "  \"relation_role_report\": " << relation_role_report << ",\n"
+            << "  \"warnings_file\": " << json_string(warnings_file) << ",\n"
+            << "  \"warnings_limit\": " << warnings_limit << ",\n"
+            << "  \"annotate_node_count\": " << annotate_node_count << ",\n"
+            << "  \"invalid_coordinate_mode\": " << json_string(invalid_coordinate_mode) << ",\n"
+            << "  \"annotate_tag_hash\": " << annotate_tag_hash << ",\n"
+            << "  \"filter_script\": " << json_string(filter_script) << ",\n"
+            << "  \"any_value_regex\": " << json_string(any_value_regex) << ",\n"
+            << "  \"any_value_mode\": " << json_string(any_value_mode) << ",\n"
+            << "  \"any_value_types\": " << json_string(any_value_types) << ",\n"
+            << "  \"require_keys\": " << json_array(require_keys) << ",\n"
+            << "  \"require_keys_types\": " << json_string(require_keys_types) << ",\n"
+            << "  \"debug_mode\": " << debug_mode << "\n"
+            << "}" << std::endl;
     }
 };
 
@@ -0,0 +1,79 @@
+#ifndef OSM_TRANSFORM_CONNECTIVITY_H
+#define OSM_TRANSFORM_CONNECTIVITY_H
+
+#include <algorithm>
+#include <cstddef>
+#include <span>
+#include <unordered_map>
+#include <vector>
+
+#include <osmium/osm/node_ref.hpp>
+#include <osmium/osm/types.hpp>
+
+// Connected components of the way network, ways are connected if they share a node. Union-find over the node ids,
+// needs ca. 50 bytes of memory per distinct way node.
+class Connectivity {
+    struct entry {
+        osmium::object_id_type parent;
+        // number of nodes, only valid for the root of a component
+        std::size_t size;
+    };
+    std::unordered_map<osmium::object_id_type, entry> nodes_;
+    // one node of each way to count the ways per component
+    std::vector<osmium::object_id_type> way_nodes_;
+
+    osmium::object_id_type find(osmium::object_id_type id) {
+        auto &e = nodes_.try_emplace(id, entry{id, 1}).first->second;
+        if (e.parent == id) {
+            return id;
+        }
+        // path compression
+        e.parent = find(e.parent);
+        return e.parent;
+    }
+
+    void unite(const osmium::object_id_type a, const osmium::object_id_type b) {
+        auto root_a = find(a);
+        auto root_b = find(b);
+        if (root_a == root_b) {
+            return;
+        }
+        if (nodes_.at(root_a).size < nodes_.at(root_b).size) {
+            std::swap(root_a, root_b);
+        }
+        nodes_.at(root_b).parent = root_a;
+        nodes_.at(root_a).size += nodes_.at(root_b).size;
+    }
+
+public:
+    void add_way(std::span<const osmium::NodeRef> refs) {
+        if (refs.empty()) {
+            return;
+        }
+        find(refs.front().ref());
+        way_nodes_.push_back(refs.front().ref());
+        for (std::size_t i = 1; i < refs.size(); i++) {
+            unite(refs[i - 1].ref(), refs[i].ref());
+        }
+    }
+
+    std::size_t components() const {
+        return std::count_if(nodes_.begin(), nodes_.end(), [](const auto &node) { return node.first == node.second.parent; });
+    }
+
+    // number of ways of the largest component
+    std::size_t largest() {
+        std::unordered_map<osmium::object_id_type, std::size_t> ways;
+        std::size_t largest = 0;
+        for (const auto node: way_nodes_) {
+            largest = std::max(largest, ++ways[find(node)]);
+        }
+        return largest;
+    }
+
+    std::size_t ways() const {
+        return way_nodes_.size();
+    }
+};
+
+#endif//OSM_TRANSFORM_CONNECTIVITY_H
@@ -0,0 +1,74 @@
+#ifndef OSM_TRANSFORM_COUNTRY_ATTRIBUTES_H
+#define OSM_TRANSFORM_COUNTRY_ATTRIBUTES_H
+
+#include <algorithm>
+#include <fstream>
+#include <stdexcept>
+#include <string>
+#include <unordered_map>
+#include <vector>
+
+#include <boost/tokenizer.hpp>
+
+// Attributes per country read from a csv file, separated by semicolons like the area mapping file. The header row
+// contains the attribute names, the first column the country ids as used in the area mapping, e.g.
+//   country;driving_side;maxspeed:unit
+//   DEU;right;kmh
+//   GBR;left;mph
+class CountryAttributes {
+    std::vector<std::string> names_;
+    std::unordered_map<std::string, std::vector<std::string>> values_;
+
+public:
+    void load(const std::string &path) {
+        std::ifstream in(path.c_str());
+        if (!in.is_open()) {
+            throw std::runtime_error("could not open country attributes file " + path);
+        }
+        typedef boost::tokenizer<boost::escaped_list_separator<char>, std::string::const_iterator, std::string> Tokenizer;
+        boost::escaped_list_separator<char> seps('\\', ';', '\"');
+        std::string line;
+        if (!getline(in, line)) {
+            throw std::runtime_error("country attributes file " + path + " is empty");
+        }
+        Tokenizer header(line, seps);
+        std::vector<std::string> columns(header.begin(), header.end());
+        if (columns.size() < 2) {
+            throw std::runtime_error("country attributes file " + path + " needs a header with the id column and at least one attribute: " + line);
+        }
+        names_.assign(std::next(columns.begin()), columns.end());
+        while (getline(in, line)) {
+            if (line.empty()) {
+                continue;
+            }
+            Tokenizer tok(line, seps);
+            std::vector<std::string> row(tok.begin(), tok.end());
+            if (row.size() != names_.size() + 1) {
+                throw std::runtime_error("country attributes file " + path + " contains row with incorrect number of columns: " + line);
+            }
+            values_[row[0]] = std::vector<std::string>(std::next(row.begin()), row.end());
+        }
+    }
+
+    const std::vector<std::string> &names() const {
+        return names_;
+    }
+
+    // index of the attribute in names(), -1 if it doesn't exist
+    int index(const std::string &name) const {
+        const auto it = std::find(names_.begin(), names_.end(), name);
+        return it == names_.end() ? -1 : static_cast<int>(it - names_.begin());
+    }
+
+    // empty if the country or its value is unknown
+    std::string get(const std::string &country, const int index) const {
+        const auto it = values_.find(country);
+        return it == values_.end() ? std::string() : it->second[index];
+    }
+
+    std::size_t size() const {
+        return values_.size();
+    }
+};
+
+#endif//OSM_TRANSFORM_COUNTRY_ATTRIBUTES_H
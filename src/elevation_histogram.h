@@ -0,0 +1,49 @@
+#ifndef OSM_TRANSFORM_ELEVATION_HISTOGRAM_H
+#define OSM_TRANSFORM_ELEVATION_HISTOGRAM_H
+
+#include <cmath>
+#include <map>
+#include <ostream>
+
+// Number of nodes per elevation bin, e.g. to spot nodata values leaking into the output as a spike at 0 or at large
+// negative values. Bins include their lower bound.
+class ElevationHistogram {
+    double bin_size_;
+    // keyed by the index of the bin, the lower bound divided by the bin size
+    std::map<long long, unsigned long long> bins_;
+    unsigned long long count_ = 0;
+
+public:
+    explicit ElevationHistogram(const double bin_size) : bin_size_(bin_size) {
+    }
+
+    void add(const double ele) {
+        bins_[static_cast<long long>(std::floor(ele / bin_size_))]++;
+        count_++;
+    }
+
+    unsigned long long count() const {
+        return count_;
+    }
+
+    std::size_t size() const {
+        return bins_.size();
+    }
+
+    // one line per bin with nodes, "  [lower, upper): count"
+    void print(std::ostream &out) const {
+        for (const auto &[bin, count]: bins_) {
+            out << "  [" << static_cast<double>(bin) * bin_size_ << ", " << static_cast<double>(bin + 1) * bin_size_ << "): " << count << "\n";
+        }
+    }
+
+    // writes lower,upper,node_count for all bins with nodes
+    void write_csv(std::ostream &out) const {
+        out << "lower,upper,node_count\n";
+        for (const auto &[bin, count]: bins_) {
+            out << static_cast<double>(bin) * bin_size_ << "," << static_cast<double>(bin + 1) * bin_size_ << "," << count << "\n";
+        }
+    }
+};
+
+#endif//OSM_TRANSFORM_ELEVATION_HISTOGRAM_H
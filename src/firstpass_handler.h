@@ -1,8 +1,16 @@
 #ifndef FIRSTPASSHANDLER_H
 #define FIRSTPASSHANDLER_H
 
+#include <algorithm>
+#include <cstring>
+#include <functional>
 #include <ostream>
 #include <set>
+#include <unordered_map>
+#include <unordered_set>
+#include <vector>
+
+#include <boost/functional/hash.hpp>
 
 #include <boost/regex.hpp>
 
@@ -15,6 +23,11 @@
 #include <osmium/osm/way.hpp>
 #include <osmium/osm/relation.hpp>
 
+#include "node_degrees.h"
+#include "warnings.h"
+
+typedef std::unordered_map<osmium::object_id_type, std::vector<std::pair<std::string, std::string>>> way_tags_map;
+
 class FirstPassHandler : public osmium::handler::Handler {
     friend std::ostream &operator<<(std::ostream &out, const FirstPassHandler &handler);
 private:
@@ -27,6 +40,45 @@ private:
     unsigned long long node_count_ = 0;
     unsigned long long relation_count_ = 0;
     unsigned long long way_count_ = 0;
+    unsigned long long ways_excluded_by_changeset_ = 0;
+    unsigned long long relations_excluded_by_changeset_ = 0;
+    unsigned long long invisible_ways_ = 0;
+    unsigned long long invisible_relations_ = 0;
+    unsigned long long ways_rejected_by_filter_ = 0;
+    unsigned long long relations_rejected_by_filter_ = 0;
+
+    // node sequences of the accepted ways, compared in full on a hash hit
+    std::unordered_map<std::vector<osmium::object_id_type>, osmium::object_id_type, boost::hash<std::vector<osmium::object_id_type>>> way_ref_sequences_;
+    unsigned long long invalid_refs_ = 0;
+    unsigned long long ways_with_invalid_refs_ = 0;
+    unsigned long long ways_dropped_by_invalid_refs_ = 0;
+
+    // accepted ways without routing tags, which are dropped if they turn out to be members of an accepted relation
+    std::unordered_set<osmium::object_id_type> ways_without_routing_tags_;
+
+    // returns true if the way has the same node sequence as a previously accepted way, whose id is recorded
+    // together with the tags of the duplicate in merged_way_tags_
+    bool merge_duplicate_way(const osmium::Way &way) {
+        std::vector<osmium::object_id_type> refs;
+        refs.reserve(way.nodes().size());
+        for (const auto &n: way.nodes()) {
+            refs.push_back(n.ref());
+        }
+        const auto [it, inserted] = way_ref_sequences_.emplace(std::move(refs), way.id());
+        if (inserted) {
+            return false;
+        }
+        auto &tags = merged_way_tags_[it->second];
+        for (const auto &tag: way.tags()) {
+            tags.emplace_back(tag.key(), tag.value());
+        }
+        merged_ways_++;
+        return true;
+    }
+
+    inline bool is_excluded_changeset(const osmium::OSMObject &object) const {
+        return !exclude_changesets_.empty() && exclude_changesets_.contains(object.changeset());
+    }
 
     static bool tag_validates(const osmium::Tag &tag) {
         const std::string key = tag.key();
@@ -44,6 +96,30 @@ private:
         return !boost::regex_match(tag.key(), remove_tags_);
     }
 
+    bool has_routing_tags(const osmium::TagList &tags) const {
+        return std::any_of(tags.begin(), tags.end(), [this](const osmium::Tag &tag) { return accept_tag(tag) && tag_validates(tag); });
+    }
+
+    bool is_valid_ref(const osmium::object_id_type ref) const {
+        return !validate_refs_ || (ref > 0 && (generated_id_base_ == 0 || ref < generated_id_base_));
+    }
+
+    // counts the invalid refs of the way, false if less than two valid refs remain
+    bool check_refs(const osmium::Way &way) {
+        const auto invalid = std::count_if(way.nodes().begin(), way.nodes().end(), [this](const osmium::NodeRef &n) { return !is_valid_ref(n.ref()); });
+        if (invalid == 0) {
+            return true;
+        }
+        invalid_refs_ += invalid;
+        ways_with_invalid_refs_++;
+        WarningLog::instance().record("invalid_ref", std::to_string(invalid) + " invalid node refs", osmium::item_type::way, way.id());
+        if (way.nodes().size() - invalid < 2) {
+            ways_dropped_by_invalid_refs_++;
+            return false;
+        }
+        return true;
+    }
+
     bool has_no_relevant_tags(const osmium::TagList &tags) const {
         bool no_tags_remain = true;
         bool has_invalidating_tags = false;
@@ -84,6 +160,30 @@ public:
     osmium::nwr_array<osmium::index::IdSetDense<osmium::unsigned_object_id_type>> &valid_ids_;
     osmium::nwr_array<osmium::index::IdSetSmall<osmium::unsigned_object_id_type>> &no_elevation_;
     unsigned long long node_max_id_ = 0;
+    std::unordered_set<osmium::changeset_id_type> exclude_changesets_;
+    bool merge_duplicate_ways_ = false;
+    bool drop_invisible_ = false;
+    bool drop_relation_member_ways_ = false;
+    // drop relations with more members (0 = no limit)
+    std::size_t max_relation_members_ = 0;
+    unsigned long long relations_dropped_by_member_count_ = 0;
+    unsigned long long relation_member_ways_dropped_ = 0;
+    std::function<bool(const osmium::OSMObject &)> element_filter_;
+    way_tags_map merged_way_tags_;
+    unsigned long long merged_ways_ = 0;
+    // way endpoints, nodes shared by several ways and relation member nodes, which must not be dropped by min_node_spacing
+    osmium::index::IdSetDense<osmium::unsigned_object_id_type> *protected_nodes_ = nullptr;
+    // nodes referenced by accepted ways and nodes referenced more than once by them, only collected if both are set
+    osmium::index::IdSetDense<osmium::unsigned_object_id_type> *way_nodes_ = nullptr;
+    // drop node refs which are not positive or collide with the ids of generated nodes
+    bool validate_refs_ = false;
+    // refs from here on collide with the nodes generated by interpolation, 0 = no upper bound without interpolation
+    osmium::object_id_type generated_id_base_ = 0;
+    osmium::index::IdSetDense<osmium::unsigned_object_id_type> *junction_nodes_ = nullptr;
+    // number of accepted ways referencing each node, only collected if set
+    NodeDegrees *node_degrees_ = nullptr;
+    // label and admin_centre member nodes of accepted relations, their representative points, only collected if set
+    osmium::index::IdSetDense<osmium::unsigned_object_id_type> *label_nodes_ = nullptr;
 
     explicit FirstPassHandler(
         boost::regex &remove_tags,
@@ -105,35 +205,139 @@ public:
     void way(const osmium::Way &way) {
         if (way.id() < 0) return;
         way_count_++;
+        if (drop_invisible_ && !way.visible()) {
+            invisible_ways_++;
+            return;
+        }
+        if (is_excluded_changeset(way)) {
+            ways_excluded_by_changeset_++;
+            return;
+        }
         if (is_removable(way)) { return; }
+        if (element_filter_ && !element_filter_(way)) {
+            ways_rejected_by_filter_++;
+            return;
+        }
+        if (validate_refs_ && !check_refs(way)) { return; }
+        // after the other checks, so a rejected way doesn't make its duplicates be dropped
+        if (merge_duplicate_ways_ && merge_duplicate_way(way)) { return; }
+        const auto is_valid = [this](const osmium::NodeRef &n) { return is_valid_ref(n.ref()); };
+        if (protected_nodes_ != nullptr) {
+            // the endpoints after invalid refs are removed in the second pass
+            if (const auto first = std::find_if(way.nodes().begin(), way.nodes().end(), is_valid); first != way.nodes().end()) {
+                protected_nodes_->set(first->ref());
+            }
+            if (const auto last = std::find_if(way.nodes().crbegin(), way.nodes().crend(), is_valid); last != way.nodes().crend()) {
+                protected_nodes_->set(last->ref());
+            }
+        }
         for (const osmium::NodeRef &n: way.nodes()) {
+            if (!is_valid(n)) continue;
+            if (protected_nodes_ != nullptr && valid_ids_.nodes().get(n.ref())) {
+                protected_nodes_->set(n.ref());
+            }
             valid_ids_.nodes().set(n.ref());
         }
+        if (way_nodes_ != nullptr && junction_nodes_ != nullptr && !way.nodes().empty()) {
+            // the last node of a closed way is the first one again and doesn't make it a junction
+            const auto end = way.is_closed() ? way.nodes().end() - 1 : way.nodes().end();
+            for (auto n = way.nodes().begin(); n != end; ++n) {
+                if (!is_valid(*n) || way_nodes_->check_and_set(n->ref())) {
+                    continue;
+                }
+                junction_nodes_->set(n->ref());
+            }
+        }
+        if (node_degrees_ != nullptr) {
+            std::vector<osmium::unsigned_object_id_type> nodes;
+            for (const auto &n: way.nodes()) {
+                if (is_valid(n)) nodes.push_back(n.ref());
+            }
+            node_degrees_->add_way(std::move(nodes));
+        }
         if (is_no_elevation(way)) {
             for (const auto &n: way.nodes()) {
+                if (!is_valid(n)) continue;
                 no_elevation_.nodes().set(n.ref());
             }
             no_elevation_.ways().set(way.id());
         }
         valid_ids_.ways().set(way.id());
+        if (drop_relation_member_ways_ && !has_routing_tags(way.tags())) {
+            ways_without_routing_tags_.insert(way.id());
+        }
     }
 
     void relation(const osmium::Relation &rel) {
         if (rel.id() < 0) return;
         relation_count_++;
+        if (drop_invisible_ && !rel.visible()) {
+            invisible_relations_++;
+            return;
+        }
+        if (is_excluded_changeset(rel)) {
+            relations_excluded_by_changeset_++;
+            return;
+        }
         if (is_removable(rel)) { return; }
+        if (element_filter_ && !element_filter_(rel)) {
+            relations_rejected_by_filter_++;
+            return;
+        }
+        if (max_relation_members_ > 0 && rel.members().size() > max_relation_members_) {
+            relations_dropped_by_member_count_++;
+            return;
+        }
         for (const auto &member: rel.members()) {
             if (member.type() == osmium::item_type::node) {
                 valid_ids_.nodes().set(member.ref());
+                if (protected_nodes_ != nullptr) {
+                    protected_nodes_->set(member.ref());
+                }
+                if (label_nodes_ != nullptr && (std::strcmp(member.role(), "label") == 0 || std::strcmp(member.role(), "admin_centre") == 0)) {
+                    label_nodes_->set(member.ref());
+                }
+            } else if (member.type() == osmium::item_type::way && ways_without_routing_tags_.erase(member.ref()) > 0) {
+                valid_ids_.ways().unset(member.ref());
+                relation_member_ways_dropped_++;
             }
         }
         valid_ids_.relations().set(rel.id());
     }
     void printStats() {
-        std::cout << "valid nodes: " << valid_ids_.nodes().size() << " (" << node_count_ << "), "
-            << "valid ways: " << valid_ids_.ways().size() << " (" << way_count_ << "), "
-            << "valid relations: " << valid_ids_.relations().size() << " (" << relation_count_ << ")"
+        std::cout << "Pass 1: read " << way_count_ << " ways, accepted " << valid_ids_.ways().size() << "; "
+            << "read " << relation_count_ << " relations, accepted " << valid_ids_.relations().size() << "; "
+            << "referenced nodes: " << valid_ids_.nodes().size()
             << std::endl;
+        if (drop_invisible_) {
+            std::cout << "invisible ways dropped: " << invisible_ways_ << ", "
+                << "invisible relations dropped: " << invisible_relations_
+                << std::endl;
+        }
+        if (element_filter_) {
+            std::cout << "ways rejected by filter: " << ways_rejected_by_filter_ << ", "
+                << "relations rejected by filter: " << relations_rejected_by_filter_
+                << std::endl;
+        }
+        if (validate_refs_) {
+            std::cout << "invalid node refs: " << invalid_refs_ << " in " << ways_with_invalid_refs_ << " ways, "
+                << "ways dropped with less than two valid refs: " << ways_dropped_by_invalid_refs_
+                << std::endl;
+        }
+        if (max_relation_members_ > 0) {
+            std::cout << "relations dropped by member count: " << relations_dropped_by_member_count_ << std::endl;
+        }
+        if (drop_relation_member_ways_) {
+            std::cout << "relation member ways without routing tags dropped: " << relation_member_ways_dropped_ << std::endl;
+        }
+        if (merge_duplicate_ways_) {
+            std::cout << "duplicate ways merged: " << merged_ways_ << std::endl;
+        }
+        if (!exclude_changesets_.empty()) {
+            std::cout << "ways excluded by changeset: " << ways_excluded_by_changeset_ << ", "
+                << "relations excluded by changeset: " << relations_excluded_by_changeset_
+                << std::endl;
+        }
     };
 };
 
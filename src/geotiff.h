@@ -1,8 +1,10 @@
 #ifndef GEOTIFF_H
 #define GEOTIFF_H
 
+#include <algorithm>
 #include <gdal_priv.h>
 #include <iostream>
+#include <unordered_map>
 #include <osmium/util/file.hpp>
 #include <osmium/util/progress_bar.hpp>
 
@@ -19,13 +21,20 @@ static auto WGS84 = getWGS84Reference();
 
 class Geotiff {
     GDALDatasetUniquePtr dataset_;
-    OGRCoordinateTransformation *transformation_;
+    OGRCoordinateTransformation *transformation_ = nullptr;
     double transform_[6] = {};
+    // maps georeferenced coordinates to pixel coordinates, handles rasters stored south-up (positive pixel height) or
+    // rotated as well as the usual north-up ones
+    double inverse_transform_[6] = {};
     int raster_has_no_data_ = 0;
     double raster_no_data_value_ = 0.0;
     bool debug_mode_ = false;
+    // values of recently read pixels, keyed by pixel coordinate. Cleared when the limit is reached.
+    mutable std::unordered_map<std::uint64_t, double> pixel_cache_;
+    std::size_t pixel_cache_limit_ = 0;
 
 public:
+    mutable unsigned long long pixel_cache_hits_ = 0;
 
     static auto getSpatialReference(const char *crs) {
         OGRSpatialReference reference;
@@ -34,12 +43,19 @@ public:
         return reference;
     }
 
-    explicit Geotiff(const char *filename, bool debug_mode) {
+    explicit Geotiff(const char *filename, bool debug_mode, std::size_t pixel_cache_limit = 0) : pixel_cache_limit_(pixel_cache_limit) {
         dataset_ = GDALDatasetUniquePtr(GDALDataset::FromHandle(GDALOpenShared(filename, GA_ReadOnly)));
         if (dataset_ == nullptr) return;
         const auto reference = getSpatialReference(dataset_->GetProjectionRef());
         transformation_ = OGRCreateCoordinateTransformation(&WGS84, &reference);
         dataset_->GetGeoTransform(transform_);
+        if (!GDALInvGeoTransform(transform_, inverse_transform_)) {
+            if (debug_mode) {
+                std::cout << "Geotransform of " << filename << " can't be inverted\n";
+            }
+            dataset_.reset();
+            return;
+        }
         raster_no_data_value_ = dataset_->GetRasterBand(1)->GetNoDataValue(&raster_has_no_data_);
         debug_mode_ = debug_mode;
     }
@@ -49,28 +65,74 @@ public:
     }
 
     double elevation(double lng, double lat) const {
+        int x, y;
+        if (!pixel(lng, lat, x, y)) {
+            return kNoDataValue;
+        }
+        return value(x, y);
+    }
+
+    // pixel coordinate of a WGS84 location, false if it is outside of the raster
+    bool pixel(double lng, double lat, int &x, int &y) const {
         transformation_->Transform(1, &lng, &lat);
-        auto x = static_cast<int>(floor((lng - transform_[0]) / transform_[1]));
-        auto y = static_cast<int>(floor((lat - transform_[3]) / transform_[5]));
+        double px, py;
+        GDALApplyGeoTransform(inverse_transform_, lng, lat, &px, &py);
+        x = static_cast<int>(floor(px));
+        y = static_cast<int>(floor(py));
         const auto max_x = dataset_->GetRasterXSize();
         const auto max_y = dataset_->GetRasterYSize();
         if (x < -1 || y < -1 || x > max_x || y > max_y) {
             if (debug_mode_) {
                 std::cout << "Coordinate out of bounds: Image coordinates (" << x << ", " << y << ") POINT (" << lat << " " << lng << ")\n";
             }
-            return kNoDataValue;
+            return false;
         }
 
         // for some coordinates close to the borders of the tile space the transformation_ returns invalid coordinates,
         // because the tiles of the dataset are not cut along full degree lines.
         x = std::max(std::min(x, dataset_->GetRasterXSize() - 1), 0);
         y = std::max(std::min(y, dataset_->GetRasterYSize() - 1), 0);
+        return true;
+    }
+
+    // distance of a WGS84 location to the nearest edge of the raster in pixels, negative if it is outside
+    double edge_distance(double lng, double lat) const {
+        transformation_->Transform(1, &lng, &lat);
+        double px, py;
+        GDALApplyGeoTransform(inverse_transform_, lng, lat, &px, &py);
+        const double width = dataset_->GetRasterXSize();
+        const double height = dataset_->GetRasterYSize();
+        return std::min({px, width - px, py, height - py});
+    }
+
+    // value of the pixel, kNoDataValue if it can't be read or is the nodata value of the raster
+    double value(const int x, const int y) const {
+        const auto key = static_cast<std::uint64_t>(x) << 32 | static_cast<std::uint32_t>(y);
+        if (pixel_cache_limit_ > 0) {
+            if (const auto it = pixel_cache_.find(key); it != pixel_cache_.end()) {
+                pixel_cache_hits_++;
+                return it->second;
+            }
+        }
         double pixel[2];
+        double ele;
         if (dataset_->GetRasterBand(1)->RasterIO(GF_Read, x, y, 1, 1, pixel, 1, 1, GDT_CFloat64, 0, 0) != CE_None ||
-            (raster_has_no_data_ && pixel[0] <= raster_no_data_value_)) { return kNoDataValue; }
-        return pixel[0];
+            (raster_has_no_data_ && pixel[0] <= raster_no_data_value_)) {
+            ele = kNoDataValue;
+        } else {
+            ele = pixel[0];
+        }
+        if (pixel_cache_limit_ > 0) {
+            if (pixel_cache_.size() >= pixel_cache_limit_) {
+                pixel_cache_.clear();
+            }
+            pixel_cache_[key] = ele;
+        }
+        return ele;
     }
 
+    bool is_valid() const { return dataset_ != nullptr && transformation_ != nullptr; }
+
     auto GetDescription() const { return dataset_->GetDriver()->GetDescription(); }
 
     auto GetRasterXSize() const { return dataset_->GetRasterXSize(); };
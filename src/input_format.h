@@ -0,0 +1,83 @@
+#ifndef OSM_TRANSFORM_INPUT_FORMAT_H
+#define OSM_TRANSFORM_INPUT_FORMAT_H
+
+#include <array>
+#include <cctype>
+#include <fstream>
+#include <string>
+#include <string_view>
+
+#include <osmium/io/file.hpp>
+#include <zlib.h>
+
+// Format of an input file determined from its first bytes, so that renamed files or files without extension are read
+// correctly. The formats are named like the format strings of osmium::io::File, e.g. "pbf", "osm.gz" or "opl".
+class InputFormat {
+public:
+    // xml or opl, empty if the text is neither
+    static std::string text_format(std::string_view bytes) {
+        if (bytes.starts_with("\xef\xbb\xbf")) {
+            bytes.remove_prefix(3);
+        }
+        while (!bytes.empty() && std::isspace(static_cast<unsigned char>(bytes.front()))) {
+            bytes.remove_prefix(1);
+        }
+        if (bytes.starts_with('<')) {
+            return "osm";
+        }
+        // opl lines start with the object type and id, e.g. n123 or w-1
+        if (bytes.size() > 1 && (bytes[0] == 'n' || bytes[0] == 'w' || bytes[0] == 'r' || bytes[0] == 'c') &&
+            (std::isdigit(static_cast<unsigned char>(bytes[1])) || bytes[1] == '-')) {
+            return "opl";
+        }
+        return {};
+    }
+
+    // format of the file, empty if it can't be read or is not recognized
+    static std::string detect(const std::string &filename) {
+        std::array<char, 64> head{};
+        std::ifstream in(filename, std::ios::binary);
+        in.read(head.data(), head.size());
+        const std::string_view bytes(head.data(), in.gcount());
+        // a pbf file starts with the 4 byte size and the type field of the first blob header
+        if (bytes.size() >= 15 && bytes[4] == '\x0a' && bytes[5] == '\x09' && bytes.substr(6, 9) == "OSMHeader") {
+            return "pbf";
+        }
+        if (bytes.starts_with("\xff\xe0")) {
+            return "o5m";
+        }
+        // bzip2 is only commonly used for xml, e.g. the planet files
+        if (bytes.starts_with("BZh")) {
+            return "osm.bz2";
+        }
+        if (bytes.starts_with("\x1f\x8b")) {
+            gzFile gz = gzopen(filename.c_str(), "rb");
+            if (gz == nullptr) {
+                return {};
+            }
+            std::array<char, 64> decompressed{};
+            const auto read = gzread(gz, decompressed.data(), decompressed.size());
+            gzclose(gz);
+            const auto format = read > 0 ? text_format({decompressed.data(), static_cast<std::size_t>(read)}) : std::string();
+            return format.empty() ? format : format + ".gz";
+        }
+        return text_format(bytes);
+    }
+
+    // the file with the detected format if it differs from the one given by the extension, otherwise as it is, which
+    // keeps settings only given by the extension, e.g. the multiple object versions of .osh.pbf files
+    static osmium::io::File file(const std::string &filename) {
+        osmium::io::File by_extension{filename};
+        const auto format = detect(filename);
+        if (format.empty()) {
+            return by_extension;
+        }
+        osmium::io::File detected{filename, format};
+        if (detected.format() == by_extension.format() && detected.compression() == by_extension.compression()) {
+            return by_extension;
+        }
+        return detected;
+    }
+};
+
+#endif//OSM_TRANSFORM_INPUT_FORMAT_H
@@ -0,0 +1,45 @@
+#ifndef OSM_TRANSFORM_JSON_H
+#define OSM_TRANSFORM_JSON_H
+
+#include <cstdio>
+#include <string>
+#include <type_traits>
+
+// the value as quoted json string with the quotes, backslashes and control characters escaped
+inline std::string json_string(const std::string &value) {
+    std::string result = "\"";
+    for (const char c: value) {
+        switch (c) {
+            case '"': result += "\\\""; break;
+            case '\\': result += "\\\\"; break;
+            case '\n': result += "\\n"; break;
+            case '\t': result += "\\t"; break;
+            default:
+                if (static_cast<unsigned char>(c) < 0x20) {
+                    char escaped[7];
+                    snprintf(escaped, sizeof(escaped), "\\u%04x", c);
+                    result += escaped;
+                } else {
+                    result += c;
+                }
+        }
+    }
+    return result + "\"";
+}
+
+// json array of strings or numbers
+template<typename Container>
+std::string json_array(const Container &values) {
+    std::string result = "[";
+    for (const auto &value: values) {
+        if (result.size() > 1) result += ", ";
+        if constexpr (std::is_convertible_v<decltype(value), std::string>) {
+            result += json_string(value);
+        } else {
+            result += std::to_string(value);
+        }
+    }
+    return result + "]";
+}
+
+#endif//OSM_TRANSFORM_JSON_H
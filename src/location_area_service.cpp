@@ -1,14 +1,26 @@
 #include "location_area_service.h"
+#include "warnings.h"
 
+#include <boost/algorithm/string/join.hpp>
 #include <boost/tokenizer.hpp>
+#include <algorithm>
+#include <chrono>
+#include <cmath>
+#include <exception>
 #include <filesystem>
 #include <fstream>
 #include <gdal_priv.h>
 #include <iostream>
+#include <memory>
+#include <numeric>
+#include <sstream>
+#include <thread>
+#include <zlib.h>
 
 namespace fs = std::filesystem;
 
 const std::string LocationAreaService::delim_str_ = ";";
+const boost::escaped_list_separator<char> LocationAreaService::seps_('\\', ';', '\"');
 
 inline bool geo_col_check(std::string &data, std::string &geo_type) {
     if (geo_type == "wkt") {
@@ -32,124 +44,318 @@ inline std::vector<std::string> split_str(std::string &line, const std::string &
     return tokens;
 }
 
-void LocationAreaService::load(const std::string &path) {
+void LocationAreaService::load(const std::vector<std::string> &paths) {
     std::cout << "Load area mapping..." << std::endl;
+    const auto sources = boost::algorithm::join(paths, ",");
 
-    auto area_file_path = processed_file_prefix_ + "area.csv";
-    auto index_file_path = processed_file_prefix_ + "index.csv";
-    auto id_file_path = processed_file_prefix_ + "id.csv";
-
-    if (std::filesystem::exists(area_file_path) && std::filesystem::exists(id_file_path) && std::filesystem::exists(index_file_path)) {
-        std::string l;
-        std::ifstream area_file(area_file_path.c_str());
-        if (area_file.is_open()) {
-            while (getline(area_file, l)) {
-                auto row = split_str(l, delim_str_);
-                OGRGeometry *poGeom;
-                OGRErr eErr = OGRERR_NONE;
-                eErr = OGRGeometryFactory::createFromWkt(row[2].c_str(), nullptr, &poGeom);
-                if (eErr != OGRERR_NONE) {
-                    std::cout << "WARNING: processed area mapping file is corrupted!" << std::endl;
-                    continue;
-                }
-                mapping_area_.insert({std::stoi(row[0]), AreaIntersect{static_cast<area_id_t>(std::stoi(row[1])), poGeom}});
-            }
-            area_file.close();
-        }
-        std::ifstream index_file(index_file_path.c_str());
-        if (index_file.is_open()) {
-            while (getline(index_file, l)) {
-                auto row = split_str(l, delim_str_);
-                mapping_index_[std::stoi(row[0])] = std::stoi(row[1]);
-            }
-            index_file.close();
-        }
-        std::ifstream id_file(id_file_path.c_str());
-        if (id_file.is_open()) {
-            while (getline(id_file, l)) {
-                auto row = split_str(l, delim_str_);
-                mapping_id_[std::stoi(row[0])] = row[1];
-            }
-            id_file.close();
+    std::map<std::string, std::string> stored_info;
+    const bool info_exists = read_processed_info(stored_info);
+    // existing files are read with the compression they were written with
+    files_compressed_ = info_exists ? stored_info["compression"] == "gzip" : compress_;
+
+    bool processed_files_exist = std::filesystem::exists(processed_file_path("area")) && std::filesystem::exists(processed_file_path("id")) && std::filesystem::exists(processed_file_path("index"));
+    if (processed_files_exist && !processed_info_matches(paths, info_exists, stored_info)) {
+        if (rebuild_on_mismatch_) {
+            std::cout << "Processed area mapping files do not match the current settings, rebuilding from " << sources << std::endl;
+            processed_files_exist = false;
+        } else {
+            Warning("area_mapping") << "Processed area mapping files do not match the current settings! "
+                         "They are loaded anyway, use --area_mapping_rebuild_on_mismatch to rebuild them from " << sources << ".";
         }
+    }
+
+    if (processed_files_exist) {
+        read_processed_files(mapping_area_, mapping_index_, mapping_id_);
         std::cout << "Successfully loaded from previously processed area mappings." << std::endl;
         output_mapping();
         initialized_ = true;
         return;
     }
 
-    std::ifstream in(path.c_str());
-    if (!in.is_open()) {
-        std::cout << "Failed to open area mapping file!" << std::endl;
+    if (!schemas_match(paths)) {
         return;
     }
 
-    typedef boost::tokenizer<boost::escaped_list_separator<char>, std::string::const_iterator, std::string> Tokenizer;
-    boost::escaped_list_separator<char> seps('\\', ';', '\"');
     std::vector<std::string> row;
     std::string line;
 
-    area_id_t index = 0;
+    // area ids are continuous across all files, 0 is no area
+    area_id_t index = 1;
     area_id_t valid_rows = 0;
-    if (!file_has_header_) {
-        index++;
-    }
-    while (getline(in, line)) {
-        Tokenizer tok(line, seps);
-        row.assign(tok.begin(), tok.end());
-        if (row.size() > std::max(id_col_, geo_col_)) {
-            if (geo_col_check(row[geo_col_], geo_type_)) {
-                if (index == 0) {
-                    std::cout << "WARNING: CSV seems to contain data in the first row though area_mapping_has_header is set to true!" << std::endl;
+    std::uint64_t intersecting_grid_tiles = 0;
+    const auto start = std::chrono::steady_clock::now();
+    for (const auto &path: paths) {
+        std::ifstream in(path.c_str());
+        if (!in.is_open()) {
+            std::cout << "Failed to open area mapping file " << path << "!" << std::endl;
+            return;
+        }
+        std::uint64_t row_number = 0;
+        while (getline(in, line)) {
+            row_number++;
+            Tokenizer tok(line, seps_);
+            row.assign(tok.begin(), tok.end());
+            if (row.size() > std::max(id_col_, geo_col_)) {
+                if (geo_col_check(row[geo_col_], geo_type_)) {
+                    if (row_number == 1 && file_has_header_) {
+                        Warning("area_mapping_csv") << "CSV " << path << " seems to contain data in the first row though area_mapping_has_header is set to true!";
+                    }
+                    valid_rows++;
+                    mapping_id_[index] = row[id_col_];
+                    intersecting_grid_tiles += add_area_to_mapping_index(index, row[geo_col_]);
+                    if (progress_interval_ > 0 && valid_rows % progress_interval_ == 0) {
+                        const auto elapsed = std::chrono::duration_cast<std::chrono::seconds>(std::chrono::steady_clock::now() - start).count();
+                        std::cout << "Areas processed: " << valid_rows << " (" << path << " row " << row_number << "), intersecting grid tiles: " << intersecting_grid_tiles << ", elapsed: " << elapsed << " s" << std::endl;
+                    }
                     index++;
+                } else {
+                    if (row_number > 1 || !file_has_header_) {
+                        Warning("area_mapping_csv") << "CSV " << path << " contains row with invalid value in geometry column! Row number: " << row_number << "!";
+                    }
                 }
-                valid_rows++;
-                mapping_id_[index] = row[id_col_];
-                add_area_to_mapping_index(index, row[geo_col_]);
             } else {
-                if (index > 0) {
-                    std::cout << "WARNING: CSV contains row with invalid value in geometry column! Row number: " << index + 1 << "!" << std::endl;
-                }
+                Warning("area_mapping_csv") << "CSV " << path << " contains row with incorrect number of columns!";
             }
-        } else {
-            std::cout << "WARNING: CSV contains row with incorrect number of columns!" << std::endl;
         }
-        index++;
     }
 
     std::cout << "Save processed area mapping" << std::endl;
-    std::ofstream o_area_file(area_file_path);
-    if (o_area_file.is_open()) {
-        for (const auto &[k, a]: mapping_area_) {
-            o_area_file << k << delim_str_ << a.id << delim_str_ << a.geo->exportToWkt(OGRWktOptions(), nullptr) << std::endl;
-        }
-        o_area_file.close();
+    files_compressed_ = compress_;
+    std::ostringstream o_area;
+    for (const auto &[k, a]: mapping_area_) {
+        o_area << k << delim_str_ << a.id << delim_str_ << a.geo->exportToWkt(OGRWktOptions(), nullptr) << "\n";
     }
-    std::ofstream o_id_file(id_file_path);
-    if (o_id_file.is_open()) {
-        for (const auto &[k, a]: mapping_id_) {
-            o_id_file << k << delim_str_ << a << std::endl;
+    write_processed_file(processed_file_path("area"), o_area.str());
+    std::ostringstream o_id;
+    for (const auto &[k, a]: mapping_id_) {
+        o_id << k << delim_str_ << a << "\n";
+    }
+    write_processed_file(processed_file_path("id"), o_id.str());
+    std::ostringstream o_index;
+    for (auto k = 0; auto a: mapping_index_) {
+        if (a != 0) {
+            o_index << k << delim_str_ << a << "\n";
         }
-        o_id_file.close();
+        k++;
     }
-    std::ofstream o_index_file(index_file_path);
-    if (o_index_file.is_open()) {
-        for (auto k = 0; auto a: mapping_index_) {
-            if (a != 0) {
-                o_index_file << k << delim_str_ << a << std::endl;
-            }
-            k++;
+    write_processed_file(processed_file_path("index"), o_index.str());
+
+    std::ofstream o_info_file(processed_file_prefix_ + "info.csv");
+    if (o_info_file.is_open()) {
+        for (const auto &[k, v]: processed_info(paths)) {
+            o_info_file << k << delim_str_ << v << std::endl;
         }
-        o_index_file.close();
+        o_info_file << "compression" << delim_str_ << (compress_ ? "gzip" : "none") << std::endl;
+        o_info_file.close();
+    }
+
+    if (verify_index_) {
+        verify_processed_files();
     }
 
     output_mapping();
     if (valid_rows > 0) {
-        std::cout << "Areas indexed: " << valid_rows << std::endl;
+        std::cout << "Areas indexed: " << valid_rows << ", intersecting grid tiles: " << intersecting_grid_tiles << std::endl;
         initialized_ = true;
     }
 }
 
+void LocationAreaService::read_processed_files(std::multimap<grid_id_t, AreaIntersect> &mapping_area, std::vector<area_id_t> &mapping_index,
+                                               std::unordered_map<area_id_t, std::string> &mapping_id) {
+    std::string l;
+    std::string content;
+    if (read_processed_file(processed_file_path("area"), content)) {
+        std::istringstream area_file(content);
+        while (getline(area_file, l)) {
+            auto row = split_str(l, delim_str_);
+            OGRGeometry *poGeom;
+            OGRErr eErr = OGRERR_NONE;
+            eErr = OGRGeometryFactory::createFromWkt(row[2].c_str(), nullptr, &poGeom);
+            if (eErr != OGRERR_NONE) {
+                Warning("area_mapping") << "processed area mapping file is corrupted!";
+                continue;
+            }
+            mapping_area.insert({std::stoi(row[0]), AreaIntersect{static_cast<area_id_t>(std::stoi(row[1])), poGeom}});
+        }
+    }
+    if (read_processed_file(processed_file_path("index"), content)) {
+        std::istringstream index_file(content);
+        while (getline(index_file, l)) {
+            auto row = split_str(l, delim_str_);
+            const auto grid_index = std::stoi(row[0]);
+            if (grid_index < 0 || grid_index >= grid_size_) {
+                Warning("area_mapping") << "processed area mapping index is out of grid range!";
+                continue;
+            }
+            mapping_index[grid_index] = std::stoi(row[1]);
+        }
+    }
+    if (read_processed_file(processed_file_path("id"), content)) {
+        std::istringstream id_file(content);
+        while (getline(id_file, l)) {
+            auto row = split_str(l, delim_str_);
+            mapping_id[std::stoi(row[0])] = row[1];
+        }
+    }
+}
+
+std::string LocationAreaService::processed_file_path(const std::string &name) const {
+    return processed_file_prefix_ + name + (files_compressed_ ? ".csv.gz" : ".csv");
+}
+
+// reads a whole processed file, uncompressed files are read as they are
+bool LocationAreaService::read_processed_file(const std::string &path, std::string &content) {
+    content.clear();
+    gzFile file = gzopen(path.c_str(), "rb");
+    if (file == nullptr) {
+        return false;
+    }
+    char buffer[1 << 16];
+    int read;
+    while ((read = gzread(file, buffer, sizeof(buffer))) > 0) {
+        content.append(buffer, read);
+    }
+    gzclose(file);
+    if (read < 0) {
+        Warning("area_mapping") << "Failed to read processed area mapping file " << path;
+        return false;
+    }
+    return true;
+}
+
+void LocationAreaService::write_processed_file(const std::string &path, const std::string &content) const {
+    if (!files_compressed_) {
+        std::ofstream file(path);
+        file << content;
+        return;
+    }
+    gzFile file = gzopen(path.c_str(), "wb");
+    if (file == nullptr) {
+        Warning("area_mapping") << "Failed to write processed area mapping file " << path;
+        return;
+    }
+    // gzwrite takes an unsigned length and returns an int, so large files are written in chunks
+    constexpr std::size_t chunk_size = 1 << 16;
+    bool failed = false;
+    for (std::size_t offset = 0; offset < content.size() && !failed; offset += chunk_size) {
+        const auto length = static_cast<unsigned>(std::min(chunk_size, content.size() - offset));
+        failed = gzwrite(file, content.data() + offset, length) != static_cast<int>(length);
+    }
+    if (gzclose(file) != Z_OK || failed) {
+        Warning("area_mapping") << "Failed to write processed area mapping file " << path;
+    }
+}
+
+// reads the processed files back and compares them with the index just built, returns the number of differences
+std::uint32_t LocationAreaService::verify_processed_files() {
+    std::multimap<grid_id_t, AreaIntersect> mapping_area;
+    std::vector<area_id_t> mapping_index(grid_size_, 0);
+    std::unordered_map<area_id_t, std::string> mapping_id;
+    read_processed_files(mapping_area, mapping_index, mapping_id);
+
+    std::uint32_t differences = 0;
+    if (mapping_id.size() != mapping_id_.size()) {
+        Warning("area_mapping") << "Processed area mapping has " << mapping_id.size() << " areas, expected " << mapping_id_.size();
+        differences++;
+    }
+    for (const auto &[id, name]: mapping_id_) {
+        if (const auto it = mapping_id.find(id); it == mapping_id.end() || it->second != name) {
+            Warning("area_mapping") << "Processed area mapping id " << id << " is '" << (it == mapping_id.end() ? "" : it->second) << "', expected '" << name << "'";
+            differences++;
+        }
+    }
+    for (std::size_t i = 0; i < mapping_index_.size(); i++) {
+        if (mapping_index[i] != mapping_index_[i]) {
+            Warning("area_mapping") << "Processed area mapping index of grid tile " << i << " is " << mapping_index[i] << ", expected " << mapping_index_[i];
+            differences++;
+        }
+    }
+    if (mapping_area.size() != mapping_area_.size()) {
+        Warning("area_mapping") << "Processed area mapping has " << mapping_area.size() << " split geometries, expected " << mapping_area_.size();
+        differences++;
+    } else {
+        // the geometries are written and read in the same order
+        for (auto a = mapping_area_.begin(), b = mapping_area.begin(); a != mapping_area_.end(); ++a, ++b) {
+            if (a->first != b->first || a->second.id != b->second.id || !a->second.geo->Equals(b->second.geo)) {
+                Warning("area_mapping") << "Processed area mapping geometry of area " << a->second.id << " in grid tile " << a->first << " differs";
+                differences++;
+            }
+        }
+    }
+    for (const auto &[k, a]: mapping_area) {
+        OGRGeometryFactory::destroyGeometry(a.geo);
+    }
+
+    if (differences == 0) {
+        std::cout << "Processed area mapping verified: " << mapping_id_.size() << " areas, " << mapping_area_.size() << " split geometries" << std::endl;
+    }
+    return differences;
+}
+
+std::map<std::string, std::string> LocationAreaService::processed_info(const std::vector<std::string> &paths) {
+    return {
+        {"grid_size", std::to_string(grid_size_)},
+        {"grid_bbox", std::to_string(grid_bbox_.min_lon) + "," + std::to_string(grid_bbox_.min_lat) + "," + std::to_string(grid_bbox_.max_lon) + "," + std::to_string(grid_bbox_.max_lat)},
+        {"source", boost::algorithm::join(paths, ",")},
+        {"id_col", std::to_string(id_col_)},
+        {"geo_col", std::to_string(geo_col_)},
+        {"geo_type", geo_type_},
+    };
+}
+
+bool LocationAreaService::read_processed_info(std::map<std::string, std::string> &stored) {
+    std::ifstream info_file((processed_file_prefix_ + "info.csv").c_str());
+    if (!info_file.is_open()) {
+        return false;
+    }
+    std::string l;
+    while (getline(info_file, l)) {
+        auto row = split_str(l, delim_str_);
+        if (row.size() == 2) {
+            stored[row[0]] = row[1];
+        }
+    }
+    info_file.close();
+    return true;
+}
+
+bool LocationAreaService::processed_info_matches(const std::vector<std::string> &paths, const bool info_exists, std::map<std::string, std::string> &stored) {
+    if (!info_exists) {
+        Warning("area_mapping") << "No info file found for processed area mapping files.";
+        return false;
+    }
+    bool matches = true;
+    for (const auto &[k, v]: processed_info(paths)) {
+        if (stored[k] != v) {
+            Warning("area_mapping") << "Processed area mapping " << k << " is '" << stored[k] << "', expected '" << v << "'";
+            matches = false;
+        }
+    }
+    return matches;
+}
+
+// all files need the same header, or the same number of columns if they have none
+bool LocationAreaService::schemas_match(const std::vector<std::string> &paths) {
+    std::vector<std::string> first_row;
+    for (const auto &path: paths) {
+        std::ifstream in(path.c_str());
+        std::string line;
+        if (!in.is_open() || !getline(in, line)) {
+            std::cout << "Failed to open area mapping file " << path << "!" << std::endl;
+            return false;
+        }
+        Tokenizer tok(line, seps_);
+        std::vector<std::string> row(tok.begin(), tok.end());
+        if (&path == &paths.front()) {
+            first_row = std::move(row);
+        } else if (file_has_header_ ? row != first_row : row.size() != first_row.size()) {
+            std::cout << "ERROR: area mapping file " << path << " has different columns than " << paths.front() << "!" << std::endl;
+            return false;
+        }
+    }
+    return true;
+}
+
 void LocationAreaService::output_mapping() {
     std::uint32_t no_area_count = 0;
     std::uint32_t single_area_count = 0;
@@ -175,7 +381,7 @@ void LocationAreaService::output_mapping() {
     std::cout << "Areas: " << mapping_id_.size() << ", Split geometries: " << split_geos_count << ", Grid: [ empty: " << no_area_count << ", single: " << single_area_count << ", multiple: " << multiple_area_count << " ] " << std::endl;
 }
 
-void LocationAreaService::add_area_to_mapping_index(area_id_t id, const std::string &geometry) {
+std::uint32_t LocationAreaService::add_area_to_mapping_index(area_id_t id, const std::string &geometry) {
     OGRGeometry *poGeom;
     OGRErr eErr = OGRERR_NONE;
     if (geo_type_ == "wkt") {
@@ -199,39 +405,108 @@ void LocationAreaService::add_area_to_mapping_index(area_id_t id, const std::str
             default:
                 pszMessage = "Unrecognized error";
         }
-        std::cout << "WARNING: CSV contains row with invalid geometry data: " << pszMessage << std::endl;
-        return;
+        Warning("area_mapping_csv") << "CSV contains row with invalid geometry data: " << pszMessage;
+        return 0;
     }
     if (debug_mode_) {
         std::cout << "Processing area " << id << ", valid: " << poGeom->IsValid();
     }
-    std::uint32_t intersecting_grid_tiles = 0;
-    std::uint32_t contained_grid_tiles = 0;
-    for (grid_id_t i = 0; i < grid_size_; i++) {
-        OGRPolygon e = grid_[i];
-        if (e.Intersects(poGeom)) {
-            intersecting_grid_tiles++;
-            if (poGeom->Contains(&e)) {
-                contained_grid_tiles++;
-                mapping_index_[i] = id;
-            } else {
-                mapping_index_[i] = area_id_multiple_;
-                mapping_area_.insert({i, AreaIntersect{id, poGeom->Intersection(&e)}});
+    // the grid tiles are split into one contiguous range per thread. Each thread only sets the index entries of its own
+    // tiles and collects the intersections, which are added to the area mapping in tile order afterwards. Areas are
+    // still added one after another, so the index is the same as with a single thread.
+    const auto threads = std::clamp<std::uint32_t>(index_threads_, 1, grid_size_);
+    std::vector<std::vector<std::pair<grid_id_t, OGRGeometry *>>> intersections(threads);
+    std::vector<std::uint32_t> intersecting(threads, 0);
+    std::vector<std::uint32_t> contained(threads, 0);
+    const auto test_tiles = [&](const std::uint32_t thread, const OGRGeometry *geometry) {
+        const auto begin = static_cast<grid_id_t>(grid_size_ * thread / threads);
+        const auto end = static_cast<grid_id_t>(grid_size_ * (thread + 1) / threads);
+        for (grid_id_t i = begin; i < end; i++) {
+            OGRPolygon e = grid_[i];
+            if (e.Intersects(geometry)) {
+                intersecting[thread]++;
+                if (geometry->Contains(&e)) {
+                    contained[thread]++;
+                    mapping_index_[i] = id;
+                } else {
+                    mapping_index_[i] = area_id_multiple_;
+                    intersections[thread].emplace_back(i, geometry->Intersection(&e));
+                }
+            }
+        }
+    };
+    if (threads == 1) {
+        test_tiles(0, poGeom);
+    } else {
+        // gdal geometries are not safe to be used by several threads at once, so each thread works on its own copy.
+        // Exceptions would terminate the program if they left a thread, so they are passed on after all threads ended.
+        std::vector<std::exception_ptr> errors(threads);
+        {
+            std::vector<std::jthread> workers;
+            for (std::uint32_t thread = 0; thread < threads; thread++) {
+                workers.emplace_back([&, thread]() {
+                    try {
+                        const std::unique_ptr<OGRGeometry> geometry(poGeom->clone());
+                        test_tiles(thread, geometry.get());
+                    } catch (...) {
+                        errors[thread] = std::current_exception();
+                    }
+                });
+            }
+        }
+        for (const auto &error: errors) {
+            if (error) {
+                for (const auto &part: intersections) {
+                    for (const auto &[i, geometry]: part) {
+                        OGRGeometryFactory::destroyGeometry(geometry);
+                    }
+                }
+                OGRGeometryFactory::destroyGeometry(poGeom);
+                std::rethrow_exception(error);
             }
         }
     }
+    for (const auto &part: intersections) {
+        for (const auto &[i, geometry]: part) {
+            mapping_area_.insert({i, AreaIntersect{id, geometry}});
+        }
+    }
+    const auto intersecting_grid_tiles = std::accumulate(intersecting.begin(), intersecting.end(), 0u);
+    const auto contained_grid_tiles = std::accumulate(contained.begin(), contained.end(), 0u);
     if (debug_mode_) {
         std::cout << " => intersecting grid tiles: " << intersecting_grid_tiles << ", contained grid tiles: " << contained_grid_tiles << std::endl;
     }
     OGRGeometryFactory::destroyGeometry(poGeom);
+    return intersecting_grid_tiles;
+}
+
+bool LocationAreaService::grid_index(const osmium::Location l, grid_id_t &index) const {
+    int tile_lon, tile_lat;
+    if (!grid_bbox_.tile(l, tile_lon, tile_lat)) {
+        return false;
+    }
+    const auto i = static_cast<std::size_t>(tile_lat - grid_bbox_.min_lat) * grid_width_ + (tile_lon - grid_bbox_.min_lon);
+    if (i >= mapping_index_.size()) {
+        return false;
+    }
+    index = static_cast<grid_id_t>(i);
+    return true;
 }
 
-std::vector<std::string> LocationAreaService::get_area(osmium::Location l) {
+std::vector<std::string> LocationAreaService::get_area(osmium::Location l, bool *ambiguous) {
     std::vector<std::string> areas;
+    if (ambiguous != nullptr) {
+        *ambiguous = false;
+    }
     if (!initialized_) {
         return areas;
     }
-    grid_id_t grid_index = ((int) l.lat() + 90) * 360 + ((int) l.lon() + 180);
+    grid_id_t grid_index;
+    if (!this->grid_index(l, grid_index)) {
+        // nodes outside of the grid get no area
+        locations_outside_grid_++;
+        return areas;
+    }
     OGRPoint point(l.lon(), l.lat());
     if (debug_mode_) {
         std::cout << "Lookup point: (" << l.lon() << " " << l.lat() << ") grid index " << grid_index << " => " << mapping_index_[grid_index] << std::endl;
@@ -243,6 +518,9 @@ std::vector<std::string> LocationAreaService::get_area(osmium::Location l) {
             areas.push_back(mapping_id_[mapping_index_[grid_index]]);
             break;
         case area_id_multiple_:// multiple areas
+            if (ambiguous != nullptr) {
+                *ambiguous = true;
+            }
             auto range = mapping_area_.equal_range(grid_index);
             for (auto i = range.first; i != range.second; ++i) {
                 if (i->second.geo->Contains(&point)) {
@@ -261,13 +539,18 @@ std::vector<std::string> LocationAreaService::get_area(osmium::Location l) {
     return areas;
 }
 
-LocationAreaService::LocationAreaService(bool debug_mode, std::uint16_t id_col, std::uint16_t geo_col, std::string &geo_type, bool file_has_header, std::string &processed_file_prefix) : debug_mode_(debug_mode), id_col_(id_col), geo_col_(geo_col), geo_type_(geo_type), file_has_header_(file_has_header), processed_file_prefix_(processed_file_prefix) {
+LocationAreaService::LocationAreaService(bool debug_mode, std::uint16_t id_col, std::uint16_t geo_col, std::string &geo_type, bool file_has_header, std::string &processed_file_prefix, GridBBox grid_bbox) : debug_mode_(debug_mode), id_col_(id_col), geo_col_(geo_col), geo_type_(geo_type), file_has_header_(file_has_header), processed_file_prefix_(processed_file_prefix), grid_bbox_(grid_bbox) {
     GDALAllRegister();
-    for (std::uint16_t grid_lat = 0; grid_lat < 180; grid_lat++) {
-        for (std::uint16_t grid_lon = 0; grid_lon < 360; grid_lon++) {
-            grid_id_t grid_index = grid_lat * 360 + grid_lon;
-            int box_lon = grid_lon - 180;
-            int box_lat = grid_lat - 90;
+    grid_width_ = grid_bbox_.max_lon - grid_bbox_.min_lon;
+    const grid_id_t grid_height = grid_bbox_.max_lat - grid_bbox_.min_lat;
+    grid_size_ = grid_width_ * grid_height;
+    grid_.resize(grid_size_);
+    mapping_index_.resize(grid_size_, 0);
+    for (std::uint16_t grid_lat = 0; grid_lat < grid_height; grid_lat++) {
+        for (std::uint16_t grid_lon = 0; grid_lon < grid_width_; grid_lon++) {
+            grid_id_t grid_index = grid_lat * grid_width_ + grid_lon;
+            int box_lon = grid_lon + grid_bbox_.min_lon;
+            int box_lat = grid_lat + grid_bbox_.min_lat;
             OGRLinearRing ring;
             OGRPolygon poly;
             ring.addPoint(box_lon, box_lat);
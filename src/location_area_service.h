@@ -1,14 +1,43 @@
 #ifndef OSM_TRANSFORM_LOCATION_AREA_SERVICE_H
 #define OSM_TRANSFORM_LOCATION_AREA_SERVICE_H
 
+#include <algorithm>
+#include <cmath>
 #include <limits>
 #include <iostream>
+#include <map>
+#include <vector>
 #include <osmium/osm/location.hpp>
 #include <ogr_geometry.h>
 
+#include <boost/tokenizer.hpp>
+
 typedef std::uint16_t area_id_t;
 typedef std::uint16_t grid_id_t;
 
+struct GridBBox {
+    int min_lon = -180;
+    int min_lat = -90;
+    int max_lon = 180;
+    int max_lat = 90;
+
+    // lower left corner of the 1x1 degree grid tile of the location, false if it is outside of the grid. Locations
+    // on the upper bounds belong to the last tile.
+    bool tile(const osmium::Location l, int &lon, int &lat) const {
+        if (!l.valid()) {
+            return false;
+        }
+        const double x = l.lon_without_check();
+        const double y = l.lat_without_check();
+        if (x < min_lon || y < min_lat || x > max_lon || y > max_lat) {
+            return false;
+        }
+        lon = std::min(static_cast<int>(std::floor(x)), max_lon - 1);
+        lat = std::min(static_cast<int>(std::floor(y)), max_lat - 1);
+        return true;
+    }
+};
+
 struct AreaIntersect {
     area_id_t id;
     OGRGeometry *geo;
@@ -17,12 +46,16 @@ struct AreaIntersect {
 class LocationAreaService {
 
 private:
-    static const grid_id_t grid_size_ = 64800;
     static const area_id_t area_id_multiple_ = std::numeric_limits<area_id_t>::max();
     static const std::string delim_str_;
-
-    OGRPolygon grid_[grid_size_];
-    area_id_t mapping_index_[grid_size_] = {0};
+    typedef boost::tokenizer<boost::escaped_list_separator<char>, std::string::const_iterator, std::string> Tokenizer;
+    static const boost::escaped_list_separator<char> seps_;
+
+    GridBBox grid_bbox_;
+    grid_id_t grid_width_;
+    grid_id_t grid_size_;
+    std::vector<OGRPolygon> grid_;
+    std::vector<area_id_t> mapping_index_;
     std::multimap<grid_id_t, AreaIntersect> mapping_area_;
     std::unordered_map<area_id_t, std::string> mapping_id_;
 
@@ -31,20 +64,57 @@ private:
     std::string geo_type_;
     std::string processed_file_prefix_;
     bool file_has_header_ = false;
+    // whether the processed files read or written are gzip compressed
+    bool files_compressed_ = false;
 
     bool debug_mode_ = false;
     bool initialized_ = false;
 
-    void add_area_to_mapping_index(area_id_t id, const std::string& geometry);
+    std::uint32_t add_area_to_mapping_index(area_id_t id, const std::string& geometry);
 
     void output_mapping();
 
+    std::map<std::string, std::string> processed_info(const std::vector<std::string>& paths);
+
+    bool read_processed_info(std::map<std::string, std::string>& stored);
+
+    bool processed_info_matches(const std::vector<std::string>& paths, bool info_exists, std::map<std::string, std::string>& stored);
+
+    std::string processed_file_path(const std::string& name) const;
+
+    static bool read_processed_file(const std::string& path, std::string& content);
+
+    void write_processed_file(const std::string& path, const std::string& content) const;
+
+    bool schemas_match(const std::vector<std::string>& paths);
+
+    void read_processed_files(std::multimap<grid_id_t, AreaIntersect>& mapping_area, std::vector<area_id_t>& mapping_index,
+                              std::unordered_map<area_id_t, std::string>& mapping_id);
+
 public:
-    explicit LocationAreaService(bool debug_mode, std::uint16_t id_col, std::uint16_t geo_col, std::string& geo_type, bool file_has_header, std::string& processed_file_prefix);
+    bool rebuild_on_mismatch_ = false;
+    // read the processed files back after building them and compare them with the built index
+    bool verify_index_ = false;
+    // write the processed files gzip compressed
+    bool compress_ = false;
+    std::uint32_t progress_interval_ = 10;
+    // threads testing the grid tiles against each area while building the index
+    std::uint32_t index_threads_ = 1;
+    unsigned long long locations_outside_grid_ = 0;
+
+    explicit LocationAreaService(bool debug_mode, std::uint16_t id_col, std::uint16_t geo_col, std::string& geo_type, bool file_has_header, std::string& processed_file_prefix, GridBBox grid_bbox = {});
+
+    // builds one index from the areas of all files, which need the same columns
+    void load(const std::vector<std::string>& paths);
+
+    std::uint32_t verify_processed_files();
 
-    void load(const std::string& path);
+    // ambiguous is set if given and the location is in a grid tile intersecting several areas, whose geometries
+    // decide which of them contain it
+    std::vector<std::string> get_area(osmium::Location l, bool *ambiguous = nullptr);
 
-    std::vector<std::string> get_area(osmium::Location l);
+    // index of the grid tile containing the location, locations on the upper grid bounds belong to the last tile
+    bool grid_index(osmium::Location l, grid_id_t &index) const;
 
     bool is_initialized() {
         return initialized_;
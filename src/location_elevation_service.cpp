@@ -1,8 +1,22 @@
 #include "location_elevation_service.h"
 
+#include <algorithm>
+#include <chrono>
+#include <cmath>
 #include <filesystem>
+#include <iomanip>
+#include <sstream>
+#include <thread>
+#include <tuple>
+#include <utility>
+
+#include <boost/algorithm/string/predicate.hpp>
 
 #include "geotiff.h"
+#include "warnings.h"
+
+#include <osmium/geom/coordinates.hpp>
+#include <osmium/geom/haversine.hpp>
 
 namespace fs = std::filesystem;
 
@@ -15,9 +29,11 @@ typedef bgm::segment<point> segment;
 typedef bgm::box<point> box;
 typedef std::pair<box, PrioAndFilename> rtree_entry;
 
-inline auto sortRTreeEntryByPrio(const rtree_entry &a, const rtree_entry &b) { return a.second.prio < b.second.prio; }
+inline auto sortRTreeEntryByPrio(const rtree_entry &a, const rtree_entry &b) {
+    return std::tie(a.second.rank, a.second.prio) < std::tie(b.second.rank, b.second.prio);
+}
 
-std::vector<LocationElevation> LocationElevationService::interpolate(osmium::Location from, osmium::Location to) {
+std::vector<LocationElevation> LocationElevationService::interpolate(osmium::Location from, osmium::Location to, const double split_distance) {
     std::vector<LocationElevation> data;
     std::vector<rtree_entry> query_result;
     box bbox;
@@ -32,13 +48,27 @@ std::vector<LocationElevation> LocationElevationService::interpolate(osmium::Loc
     auto delta_x = to.lon() - from.lon();
     auto delta_y = to.lat() - from.lat();
     auto length = std::sqrt(delta_x * delta_x + delta_y * delta_y);
+    if (length <= 0) {
+        data.push_back(LocationElevation {to, elevation(to, false)});
+        return data;
+    }
+    if (split_distance > 0) {
+        // same spacing on the ground at all latitudes instead of a fixed step in degrees
+        const auto distance = osmium::geom::haversine::distance(osmium::geom::Coordinates(from), osmium::geom::Coordinates(to));
+        if (distance <= 0) {
+            data.push_back(LocationElevation {to, elevation(to, false)});
+            return data;
+        }
+        step_width = length * split_distance / distance;
+    }
 
     const auto nx = delta_x / length;
     const auto ny = delta_y / length;
     const auto sx = nx * step_width;
     const auto sy = ny * step_width;
 
-    auto steps = static_cast<int>(delta_x / sx);
+    // delta_x / sx would be 0 / 0 on north-south segments
+    auto steps = static_cast<int>(length / step_width);
     for (auto s = 0; s <= steps; s++) {
         double lng = from.lon() + sx * s;
         double lat = from.lat() + sy * s;
@@ -63,8 +93,22 @@ inline void put_tiffs_in_dir(const std::string &path, std::vector<std::string> &
             geotiffs.push_back(filename);
         }
     } catch (std::filesystem::filesystem_error const& ex) {
-        std::cout << "WARNING: Failed to read geotiffs from " << path << ". This might lead to a lesser success rate when determining location elevations.\n";
+        Warning("geotiff") << "Failed to read geotiffs from " << path << ". This might lead to a lesser success rate when determining location elevations.";
+    }
+}
+
+std::string LocationElevationService::srs_name(OGRSpatialReference &reference) {
+    if (reference.IsEmpty()) {
+        return "unknown";
     }
+    reference.AutoIdentifyEPSG();
+    const char *authority = reference.GetAuthorityName(nullptr);
+    const char *code = reference.GetAuthorityCode(nullptr);
+    if (authority != nullptr && code != nullptr) {
+        return std::string(authority) + ":" + code;
+    }
+    const char *name = reference.GetName();
+    return name != nullptr ? name : "unknown";
 }
 
 void LocationElevationService::load(const std::vector<std::string> &paths) {
@@ -73,16 +117,35 @@ void LocationElevationService::load(const std::vector<std::string> &paths) {
         put_tiffs_in_dir(path, geotiffs);
     }
     std::cout << "Load geotiff index...\n";
-    osmium::ProgressBar pTiffs{geotiffs.size(), osmium::isatty(2)};
+    osmium::ProgressBar pTiffs{geotiffs.size(), show_progress_ && osmium::isatty(2)};
     auto loaded = 0;
+    const auto index_failed = [&](const std::string &geotiff, const char *reason) {
+        if (strict_indexing_) {
+            throw std::runtime_error("could not index geotiff " + geotiff + ": " + reason);
+        }
+        Warning("geotiff") << "Failed to index geotiff " << geotiff << " (" << reason << "), it is skipped.";
+        tiffs_failed_to_index_++;
+        pTiffs.update(++loaded);
+    };
     for (const auto& geotiff: geotiffs) {
         const auto tif = GDALDatasetUniquePtr(GDALDataset::FromHandle(GDALOpen(geotiff.c_str(), GA_ReadOnly)));
+        if (!tif) {
+            index_failed(geotiff, "can not be opened");
+            continue;
+        }
 
         auto reference = Geotiff::getSpatialReference(tif->GetProjectionRef());
         const auto transformation = OGRCreateCoordinateTransformation(&reference, &WGS84);
+        if (transformation == nullptr) {
+            index_failed(geotiff, "unsupported spatial reference system");
+            continue;
+        }
 
         double transform[6] = {};
-        tif->GetGeoTransform(transform);
+        if (tif->GetGeoTransform(transform) != CE_None) {
+            index_failed(geotiff, "no geotransform");
+            continue;
+        }
 
         const double lng_min = transform[0] + 0 * transform[1] + 0 * transform[2];
         const double lat_max = transform[3] + 0 * transform[4] + 0 * transform[5];
@@ -91,20 +154,73 @@ void LocationElevationService::load(const std::vector<std::string> &paths) {
 
         double lng[2] = {lng_min, lng_max};
         double lat[2] = {lat_min, lat_max};
-        transformation->Transform(2, lng, lat);
+        if (!transformation->Transform(2, lng, lat)) {
+            index_failed(geotiff, "corners can not be transformed to WGS84");
+            continue;
+        }
+        // south-up rasters have a positive pixel height, their origin is the lower corner
+        if (lng[0] > lng[1]) {
+            std::swap(lng[0], lng[1]);
+        }
+        if (lat[0] > lat[1]) {
+            std::swap(lat[0], lat[1]);
+        }
 
         box b(point(lng[0], lat[0]), point(lng[1], lat[1]));
+        if (extent_.valid() && (lng[1] < extent_.bottom_left().lon() || lng[0] > extent_.top_right().lon() ||
+                                lat[1] < extent_.bottom_left().lat() || lat[0] > extent_.top_right().lat())) {
+            tiffs_outside_extent_++;
+            pTiffs.update(++loaded);
+            continue;
+        }
+        srs_counts_[srs_name(reference)]++;
         double lngStep = (lng[1] - lng[0]) / static_cast<double>(tif->GetRasterXSize());
         double latStep = (lat[1] - lat[0]) / static_cast<double>(tif->GetRasterYSize());
         const auto prio = std::min(lngStep, latStep);
 
-        auto v = std::make_pair(b, PrioAndFilename{prio, geotiff});
+        std::size_t rank = 0;
+        while (rank < tiff_priority_.size() && !geotiff.contains(tiff_priority_[rank])) {
+            rank++;
+        }
+        auto v = std::make_pair(b, PrioAndFilename{prio, geotiff, rank, lngStep, latStep});
         rtree_.insert(v);
         loaded += 1;
         pTiffs.update(loaded);
     }
     initialized_ = true;
     std::cout << std::endl << "geotiff tiles indexed: " << rtree_.size() << std::endl;
+    if (tiffs_failed_to_index_ > 0) {
+        std::cout << "geotiff tiles which could not be indexed: " << tiffs_failed_to_index_ << std::endl;
+    }
+    if (tiffs_outside_extent_ > 0) {
+        std::cout << "geotiff tiles outside of the elevation_bbox skipped: " << tiffs_outside_extent_ << std::endl;
+    }
+    if (srs_counts_.size() > 1) {
+        Warning("geotiff") << "geotiffs use different spatial reference systems, elevations near tile borders might be slightly inconsistent";
+    }
+    if (srs_counts_.size() > 1 || debug_mode_) {
+        for (const auto &[srs, count]: srs_counts_) {
+            std::cout << "  " << srs << ": " << count << " geotiffs" << std::endl;
+        }
+    }
+}
+
+std::string LocationElevationService::settings() const {
+    std::vector<std::string> tiffs;
+    for (const auto &entry: rtree_) {
+        tiffs.push_back(entry.second.filename);
+    }
+    std::sort(tiffs.begin(), tiffs.end());
+    std::ostringstream out;
+    out << std::setprecision(17) << "scale=" << scale_ << ";offset=" << offset_ << ";seam_blend=" << seam_blend_ << ";priority=";
+    for (const auto &pattern: tiff_priority_) {
+        out << pattern << '\n';
+    }
+    out << ";tiffs=";
+    for (const auto &tiff: tiffs) {
+        out << tiff << '\n';
+    }
+    return out.str();
 }
 
 std::shared_ptr<Geotiff> LocationElevationService::load_tiff(const char * filename) {
@@ -124,16 +240,29 @@ std::shared_ptr<Geotiff> LocationElevationService::load_tiff(const char * filena
         return geoTiff;
     }
 
-    if (!std::filesystem::exists(filename)) {
+    if (!std::filesystem::exists(filename) || failed_tiffs_.contains(filename)) {
         return nullptr;
     }
-    auto geotiff = std::make_shared<Geotiff>(filename, debug_mode_);
-    if (geotiff == nullptr) {
+    auto geotiff = std::make_shared<Geotiff>(filename, debug_mode_, pixel_cache_limit_);
+    for (std::uint32_t attempt = 1; !geotiff->is_valid() && attempt <= open_retries_; attempt++) {
+        const auto backoff = std::chrono::milliseconds(open_backoff_ms_ << (attempt - 1));
+        Warning("geotiff") << "Failed to open geotiff " << filename << ", retry " << attempt << "/" << open_retries_ << " in " << backoff.count() << " ms";
+        std::this_thread::sleep_for(backoff);
+        geotiff = std::make_shared<Geotiff>(filename, debug_mode_, pixel_cache_limit_);
+    }
+    if (!geotiff->is_valid()) {
+        if (strict_loading_) {
+            throw std::runtime_error(std::string("could not load geotiff ") + filename);
+        }
+        Warning("geotiff") << "Failed to open geotiff " << filename << ", it is skipped.";
+        failed_tiffs_.insert(filename);
+        failed_tiff_loads_++;
         return nullptr;
     }
 
     while (mem_size_ > 0 && mem_size_ + tile_size_[filename] > cache_limit_) {
         auto to_remove = lru_.back();
+        evicted_pixel_cache_hits_ += cache_[to_remove]->pixel_cache_hits_;
         mem_size_ -= tile_size_[to_remove];
         cache_.erase(to_remove);
         lru_.pop_back();
@@ -158,7 +287,16 @@ double LocationElevationService::elevation(osmium::Location l, bool count) {
     }
     auto filename = query_result.front().second.filename;
     auto geo_tiff = load_tiff(filename.c_str());
+    if (geo_tiff == nullptr) {
+        return kNoDataValue;
+    }
     double ele = geo_tiff->elevation(l.lon(), l.lat());
+    if (seam_blend_ && ele != kNoDataValue) {
+        ele = blend_seam(l, query_result.front(), *geo_tiff, ele);
+    }
+    if (ele != kNoDataValue) {
+        ele = scale_ * ele + offset_;
+    }
 
     if (ele != kNoDataValue && count) {
         if (filename.starts_with("srtm")) {
@@ -172,6 +310,82 @@ double LocationElevationService::elevation(osmium::Location l, bool count) {
     return ele;
 }
 
+// within one pixel of the edge of the geotiff, the value is blended with the nearest pixel of the best other geotiff
+// covering the location or the adjacent area: half each at the seam, only the geotiff itself one pixel away from it. The
+// seam is the edge of the other geotiff if the location is outside of it, so locations near another edge, e.g. the
+// south edge next to an eastern neighbor, are weighted by their distance to the eastern seam and not to the south edge.
+double LocationElevationService::blend_seam(osmium::Location l, const rtree_entry &entry, const Geotiff &geotiff, const double ele) {
+    const auto distance = geotiff.edge_distance(l.lon(), l.lat());
+    if (distance >= 1) {
+        return ele;
+    }
+    const auto margin = entry.second.prio;
+    std::vector<rtree_entry> candidates;
+    rtree_.query(bgi::intersects(box(point(l.lon() - margin, l.lat() - margin), point(l.lon() + margin, l.lat() + margin))),
+                 std::back_inserter(candidates));
+    std::sort(candidates.begin(), candidates.end(), sortRTreeEntryByPrio);
+    for (const auto &candidate: candidates) {
+        if (candidate.second.filename == entry.second.filename) {
+            continue;
+        }
+        const auto neighbor = load_tiff(candidate.second.filename.c_str());
+        if (neighbor == nullptr) {
+            continue;
+        }
+        const auto neighbor_distance = neighbor->edge_distance(l.lon(), l.lat());
+        if (neighbor_distance < -1) {
+            continue;
+        }
+        // pixel() moves locations up to one pixel outside of the raster to its edge
+        const auto neighbor_ele = neighbor->elevation(l.lon(), l.lat());
+        if (neighbor_ele == kNoDataValue) {
+            continue;
+        }
+        // adjacent geotiffs meet at the edge of the neighbor, overlapping ones at the edge of this geotiff
+        const auto seam_distance = neighbor_distance < 0 ? -neighbor_distance : distance;
+        const auto weight = 0.5 + 0.5 * std::clamp(seam_distance, 0.0, 1.0);
+        seam_blends_++;
+        return weight * ele + (1 - weight) * neighbor_ele;
+    }
+    return ele;
+}
+
+bool LocationElevationService::raw_elevation(osmium::Location l, int &x, int &y, double &value) {
+    std::vector<rtree_entry> query_result;
+    rtree_.query(bgi::contains(point(l.lon(),l.lat())), std::back_inserter(query_result));
+    if (query_result.empty()) {
+        return false;
+    }
+    std::sort(query_result.begin(), query_result.end(), sortRTreeEntryByPrio);
+    const auto geo_tiff = load_tiff(query_result.front().second.filename.c_str());
+    if (geo_tiff == nullptr || !geo_tiff->pixel(l.lon(), l.lat(), x, y)) {
+        return false;
+    }
+    value = geo_tiff->value(x, y);
+    return true;
+}
+
+double LocationElevationService::resolution(osmium::Location l) {
+    std::vector<rtree_entry> query_result;
+    rtree_.query(bgi::contains(point(l.lon(),l.lat())), std::back_inserter(query_result));
+    if (query_result.empty()) {
+        return 0;
+    }
+    std::sort(query_result.begin(), query_result.end(), sortRTreeEntryByPrio);
+    // a degree of latitude is about 111 km, a degree of longitude gets shorter towards the poles
+    const auto &entry = query_result.front().second;
+    const auto width = entry.lon_step * 111320.0 * std::cos(l.lat() * M_PI / 180.0);
+    return std::min(width, entry.lat_step * 111320.0);
+}
+
+unsigned long long LocationElevationService::pixel_cache_hits() {
+    auto hits = evicted_pixel_cache_hits_;
+    for (const auto &[filename, geotiff]: cache_) {
+        hits += geotiff->pixel_cache_hits_;
+    }
+    return hits;
+}
+
 LocationElevationService::LocationElevationService(ulong cache_limit, bool debug_mode) : cache_limit_(cache_limit), debug_mode_(debug_mode) {
     GDALAllRegister();
 }
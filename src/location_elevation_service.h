@@ -2,12 +2,15 @@
 #define OSM_TRANSFORM_LOCATION_ELEVATION_SERVICE_H
 
 #include <list>
+#include <map>
+#include <set>
 
 #include <boost/geometry.hpp>
 #include <boost/geometry/geometries/box.hpp>
 #include <boost/geometry/geometries/point.hpp>
 #include <boost/geometry/index/rtree.hpp>
 
+#include <osmium/osm/box.hpp>
 #include <osmium/osm/location.hpp>
 
 typedef unsigned long ulong;
@@ -18,10 +21,17 @@ struct LocationElevation {
 };
 
 class Geotiff;
+class OGRSpatialReference;
 
 struct PrioAndFilename {
+    // pixel size in degrees
     double prio;
     std::string filename;
+    // index of the first matching pattern of the tiff priority list, the size of the list if none matches
+    std::size_t rank = 0;
+    // pixel width and height in degrees
+    double lon_step = 0;
+    double lat_step = 0;
 };
 
 class LocationElevationService {
@@ -36,13 +46,41 @@ private:
     ulong mem_size_ = 0;
     ulong cache_limit_ = 150000000;
     std::map<std::string, std::uint64_t> tile_size_;
+    std::set<std::string> failed_tiffs_;
     bool initialized_ = false;
     bool debug_mode_ = false;
 
+    double blend_seam(osmium::Location l, const rtree_entry &entry, const Geotiff &geotiff, double ele);
+
 public:
     unsigned long long found_custom_ = 0;
     unsigned long long found_srtm_ = 0;
     unsigned long long found_gmted_ = 0;
+    unsigned long long failed_tiff_loads_ = 0;
+    unsigned long long evicted_pixel_cache_hits_ = 0;
+    std::size_t pixel_cache_limit_ = 1024;
+    std::uint32_t open_retries_ = 0;
+    std::uint32_t open_backoff_ms_ = 100;
+    bool strict_loading_ = false;
+    // abort instead of skipping geotiffs which can not be indexed, e.g. because they are corrupt
+    bool strict_indexing_ = false;
+    unsigned long long tiffs_failed_to_index_ = 0;
+    bool show_progress_ = true;
+    // distance in meters between the sampled points of interpolate, 0 samples at the pixel size of the finest geotiff
+    double split_distance_ = 0;
+    // geotiffs whose path contains an earlier pattern are preferred over others regardless of their pixel size
+    std::vector<std::string> tiff_priority_;
+    // linear transformation of the geotiff values, e.g. to convert feet or apply a datum offset
+    double scale_ = 1;
+    double offset_ = 0;
+    // blend the values of adjacent geotiffs within one pixel of their edges to avoid steps at the seams
+    bool seam_blend_ = false;
+    unsigned long long seam_blends_ = 0;
+    // only geotiffs intersecting this extent are indexed, all if it is invalid (the default)
+    osmium::Box extent_;
+    unsigned long long tiffs_outside_extent_ = 0;
+    // number of indexed geotiffs per spatial reference system, e.g. "EPSG:4326"
+    std::map<std::string, std::uint32_t> srs_counts_;
 
     explicit LocationElevationService(ulong cache_limit, bool debug_mode);
 
@@ -50,9 +88,28 @@ public:
 
     std::shared_ptr<Geotiff> load_tiff(const char* filename);
 
+    static std::string srs_name(OGRSpatialReference &reference);
+
     double elevation(osmium::Location l, bool count);
 
-    std::vector<LocationElevation> interpolate(osmium::Location from, osmium::Location to);
+    // pixel coordinate and value before scale and offset of the geotiff used for the location, false if there is none
+    bool raw_elevation(osmium::Location l, int &x, int &y, double &value);
+
+    // approximate pixel size in meters of the geotiff used for the location, 0 if there is none
+    double resolution(osmium::Location l);
+
+    std::vector<LocationElevation> interpolate(osmium::Location from, osmium::Location to) {
+        return interpolate(from, to, split_distance_);
+    }
+
+    // samples the segment every split_distance meters, at the pixel size of the geotiffs if it is 0
+    std::vector<LocationElevation> interpolate(osmium::Location from, osmium::Location to, double split_distance);
+
+    unsigned long long pixel_cache_hits();
+
+    // the settings affecting the elevations and the indexed geotiffs as text, to detect cached elevations which were
+    // determined with other settings
+    std::string settings() const;
 
     bool is_initialized() {
         return initialized_;
@@ -0,0 +1,65 @@
+#ifndef OSM_TRANSFORM_NODE_DEGREES_H
+#define OSM_TRANSFORM_NODE_DEGREES_H
+
+#include <algorithm>
+#include <cstdint>
+#include <limits>
+#include <utility>
+#include <vector>
+
+#include <osmium/index/id_set.hpp>
+#include <osmium/osm/types.hpp>
+
+// Number of ways referencing each node. Nodes referenced by one way are kept in an id set, every further reference is
+// appended to a list, which finish() sorts and compacts into the degrees of the nodes with more than one way. Degrees
+// above max_degree are stored as max_degree.
+class NodeDegrees {
+    osmium::index::IdSetDense<osmium::unsigned_object_id_type> referenced_;
+    std::vector<osmium::unsigned_object_id_type> repeated_;
+    std::vector<std::pair<osmium::unsigned_object_id_type, std::uint16_t>> degrees_;
+    std::uint16_t max_degree_;
+
+public:
+    // 0 = no limit
+    explicit NodeDegrees(const std::uint16_t max_degree = 0) : max_degree_(max_degree > 0 ? max_degree : std::numeric_limits<std::uint16_t>::max()) {
+    }
+
+    // the nodes of one way, nodes referenced several times by it are only counted once
+    void add_way(std::vector<osmium::unsigned_object_id_type> nodes) {
+        std::sort(nodes.begin(), nodes.end());
+        nodes.erase(std::unique(nodes.begin(), nodes.end()), nodes.end());
+        for (const auto id: nodes) {
+            if (!referenced_.check_and_set(id)) {
+                repeated_.push_back(id);
+            }
+        }
+    }
+
+    // must be called after all ways were added and before get
+    void finish() {
+        std::sort(repeated_.begin(), repeated_.end());
+        for (auto it = repeated_.begin(); it != repeated_.end();) {
+            const auto end = std::upper_bound(it, repeated_.end(), *it);
+            const auto degree = std::min<std::size_t>(end - it + 1, max_degree_);
+            degrees_.emplace_back(*it, static_cast<std::uint16_t>(degree));
+            it = end;
+        }
+        repeated_.clear();
+        repeated_.shrink_to_fit();
+    }
+
+    std::uint16_t get(const osmium::unsigned_object_id_type id) const {
+        const auto it = std::lower_bound(degrees_.begin(), degrees_.end(), id, [](const auto &entry, const auto value) { return entry.first < value; });
+        if (it != degrees_.end() && it->first == id) {
+            return it->second;
+        }
+        return referenced_.get(id) ? 1 : 0;
+    }
+
+    // nodes referenced by more than one way
+    std::size_t shared_nodes() const {
+        return degrees_.size();
+    }
+};
+
+#endif//OSM_TRANSFORM_NODE_DEGREES_H
@@ -0,0 +1,106 @@
+#ifndef OSM_TRANSFORM_NODE_ELEVATION_CACHE_H
+#define OSM_TRANSFORM_NODE_ELEVATION_CACHE_H
+
+#include <cstdint>
+#include <cstdio>
+#include <filesystem>
+#include <fstream>
+#include <iostream>
+#include <string>
+#include <unordered_map>
+
+#include <osmium/osm/location.hpp>
+#include <osmium/osm/types.hpp>
+
+#include "warnings.h"
+
+// Elevations determined for nodes, which can be saved to disk and reused by subsequent runs on the same input file
+// with the same elevation settings. The file starts with a signature line of the input file (size and modification
+// time) and a hash of the settings, followed by binary records of node id, location and elevation.
+class NodeElevationCache {
+    struct Entry {
+        osmium::Location location;
+        double ele;
+    };
+
+    std::unordered_map<osmium::object_id_type, Entry> cache_;
+    std::string input_signature_;
+
+    // FNV-1a, which unlike std::hash gives the same value in every build
+    static std::uint64_t hash(const std::string &text) {
+        std::uint64_t result = 14695981039346656037ULL;
+        for (const unsigned char c: text) {
+            result = (result ^ c) * 1099511628211ULL;
+        }
+        return result;
+    }
+
+public:
+    unsigned long long hits_ = 0;
+
+    // settings describes everything the elevations depend on besides the input, e.g. LocationElevationService::settings
+    NodeElevationCache(const std::string &input, const std::string &settings) {
+        char settings_hash[17];
+        snprintf(settings_hash, sizeof(settings_hash), "%016llx", static_cast<unsigned long long>(hash(settings)));
+        input_signature_ = std::to_string(std::filesystem::file_size(input)) + ";" +
+                std::to_string(std::filesystem::last_write_time(input).time_since_epoch().count()) + ";" + settings_hash;
+    }
+
+    bool load(const std::string &path) {
+        std::ifstream in(path, std::ios::binary);
+        if (!in.is_open()) {
+            return false;
+        }
+        std::string signature;
+        getline(in, signature);
+        if (signature != input_signature_) {
+            Warning("node_cache") << "node cache file " << path << " was created for a different input file or other elevation settings and is ignored.";
+            return false;
+        }
+        osmium::object_id_type id;
+        std::int32_t x, y;
+        double ele;
+        while (in.read(reinterpret_cast<char *>(&id), sizeof(id)) &&
+               in.read(reinterpret_cast<char *>(&x), sizeof(x)) &&
+               in.read(reinterpret_cast<char *>(&y), sizeof(y)) &&
+               in.read(reinterpret_cast<char *>(&ele), sizeof(ele))) {
+            cache_[id] = Entry{osmium::Location(x, y), ele};
+        }
+        return true;
+    }
+
+    void save(const std::string &path) const {
+        std::ofstream out(path, std::ios::binary | std::ios::trunc);
+        if (!out.is_open()) {
+            Warning("node_cache") << "failed to write node cache file " << path;
+            return;
+        }
+        out << input_signature_ << '\n';
+        for (const auto &[id, entry]: cache_) {
+            const std::int32_t x = entry.location.x();
+            const std::int32_t y = entry.location.y();
+            out.write(reinterpret_cast<const char *>(&id), sizeof(id));
+            out.write(reinterpret_cast<const char *>(&x), sizeof(x));
+            out.write(reinterpret_cast<const char *>(&y), sizeof(y));
+            out.write(reinterpret_cast<const char *>(&entry.ele), sizeof(entry.ele));
+        }
+    }
+
+    bool get(const osmium::object_id_type id, const osmium::Location location, double &ele) {
+        const auto it = cache_.find(id);
+        if (it == cache_.end() || it->second.location != location) {
+            return false;
+        }
+        hits_++;
+        ele = it->second.ele;
+        return true;
+    }
+
+    void put(const osmium::object_id_type id, const osmium::Location location, const double ele) {
+        cache_[id] = Entry{location, ele};
+    }
+
+    auto size() const { return cache_.size(); }
+};
+
+#endif//OSM_TRANSFORM_NODE_ELEVATION_CACHE_H
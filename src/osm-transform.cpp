@@ -1,10 +1,26 @@
+#include "accept_ids.h"
+#include "batched_writer.h"
 #include "config.h"
 #include "firstpass_handler.h"
+#include "input_format.h"
+#include "output_limit.h"
+#include "quiet_output.h"
+#include "reorder_file.h"
+#include "required_keys_filter.h"
 #include "rewrite_handler.h"
+#include "script_filter.h"
+#include "sharded_writer.h"
+#include "warnings.h"
 
+#include <algorithm>
 #include <chrono>
+#include <cstdlib>
 #include <filesystem>
+#include <fstream>
+#include <iomanip>
 #include <iostream>
+#include <optional>
+#include <sstream>
 #include <string>
 
 #include <boost/regex.hpp>
@@ -126,8 +142,14 @@ void download_tiles(const string type) {
     cout << "Requested " << request << " tiles. " << present << " files already present, " << loaded << " downloaded." << endl;
 }
 
-void first_pass(Config &config, boost::regex &remove_tag_regex, osmium::nwr_array<osmium::index::IdSetDense<osmium::unsigned_object_id_type>> &valid_ids, osmium::nwr_array<osmium::index::IdSetSmall<osmium::unsigned_object_id_type>> &no_elevation);
-void second_pass(Config &config, boost::regex &remove_tag_regex, osmium::nwr_array<osmium::index::IdSetDense<osmium::unsigned_object_id_type>> &valid_ids, osmium::nwr_array<osmium::index::IdSetSmall<osmium::unsigned_object_id_type>> &no_elevation);
+void first_pass(Config &config, boost::regex &remove_tag_regex, osmium::nwr_array<osmium::index::IdSetDense<osmium::unsigned_object_id_type>> &valid_ids, osmium::nwr_array<osmium::index::IdSetSmall<osmium::unsigned_object_id_type>> &no_elevation, way_tags_map &merged_way_tags, osmium::index::IdSetDense<osmium::unsigned_object_id_type> &protected_nodes, osmium::index::IdSetDense<osmium::unsigned_object_id_type> &way_nodes, osmium::index::IdSetDense<osmium::unsigned_object_id_type> &junction_nodes, osmium::index::IdSetDense<osmium::unsigned_object_id_type> &label_nodes, NodeDegrees &node_degrees);
+void load_services(const Config &config, LocationElevationService &location_elevation_service, LocationAreaService &location_area_service);
+std::string second_pass(Config &config, LocationElevationService &location_elevation_service, LocationAreaService &location_area_service, boost::regex &remove_tag_regex, osmium::nwr_array<osmium::index::IdSetDense<osmium::unsigned_object_id_type>> &valid_ids, osmium::nwr_array<osmium::index::IdSetSmall<osmium::unsigned_object_id_type>> &no_elevation, way_tags_map &merged_way_tags, const osmium::index::IdSetDense<osmium::unsigned_object_id_type> &protected_nodes, const osmium::index::IdSetDense<osmium::unsigned_object_id_type> &way_nodes, const osmium::index::IdSetDense<osmium::unsigned_object_id_type> &junction_nodes, const osmium::index::IdSetDense<osmium::unsigned_object_id_type> &label_nodes, const NodeDegrees &node_degrees);
+
+void load_accept_ids(const std::string &filename, osmium::nwr_array<osmium::index::IdSetDense<osmium::unsigned_object_id_type>> &valid_ids);
+void write_accept_ids(const std::string &filename, const osmium::nwr_array<osmium::index::IdSetDense<osmium::unsigned_object_id_type>> &valid_ids);
+
+void count_elements(const Config &config);
 
 int main(int argc, char **argv) {
     Config config;
@@ -144,13 +166,103 @@ int main(int argc, char **argv) {
             return 0;
         }
 
+        if (!config.config_sidecar.empty()) {
+            ofstream sidecar(config.config_sidecar);
+            if (!sidecar) {
+                cerr << "could not write config sidecar " << config.config_sidecar << '\n';
+                return (1);
+            }
+            config.write_json(sidecar);
+        }
+
+        if (config.prefetch > 0) {
+            // libosmium reads and decodes blocks on background threads, the queue sizes limit how far it reads ahead
+            const auto queue_size = to_string(config.prefetch);
+            setenv("OSMIUM_MAX_INPUT_QUEUE_SIZE", queue_size.c_str(), 1);
+            setenv("OSMIUM_MAX_OSMDATA_QUEUE_SIZE", queue_size.c_str(), 1);
+        }
+
+        if (!config.warnings_file.empty()) {
+            WarningLog::instance().open(config.warnings_file, config.warnings_limit);
+        }
+
+        if (config.count_only) {
+            count_elements(config);
+            return 0;
+        }
+
         boost::regex remove_tag_regex(config.remove_tag_regex_str, boost::regex::icase);
-        osmium::nwr_array<osmium::index::IdSetDense<osmium::unsigned_object_id_type>> valid_ids;
-        osmium::nwr_array<osmium::index::IdSetSmall<osmium::unsigned_object_id_type>> no_elevation;
 
-        first_pass(config, remove_tag_regex, valid_ids, no_elevation);
-        second_pass(config, remove_tag_regex, valid_ids, no_elevation);
+        // in summary only mode stdout is discarded until the summary line is printed
+        std::optional<QuietOutput> quiet_output;
+        if (config.summary_only) {
+            quiet_output.emplace();
+        }
+
+        // the geotiffs and the area mapping are loaded once and shared by all input files
+        const auto &bbox = config.area_mapping_grid_bbox;
+        LocationElevationService location_elevation_service(config.cache_limit, config.debug_mode);
+        LocationAreaService location_area_service(config.debug_mode, config.area_mapping_id_col, config.area_mapping_geo_col, config.area_mapping_geo_type, config.area_mapping_has_header, config.area_mapping_processed_file_prefix, GridBBox{bbox[0], bbox[1], bbox[2], bbox[3]});
+        if (!config.filter_only) {
+            load_services(config, location_elevation_service, location_area_service);
+        }
+
+        std::vector<std::string> summaries;
+        for (const auto &filename: config.filenames) {
+            config.filename = filename;
+            if (config.filenames.size() > 1) {
+                cout << "Input file " << filename << endl;
+            }
+            osmium::nwr_array<osmium::index::IdSetDense<osmium::unsigned_object_id_type>> valid_ids;
+            osmium::nwr_array<osmium::index::IdSetSmall<osmium::unsigned_object_id_type>> no_elevation;
+            way_tags_map merged_way_tags;
+            osmium::index::IdSetDense<osmium::unsigned_object_id_type> protected_nodes;
+            osmium::index::IdSetDense<osmium::unsigned_object_id_type> way_nodes;
+            osmium::index::IdSetDense<osmium::unsigned_object_id_type> junction_nodes;
+            // label and admin_centre member nodes of accepted relations
+            osmium::index::IdSetDense<osmium::unsigned_object_id_type> label_nodes;
+            NodeDegrees node_degrees(config.annotate_node_degree_max);
+
+            if (config.accept_ids_file.empty()) {
+                first_pass(config, remove_tag_regex, valid_ids, no_elevation, merged_way_tags, protected_nodes, way_nodes, junction_nodes, label_nodes, node_degrees);
+            } else {
+                load_accept_ids(config.accept_ids_file, valid_ids);
+            }
+            if (!config.export_accept_ids.empty()) {
+                write_accept_ids(config.export_accept_ids, valid_ids);
+            }
+            if (config.filter_only) {
+                cout << "Accepted nodes: " << valid_ids.nodes().size() << ", ways: " << valid_ids.ways().size()
+                     << ", relations: " << valid_ids.relations().size() << endl;
+                continue;
+            }
+            summaries.push_back(second_pass(config, location_elevation_service, location_area_service, remove_tag_regex, valid_ids, no_elevation, merged_way_tags, protected_nodes, way_nodes, junction_nodes, label_nodes, node_degrees));
+        }
         show_memory_used();
+        if (!config.summary_file.empty()) {
+            ofstream summary_file(config.summary_file);
+            for (const auto &summary: summaries) {
+                summary_file << summary << '\n';
+            }
+            if (!summary_file) {
+                throw runtime_error("could not write summary file " + config.summary_file);
+            }
+        }
+        if (WarningLog::instance().is_open()) {
+            cout << "Warnings written to " << config.warnings_file << ":";
+            for (const auto &[category, count]: WarningLog::instance().counts()) {
+                cout << " " << category << " " << count;
+            }
+            cout << endl;
+            WarningLog::instance().close();
+        }
+
+        if (quiet_output) {
+            quiet_output->restore();
+            for (const auto &summary: summaries) {
+                cout << summary << endl;
+            }
+        }
     } catch (const exception &e) {
         cerr << e.what() << '\n';
         return (3);
@@ -160,13 +272,62 @@ int main(int argc, char **argv) {
 
 void first_pass(Config &config, boost::regex &remove_tag_regex,
                 osmium::nwr_array<osmium::index::IdSetDense<osmium::unsigned_object_id_type>> &valid_ids,
-                osmium::nwr_array<osmium::index::IdSetSmall<osmium::unsigned_object_id_type>> &no_elevation) {
+                osmium::nwr_array<osmium::index::IdSetSmall<osmium::unsigned_object_id_type>> &no_elevation,
+                way_tags_map &merged_way_tags,
+                osmium::index::IdSetDense<osmium::unsigned_object_id_type> &protected_nodes,
+                osmium::index::IdSetDense<osmium::unsigned_object_id_type> &way_nodes,
+                osmium::index::IdSetDense<osmium::unsigned_object_id_type> &junction_nodes,
+                osmium::index::IdSetDense<osmium::unsigned_object_id_type> &label_nodes,
+                NodeDegrees &node_degrees) {
     cout << "Processing first pass: validate ways & relations..." << endl;
     auto start = chrono::steady_clock::now();
 
-    osmium::io::Reader reader{config.filename, osmium::osm_entity_bits::way | osmium::osm_entity_bits::relation,  osmium::io::read_meta::no};
-    osmium::ProgressBar progress{reader.file_size(), osmium::isatty(2)};
+    // metadata is only needed to filter by changeset or visibility
+    const auto read_meta = config.exclude_changesets.empty() && !config.drop_invisible ? osmium::io::read_meta::no : osmium::io::read_meta::yes;
+    osmium::io::Reader reader{InputFormat::file(config.filename), osmium::osm_entity_bits::way | osmium::osm_entity_bits::relation, read_meta};
+    osmium::ProgressBar progress{reader.file_size(), !config.summary_only && osmium::isatty(2)};
     FirstPassHandler handler(remove_tag_regex, valid_ids, no_elevation);
+    handler.exclude_changesets_ = config.exclude_changesets;
+    handler.merge_duplicate_ways_ = config.merge_duplicate_ways;
+    handler.drop_invisible_ = config.drop_invisible;
+    handler.drop_relation_member_ways_ = config.drop_relation_member_ways;
+    handler.max_relation_members_ = config.max_relation_members;
+    if (config.min_node_spacing > 0) {
+        handler.protected_nodes_ = &protected_nodes;
+    }
+    if (config.annotate_node_role) {
+        handler.way_nodes_ = &way_nodes;
+        handler.junction_nodes_ = &junction_nodes;
+    }
+    if (config.annotate_node_degree) {
+        handler.node_degrees_ = &node_degrees;
+    }
+    if (config.elevation_relation_labels) {
+        handler.label_nodes_ = &label_nodes;
+    }
+    handler.validate_refs_ = config.validate_refs;
+    if (config.interpolate) {
+        handler.generated_id_base_ = config.generated_id_base;
+    }
+#ifdef OSM_TRANSFORM_WITH_LUA
+    std::unique_ptr<ScriptFilter> script_filter;
+    if (!config.filter_script.empty()) {
+        script_filter = std::make_unique<ScriptFilter>(config.filter_script);
+        handler.element_filter_ = [&script_filter](const osmium::OSMObject &object) { return script_filter->accept(object); };
+    }
+#endif
+    if (!config.any_value_regex.empty()) {
+        const AnyTagValueFilter any_value_filter(config.any_value_regex, config.any_value_mode == "accept", config.any_value_entities);
+        handler.element_filter_ = [any_value_filter, script = std::move(handler.element_filter_)](const osmium::OSMObject &object) {
+            return any_value_filter.accept(object) && (!script || script(object));
+        };
+    }
+    if (!config.require_keys.empty()) {
+        const RequiredKeysFilter required_keys_filter(config.require_keys, config.require_keys_entities);
+        handler.element_filter_ = [required_keys_filter, other = std::move(handler.element_filter_)](const osmium::OSMObject &object) {
+            return required_keys_filter.accept(object) && (!other || other(object));
+        };
+    }
     while (osmium::memory::Buffer input_buffer = reader.read()) {
         osmium::apply(input_buffer, handler);
         progress.update(reader.offset());
@@ -176,114 +337,598 @@ void first_pass(Config &config, boost::regex &remove_tag_regex,
     reader.close();
 
     handler.printStats();
+    merged_way_tags = std::move(handler.merged_way_tags_);
+    if (config.annotate_node_degree) {
+        node_degrees.finish();
+        cout << "Nodes shared by several ways: " << node_degrees.shared_nodes() << endl;
+    }
 
     printf("Processed in %.3f s\n\n", chrono::duration_cast<chrono::milliseconds>(chrono::steady_clock::now() - start).count() / 1000.0);
 }
 
-void copy(const std::string& input, osmium::io::Writer& writer) {
+// reads ids in the format of osmium getid, e.g. n123, w456, r789, one per line, text after a space or # is ignored
+void load_accept_ids(const std::string &filename, osmium::nwr_array<osmium::index::IdSetDense<osmium::unsigned_object_id_type>> &valid_ids) {
+    cout << "Loading accepted ids from " << filename << "..." << endl;
+    ifstream in(filename);
+    read_accept_ids(in, filename, valid_ids);
+    cout << "Accepted nodes: " << valid_ids.nodes().size() << ", ways: " << valid_ids.ways().size()
+         << ", relations: " << valid_ids.relations().size() << endl << endl;
+}
+
+void write_accept_ids(const std::string &filename, const osmium::nwr_array<osmium::index::IdSetDense<osmium::unsigned_object_id_type>> &valid_ids) {
+    cout << "Writing accepted ids to " << filename << "..." << endl;
+    ofstream out(filename);
+    write_accept_ids(out, valid_ids);
+    if (!out) {
+        throw runtime_error("could not write " + filename);
+    }
+}
+
+// reads the input once without metadata and prints the number of elements per type
+void count_elements(const Config &config) {
+    const auto start = chrono::steady_clock::now();
+    osmium::nwr_array<unsigned long long> counts{};
+    osmium::io::Reader reader{InputFormat::file(config.filename), osmium::osm_entity_bits::nwr, osmium::io::read_meta::no};
+    osmium::ProgressBar progress{reader.file_size(), !config.summary_only && osmium::isatty(2)};
+    while (osmium::memory::Buffer buffer = reader.read()) {
+        for (const auto &object: buffer.select<osmium::OSMObject>()) {
+            counts(object.type())++;
+        }
+        progress.update(reader.offset());
+    }
+    progress.done();
+    reader.close();
+    if (config.summary_only) {
+        cout << "{\"nodes\": " << counts.nodes() << ", \"ways\": " << counts.ways() << ", \"relations\": " << counts.relations() << "}" << endl;
+        return;
+    }
+    cout << "Nodes: " << counts.nodes() << ", ways: " << counts.ways() << ", relations: " << counts.relations() << endl;
+    printf("Processed in %.3f s\n", chrono::duration_cast<chrono::milliseconds>(chrono::steady_clock::now() - start).count() / 1000.0);
+}
+
+// copies all elements except the given nodes
+void copy(const std::string& input, osmium::io::Writer& writer, OutputLimit& limit, bool show_progress,
+          const osmium::index::IdSetDense<osmium::unsigned_object_id_type> *dropped_nodes) {
     osmium::io::Reader reader{input};
-    osmium::ProgressBar progress{reader.file_size(), osmium::isatty(2)};
+    osmium::ProgressBar progress{reader.file_size(), show_progress && osmium::isatty(2)};
     while (osmium::memory::Buffer buffer = reader.read()) {
-        writer(std::move(buffer));
+        if (dropped_nodes != nullptr && !dropped_nodes->empty()) {
+            osmium::memory::Buffer kept{buffer.committed(), osmium::memory::Buffer::auto_grow::yes};
+            for (const auto &object: buffer.select<osmium::OSMObject>()) {
+                if (object.type() != osmium::item_type::node || !dropped_nodes->get(object.positive_id())) {
+                    kept.add_item(object);
+                    kept.commit();
+                }
+            }
+            buffer = std::move(kept);
+        }
+        writer(limit.apply(std::move(buffer)));
         progress.update(reader.offset());
     }
     reader.close();
 }
 
-void second_pass(Config &config, boost::regex &remove_tag_regex,
-                 osmium::nwr_array<osmium::index::IdSetDense<osmium::unsigned_object_id_type>> &valid_ids,
-                 osmium::nwr_array<osmium::index::IdSetSmall<osmium::unsigned_object_id_type>> &no_elevation) {
-    LocationElevationService location_elevation_service(config.cache_limit, config.debug_mode);
+// distributes the elements of the file over the shard files and removes it, returns the number of elements per shard
+std::vector<unsigned long long> split_file(const std::string &input, const std::vector<osmium::io::File> &shards, const std::size_t chunk_size,
+                                           const ShardedWriter::boundary_way_policy policy) {
+    osmium::io::Reader reader{input};
+    ShardedWriter writer{shards, reader.header(), chunk_size};
+    writer.boundary_way_policy_ = policy;
+    while (osmium::memory::Buffer buffer = reader.read()) {
+        writer(std::move(buffer));
+    }
+    reader.close();
+    writer.close();
+    std::remove(input.c_str());
+    if (policy != ShardedWriter::boundary_way_policy::none) {
+        cout << "Ways crossing shard boundaries: " << writer.boundary_ways_ << endl;
+    }
+    return writer.counts();
+}
+
+// loads the geotiffs and the area mapping, the extent of the geotiffs defaults to the bounding boxes of all input files
+void load_services(const Config &config, LocationElevationService &location_elevation_service, LocationAreaService &location_area_service) {
+    location_elevation_service.open_retries_ = config.tiff_open_retries;
+    location_elevation_service.open_backoff_ms_ = config.tiff_open_backoff;
+    location_elevation_service.strict_loading_ = config.tiff_open_strict;
+    location_elevation_service.strict_indexing_ = config.elevation_strict_indexing;
+    location_elevation_service.pixel_cache_limit_ = config.pixel_cache_limit;
+    location_elevation_service.show_progress_ = !config.summary_only;
+    location_elevation_service.split_distance_ = config.elevation_split_distance;
+    location_elevation_service.tiff_priority_ = config.elevation_tiff_priority;
+    location_elevation_service.seam_blend_ = config.elevation_seam_blend;
+    location_elevation_service.scale_ = config.elevation_scale;
+    location_elevation_service.offset_ = config.elevation_offset;
     if (config.add_elevation) {
         auto start = chrono::steady_clock::now();
+        // the header bounding box is not used, extracts with complete ways or relations have nodes outside of it
+        if (!config.elevation_bbox_str.empty()) {
+            const auto &e = config.elevation_bbox;
+            location_elevation_service.extent_ = osmium::Box(e[0], e[1], e[2], e[3]);
+        }
         location_elevation_service.load(config.geo_tiff_folders);
         printf("Processed in %.3f s\n\n", chrono::duration_cast<chrono::milliseconds>(chrono::steady_clock::now() - start).count() / 1000.0);
     }
 
-    LocationAreaService location_area_service(config.debug_mode, config.area_mapping_id_col, config.area_mapping_geo_col, config.area_mapping_geo_type, config.area_mapping_has_header, config.area_mapping_processed_file_prefix);
+    location_area_service.rebuild_on_mismatch_ = config.area_mapping_rebuild_on_mismatch;
+    location_area_service.verify_index_ = config.area_mapping_verify_index;
+    location_area_service.compress_ = config.area_mapping_compress_index;
+    location_area_service.progress_interval_ = config.area_mapping_progress_interval;
+    location_area_service.index_threads_ = config.area_mapping_index_threads;
     if (!config.area_mapping.empty()) {
         auto start = chrono::steady_clock::now();
         location_area_service.load(config.area_mapping);
         printf("Processed in %.3f s\n\n", chrono::duration_cast<chrono::milliseconds>(chrono::steady_clock::now() - start).count() / 1000.0);
     }
+}
+
+std::string second_pass(Config &config, LocationElevationService &location_elevation_service, LocationAreaService &location_area_service,
+                        boost::regex &remove_tag_regex,
+                        osmium::nwr_array<osmium::index::IdSetDense<osmium::unsigned_object_id_type>> &valid_ids,
+                        osmium::nwr_array<osmium::index::IdSetSmall<osmium::unsigned_object_id_type>> &no_elevation,
+                        way_tags_map &merged_way_tags,
+                        const osmium::index::IdSetDense<osmium::unsigned_object_id_type> &protected_nodes,
+                        const osmium::index::IdSetDense<osmium::unsigned_object_id_type> &way_nodes,
+                        const osmium::index::IdSetDense<osmium::unsigned_object_id_type> &junction_nodes,
+                        const osmium::index::IdSetDense<osmium::unsigned_object_id_type> &label_nodes,
+                        const NodeDegrees &node_degrees) {
+    // the services are shared by all input files, their counters are printed per file
+    location_elevation_service.found_custom_ = 0;
+    location_elevation_service.found_srtm_ = 0;
+    location_elevation_service.found_gmted_ = 0;
+    location_elevation_service.seam_blends_ = 0;
+    location_area_service.locations_outside_grid_ = 0;
 
     const auto& map_factory = osmium::index::MapFactory<osmium::unsigned_object_id_type, osmium::Location>::instance();
-    auto location_index = map_factory.create_map(config.index_type);
+    std::unique_ptr<osmium::index::map::Map<osmium::unsigned_object_id_type, osmium::Location>> location_index;
+    if (config.node_cache_spill_dir.empty()) {
+        location_index = map_factory.create_map(config.index_type);
+    } else {
+        // a memory mapped file, pages are written to disk by the kernel when memory gets short
+        const auto index_file = std::filesystem::path(config.node_cache_spill_dir) / ("osm-transform-locations-" + to_string(getpid()) + ".idx");
+        std::filesystem::remove(index_file);
+        location_index = map_factory.create_map("sparse_file_array," + index_file.string());
+        // the file stays accessible through the open descriptor and is deleted when the index is closed
+        std::filesystem::remove(index_file);
+    }
 
-    auto output = remove_extension(std::filesystem::path(config.filename.c_str()).stem()) + ".ors.pbf";
+    // output files are named after the input file, in the output_dir if given
+    const auto output_path = [&config](const std::string &suffix) {
+        const auto name = remove_extension(std::filesystem::path(config.filename.c_str()).stem()) + suffix;
+        return config.output_dir.empty() ? name : (std::filesystem::path(config.output_dir) / name).string();
+    };
+    auto output = output_path(".ors.pbf");
     const auto total_elements = valid_ids.nodes().size() + valid_ids.ways().size() + valid_ids.relations().size();
     unsigned long long processed_elements = 0;
 
     const auto start = chrono::steady_clock::now();
     cout << "Processing second pass: rebuild data..." << endl;
-    osmium::io::Reader reader{config.filename, osmium::osm_entity_bits::node | osmium::osm_entity_bits::way | osmium::osm_entity_bits::relation, osmium::io::read_meta::no};
+    auto keep_metadata = osmium::osm_entity_bits::nothing;
+    if (!config.remove_metadata_from.contains("node")) keep_metadata |= osmium::osm_entity_bits::node;
+    if (!config.remove_metadata_from.contains("way")) keep_metadata |= osmium::osm_entity_bits::way;
+    if (!config.remove_metadata_from.contains("relation")) keep_metadata |= osmium::osm_entity_bits::relation;
+    const auto read_meta = config.exclude_changesets.empty() && !config.drop_invisible && keep_metadata == osmium::osm_entity_bits::nothing && config.keep_metadata_fields.none() ? osmium::io::read_meta::no : osmium::io::read_meta::yes;
+    osmium::io::Reader reader{InputFormat::file(config.filename), osmium::osm_entity_bits::node | osmium::osm_entity_bits::way | osmium::osm_entity_bits::relation, read_meta};
 
     // keep existing headers including osm data dates
     osmium::io::Header header(reader.header());
-    header.set("generator", "osm-transform_ v0.1.0");
+    header.set("generator", std::string(PROJECT_NAME) + " v" + PROJECT_VERSION);
+    if (!config.output_source.empty()) {
+        header.set("source", config.output_source);
+    }
 
-    RewriteHandler handler(1000000000, location_index, location_elevation_service, location_area_service, remove_tag_regex, valid_ids, no_elevation, config.interpolate, config.interpolate_threshold);
+    RewriteHandler handler(config.generated_id_base, location_index, location_elevation_service, location_area_service, remove_tag_regex, valid_ids, no_elevation, config.interpolate, config.interpolate_threshold);
     handler.add_elevation_ = config.add_elevation;
+    handler.normalize_oneway_direction_ = config.normalize_oneway_direction;
+    handler.exclude_changesets_ = config.exclude_changesets;
+    handler.drop_invisible_ = config.drop_invisible;
+    handler.min_node_spacing_ = config.min_node_spacing;
+    handler.decimal_separator_ = config.decimal_separator[0];
+    handler.elevation_only_tagged_ = config.elevation_only_tagged;
+    handler.normalize_ele_units_ = config.normalize_ele_units;
+    handler.annotate_tag_hash_ = config.annotate_tag_hash;
+    handler.min_way_length_ = config.min_way_length;
+    handler.tag_elevation_resolution_ = config.tag_elevation_resolution;
+    handler.tag_country_confidence_ = config.tag_country_confidence;
+    handler.tag_elevation_status_ = config.tag_elevation_status;
+    handler.debug_elevation_raw_ = config.debug_elevation_raw;
+    handler.annotate_way_grade_ = config.annotate_way_grade;
+    handler.annotate_sinuosity_ = config.annotate_sinuosity;
+    handler.annotate_bearing_ = config.annotate_bearing;
+    handler.normalize_winding_ = config.normalize_winding;
+    handler.lift_node_tags_ = config.lift_node_tags;
+    handler.annotate_node_count_ = config.annotate_node_count;
+    if (config.split_node_tag_mode == "marker") {
+        handler.split_node_tags_ = RewriteHandler::split_node_tags::marker;
+    } else if (config.split_node_tag_mode == "none") {
+        handler.split_node_tags_ = RewriteHandler::split_node_tags::none;
+    }
+    handler.count_stripped_elements_ = config.report_stripped_elements;
+    if (config.dedup_tags) {
+        handler.dedup_tags_ = config.dedup_tags_keep == "last" ? RewriteHandler::dedup_tags_mode::last : RewriteHandler::dedup_tags_mode::first;
+    }
+    if (config.invalid_coordinate_mode == "drop") {
+        handler.invalid_coordinate_mode_ = RewriteHandler::invalid_coordinate_mode::drop;
+    } else if (config.invalid_coordinate_mode == "clamp") {
+        handler.invalid_coordinate_mode_ = RewriteHandler::invalid_coordinate_mode::clamp;
+    }
+    Connectivity connectivity;
+    if (config.connectivity_report) {
+        handler.connectivity_ = &connectivity;
+    }
+    RelationRoles relation_roles;
+    if (config.relation_role_report) {
+        handler.relation_roles_ = &relation_roles;
+    }
+    TagKeyTypos tag_key_typos;
+    if (config.report_tag_typos) {
+        handler.tag_key_typos_ = &tag_key_typos;
+    }
+    ElevationHistogram elevation_histogram(config.elevation_histogram_bin);
+    if (config.elevation_histogram || !config.elevation_histogram_csv.empty()) {
+        handler.elevation_histogram_ = &elevation_histogram;
+    }
+    UnitNormalizer unit_normalizer(config.normalize_units_keys);
+    if (config.normalize_units) {
+        handler.unit_normalizer_ = &unit_normalizer;
+    }
+    TagKeyCaseNormalizer key_case_normalizer(config.lowercase_keys_keys, config.lowercase_keys_exclude);
+    if (config.lowercase_keys) {
+        handler.key_case_normalizer_ = &key_case_normalizer;
+    }
+    const auto &bbox = config.area_mapping_grid_bbox;
+    TileDensity tile_density{GridBBox{bbox[0], bbox[1], bbox[2], bbox[3]}};
+    if (!config.tile_density_csv.empty()) {
+        handler.tile_density_ = &tile_density;
+    }
+    WayIndex way_index;
+    if (!config.export_way_index.empty()) {
+        handler.way_index_ = &way_index;
+    }
+    handler.count_nodes_by_resolution_ = config.debug_mode && config.interpolate;
+    CountryAttributes country_attributes;
+    if (!config.country_attributes.empty()) {
+        country_attributes.load(config.country_attributes);
+        for (const auto &name: config.country_attribute_names) {
+            const auto index = country_attributes.index(name);
+            if (index < 0) {
+                throw runtime_error("attribute " + name + " not found in " + config.country_attributes);
+            }
+            handler.country_attribute_indices_.push_back(index);
+        }
+        if (config.country_attribute_names.empty()) {
+            for (int index = 0; index < static_cast<int>(country_attributes.names().size()); index++) {
+                handler.country_attribute_indices_.push_back(index);
+            }
+        }
+        handler.country_attributes_ = &country_attributes;
+        cout << "Loaded attributes of " << country_attributes.size() << " countries" << endl;
+    }
+    handler.ele_default_unit_factor_ = config.ele_default_unit == "ft" ? 0.3048 : 1.0;
+    handler.split_interpolated_ = config.elevation_profile_mode != "annotate";
+    handler.annotate_elevation_profile_ = config.elevation_profile_mode != "split";
+    handler.split_distances_by_highway_.insert(config.elevation_split_distances_by_highway.begin(), config.elevation_split_distances_by_highway.end());
+    if (config.annotate_highway_rank) {
+        handler.highway_ranks_ = RewriteHandler::default_highway_ranks();
+        for (const auto &[value, rank]: config.highway_ranks) {
+            handler.highway_ranks_[value] = rank;
+        }
+    }
+    handler.protected_nodes_ = &protected_nodes;
+    if (config.annotate_node_role) {
+        handler.way_nodes_ = &way_nodes;
+        handler.junction_nodes_ = &junction_nodes;
+    }
+    if (config.annotate_node_degree) {
+        handler.node_degrees_ = &node_degrees;
+    }
+    if (config.elevation_relation_labels) {
+        handler.label_nodes_ = &label_nodes;
+    }
+    handler.validate_refs_ = config.validate_refs;
+#ifdef OSM_TRANSFORM_WITH_LUA
+    std::unique_ptr<ScriptFilter> script_filter;
+    if (!config.filter_script.empty()) {
+        script_filter = std::make_unique<ScriptFilter>(config.filter_script);
+        handler.element_filter_ = [&script_filter](const osmium::OSMObject &object) { return script_filter->accept(object); };
+    }
+#endif
+    if (!config.any_value_regex.empty()) {
+        const AnyTagValueFilter any_value_filter(config.any_value_regex, config.any_value_mode == "accept", config.any_value_entities);
+        handler.element_filter_ = [any_value_filter, script = std::move(handler.element_filter_)](const osmium::OSMObject &object) {
+            return any_value_filter.accept(object) && (!script || script(object));
+        };
+    }
+    if (!config.require_keys.empty()) {
+        const RequiredKeysFilter required_keys_filter(config.require_keys, config.require_keys_entities);
+        handler.element_filter_ = [required_keys_filter, other = std::move(handler.element_filter_)](const osmium::OSMObject &object) {
+            return required_keys_filter.accept(object) && (!other || other(object));
+        };
+    }
+    handler.max_way_nodes_ = config.max_way_nodes;
+    handler.output_only_modified_ = config.output_only_modified;
+    handler.annotate_utm_zone_ = config.annotate_utm_zone;
+    handler.keep_metadata_ = keep_metadata;
+    handler.keep_metadata_fields_ = config.keep_metadata_fields;
+    handler.normalize_multivalue_keys_.insert(config.normalize_multivalue_keys.begin(), config.normalize_multivalue_keys.end());
+    std::ofstream elevation_csv;
+    if (config.add_elevation && !config.elevation_csv.empty()) {
+        elevation_csv.open(config.elevation_csv);
+        if (!elevation_csv.is_open()) {
+            throw std::runtime_error("could not open elevation csv file " + config.elevation_csv);
+        }
+        elevation_csv << std::fixed << std::setprecision(7) << "id,lon,lat,ele\n";
+        handler.elevation_csv_ = &elevation_csv;
+        handler.ele_tag_ = !config.no_ele_tag;
+    }
+    handler.preserve_ele_key_ = config.preserve_original_ele_as;
+    std::ofstream country_points_csv;
+    if (!config.country_points_csv.empty()) {
+        country_points_csv.open(config.country_points_csv);
+        if (!country_points_csv.is_open()) {
+            throw std::runtime_error("could not open country points csv file " + config.country_points_csv);
+        }
+        country_points_csv << std::fixed << std::setprecision(7) << "id,lon,lat,country\n";
+        handler.country_points_csv_ = &country_points_csv;
+    }
+    if (config.merge_duplicate_ways) {
+        handler.merged_way_tags_ = &merged_way_tags;
+    }
+    NodeElevationCache node_cache(config.filename, location_elevation_service.settings());
+    bool node_cache_loaded = false;
+    if (config.add_elevation && !config.node_cache_file.empty()) {
+        node_cache_loaded = node_cache.load(config.node_cache_file);
+        if (node_cache_loaded) {
+            std::cout << "Loaded " << node_cache.size() << " node elevations from " << config.node_cache_file << std::endl;
+        }
+        handler.node_cache_ = &node_cache;
+    }
 
-    if (config.interpolate) {
-        auto wr_output = remove_extension(std::filesystem::path(config.filename.c_str()).stem()) + ".ors.wr.pbf";
-        osmium::io::Writer wr_writer{wr_output, header, osmium::io::overwrite::allow};
-        const auto n_output = remove_extension(std::filesystem::path(config.filename.c_str()).stem()) + ".ors.n.pbf";
-        osmium::io::Writer n_writer{n_output, header, osmium::io::overwrite::allow};
-        osmium::ProgressBar progress{total_elements, osmium::isatty(2)};
+    const auto output_file = [&config](const std::string &filename) {
+        osmium::io::File file{filename};
+        if (config.output_raw) {
+            file.set("pbf_compression", "none");
+        }
+        return file;
+    };
+
+    // the nodes, ways and relations are written in this order, so the limit drops the last elements of the output
+    OutputLimit output_limit{config.max_output_elements, config.max_output_elements_per_type};
+    // nodes dropped by min_node_spacing are only known after the ways, so the nodes are written to a file of their own
+    if (config.interpolate || config.min_node_spacing > 0) {
+        auto wr_output = output_path(".ors.wr.pbf");
+        osmium::io::Writer wr_writer{output_file(wr_output), header, osmium::io::overwrite::allow};
+        BatchedWriter wr_batch{wr_writer, config.output_batch_size};
+        const auto n_output = output_path(".ors.n.pbf");
+        osmium::io::Writer n_writer{output_file(n_output), header, osmium::io::overwrite::allow};
+        BatchedWriter n_batch{n_writer, config.output_batch_size};
+        osmium::ProgressBar progress{total_elements, !config.summary_only && osmium::isatty(2)};
         while (auto input_buffer = reader.read()) {
             osmium::memory::Buffer output_buffer{input_buffer.committed()};
             osmium::memory::Buffer node_output_buffer{input_buffer.committed()};
             handler.set_buffers(&output_buffer, &node_output_buffer);
 
             osmium::apply(input_buffer, handler);
-            wr_writer(std::move(output_buffer));
-            n_writer(std::move(node_output_buffer));
+            wr_batch(std::move(output_buffer));
+            n_batch(std::move(node_output_buffer));
 
             processed_elements += handler.processed_elements_;
             progress.update(processed_elements);
         }
+        n_batch.flush();
+        wr_batch.flush();
         n_writer.close();
         wr_writer.close();
         progress.done();
         reader.close();
 
-        osmium::io::Writer writer{output, header, osmium::io::overwrite::allow};
-        copy(n_output, writer);
+        osmium::io::Writer writer{output_file(output), header, osmium::io::overwrite::allow};
+        copy(n_output, writer, output_limit, !config.summary_only, config.min_node_spacing > 0 ? &handler.dropped_nodes() : nullptr);
         std::remove(n_output.c_str());
-        copy(wr_output, writer);
+        copy(wr_output, writer, output_limit, !config.summary_only, nullptr);
         std::remove(wr_output.c_str());
         writer.close();
     } else {
-        osmium::io::Writer writer{output, header, osmium::io::overwrite::allow};
-        osmium::ProgressBar progress{total_elements, osmium::isatty(2)};
+        osmium::io::Writer writer{output_file(output), header, osmium::io::overwrite::allow};
+        BatchedWriter batch{writer, config.output_batch_size};
+        osmium::ProgressBar progress{total_elements, !config.summary_only && osmium::isatty(2)};
         while (auto input_buffer = reader.read()) {
             osmium::memory::Buffer output_buffer{input_buffer.committed()};
             handler.set_buffers(&output_buffer, &output_buffer);
 
             osmium::apply(input_buffer, handler);
-            writer(std::move(output_buffer));
+            batch(output_limit.apply(std::move(output_buffer)));
 
             processed_elements += handler.processed_elements_;
             progress.update(processed_elements);
         }
+        batch.flush();
         writer.close();
         progress.done();
         reader.close();
     }
 
+    // the boundary way policies look up the shards of the nodes by id, so unsorted output is sorted first
+    const bool sort_for_shards = config.output_shards > 0 && config.boundary_way_policy != "none" && header.get("sorting") != "Type_then_ID";
+    if (config.sort_output || sort_for_shards || config.output_order == "relations-first") {
+        cout << "Reordering output..." << endl;
+        reorder_file(output_file(output), header, config.sort_output || sort_for_shards, config.output_order == "relations-first");
+    }
+    std::vector<std::string> output_files{output};
+    if (config.output_shards > 0) {
+        cout << "Splitting output into " << config.output_shards << " shards..." << endl;
+        output_files.clear();
+        std::vector<osmium::io::File> shard_files;
+        for (std::size_t i = 0; i < config.output_shards; i++) {
+            output_files.push_back(ShardedWriter::shard_path(output, i));
+            shard_files.push_back(output_file(output_files.back()));
+        }
+        auto policy = ShardedWriter::boundary_way_policy::none;
+        if (config.boundary_way_policy == "duplicate") {
+            policy = ShardedWriter::boundary_way_policy::duplicate;
+        } else if (config.boundary_way_policy == "assign-to-first") {
+            policy = ShardedWriter::boundary_way_policy::assign_to_first;
+        } else if (config.boundary_way_policy == "clip") {
+            policy = ShardedWriter::boundary_way_policy::clip;
+        }
+        const auto counts = split_file(output, shard_files, config.output_shard_chunk_size, policy);
+        const auto [min, max] = std::minmax_element(counts.begin(), counts.end());
+        cout << "Elements per shard: " << *min << " to " << *max << endl;
+    }
+    if (elevation_csv.is_open()) {
+        elevation_csv.close();
+    }
+    if (country_points_csv.is_open()) {
+        country_points_csv.close();
+    }
+    handler.printPassStats();
+    if (output_limit.enabled()) {
+        std::cout << "Elements not written due to the output limit: " << output_limit.dropped_ << std::endl;
+    }
+    if (handler.node_cache_ != nullptr) {
+        if (!node_cache_loaded) {
+            node_cache.save(config.node_cache_file);
+            std::cout << "Saved " << node_cache.size() << " node elevations to " << config.node_cache_file << std::endl;
+        } else {
+            std::cout << "Node elevations reused from cache: " << node_cache.hits_ << std::endl;
+        }
+    }
+
     if (config.debug_mode)  {
         const auto mem = location_index->used_memory() / (1024UL );
         std::cout << "About " << mem << " KBytes used for node location index (in main memory or on disk).\n";
     }
 
     handler.printCountryStats();
+    if (location_area_service.locations_outside_grid_ > 0) {
+        std::cout << "Nodes outside of the area mapping grid: " << location_area_service.locations_outside_grid_ << std::endl;
+    }
+    if (handler.output_bbox_.valid()) {
+        const auto &extent = handler.output_bbox_;
+        printf("Output extent: %.7f,%.7f,%.7f,%.7f (min_lon,min_lat,max_lon,max_lat)\n",
+               extent.bottom_left().lon(), extent.bottom_left().lat(), extent.top_right().lon(), extent.top_right().lat());
+    }
+    if (config.normalize_oneway_direction) {
+        std::cout << "Ways with normalized oneway direction: " << handler.ways_with_normalized_oneway_ << std::endl;
+    }
+    if (config.max_way_nodes > 0) {
+        std::cout << "Ways split by node count: " << handler.ways_split_by_length_ << ", added ways: " << handler.ways_added_by_length_split_ << std::endl;
+    }
+    if (config.output_only_modified) {
+        std::cout << "Unmodified elements skipped: " << handler.unmodified_elements_skipped_ << std::endl;
+    }
+    if (!config.exclude_changesets.empty()) {
+        std::cout << "Nodes excluded by changeset: " << handler.nodes_excluded_by_changeset_ << std::endl;
+    }
+    if (config.drop_invisible) {
+        std::cout << "Invisible nodes dropped: " << handler.invisible_nodes_ << std::endl;
+    }
+    if (config.interpolate && config.elevation_profile_mode != "split") {
+        std::cout << "Ways with elevation profile: " << handler.ways_with_elevation_profile_ << ", truncated: " << handler.truncated_elevation_profiles_ << std::endl;
+    }
+    if (!config.preserve_original_ele_as.empty()) {
+        std::cout << "Original ele tags preserved as " << config.preserve_original_ele_as << ": " << handler.ele_tags_preserved_ << std::endl;
+    }
+    if (config.normalize_ele_units) {
+        std::cout << "Normalized ele tags: " << handler.ele_tags_normalized_ << ", unparsable: " << handler.ele_tags_unparsable_ << std::endl;
+    }
+    if (config.annotate_highway_rank) {
+        std::cout << "Ways with highway rank: " << handler.ways_with_highway_rank_ << std::endl;
+    }
+    if (handler.invalid_coordinates_ > 0) {
+        std::cout << "Nodes with invalid coordinates (" << config.invalid_coordinate_mode << "): " << handler.invalid_coordinates_ << std::endl;
+    }
+    if (config.connectivity_report) {
+        const auto largest = connectivity.largest();
+        printf("Connected components: %llu, largest: %llu of %llu ways (%.2f %%)\n", static_cast<unsigned long long>(connectivity.components()),
+               static_cast<unsigned long long>(largest), static_cast<unsigned long long>(connectivity.ways()),
+               connectivity.ways() > 0 ? static_cast<double>(largest) / static_cast<double>(connectivity.ways()) * 100 : 0.0);
+    }
+    if (config.relation_role_report) {
+        std::cout << "Relation types: " << relation_roles.size() << std::endl;
+        relation_roles.print(std::cout);
+    }
+    if (config.report_tag_typos) {
+        std::cout << "Tag keys similar to common keys: " << tag_key_typos.size() << std::endl;
+        tag_key_typos.print(std::cout, 50);
+    }
+    if (config.elevation_histogram) {
+        std::cout << "Elevation histogram, " << config.elevation_histogram_bin << " m bins, nodes: " << elevation_histogram.count() << std::endl;
+        elevation_histogram.print(std::cout);
+    }
+    if (!config.elevation_histogram_csv.empty()) {
+        std::ofstream elevation_histogram_csv(config.elevation_histogram_csv);
+        if (!elevation_histogram_csv.is_open()) {
+            throw std::runtime_error("could not open elevation histogram csv file " + config.elevation_histogram_csv);
+        }
+        elevation_histogram.write_csv(elevation_histogram_csv);
+        std::cout << "Elevation bins with nodes: " << elevation_histogram.size() << ", written to " << config.elevation_histogram_csv << std::endl;
+    }
+    if (!config.tile_density_csv.empty()) {
+        std::ofstream tile_density_csv(config.tile_density_csv);
+        if (!tile_density_csv.is_open()) {
+            throw std::runtime_error("could not open tile density csv file " + config.tile_density_csv);
+        }
+        tile_density.write_csv(tile_density_csv);
+        std::cout << "Tiles with elements: " << tile_density.size() << ", written to " << config.tile_density_csv << std::endl;
+    }
+    if (!config.export_way_index.empty()) {
+        std::ofstream way_index_file(config.export_way_index, std::ios::binary | std::ios::trunc);
+        if (!way_index_file.is_open()) {
+            throw std::runtime_error("could not open way index file " + config.export_way_index);
+        }
+        way_index.write(way_index_file);
+        std::cout << "Ways in way index: " << way_index.size() << ", written to " << config.export_way_index << std::endl;
+    }
+    if (!config.lift_node_tags.empty()) {
+        std::cout << "Ways with tags lifted from nodes: " << handler.ways_with_lifted_tags_ << std::endl;
+    }
+    if (config.normalize_winding) {
+        std::cout << "Ways with reversed winding: " << handler.ways_with_reversed_winding_ << std::endl;
+    }
+    if (config.annotate_way_grade) {
+        std::cout << "Ways with grade: " << handler.ways_with_grade_ << std::endl;
+    }
+    if (config.annotate_sinuosity) {
+        std::cout << "Ways with sinuosity: " << handler.ways_with_sinuosity_ << std::endl;
+    }
+    if (config.annotate_bearing) {
+        std::cout << "Ways with bearing: " << handler.ways_with_bearing_ << std::endl;
+    }
+    if (config.min_way_length > 0) {
+        std::cout << "Ways dropped by min way length: " << handler.ways_dropped_by_length_ << std::endl;
+    }
+    if (config.min_node_spacing > 0) {
+        std::cout << "Nodes dropped by min node spacing: " << handler.nodes_dropped_by_spacing_ << std::endl;
+    }
+    if (handler.element_filter_) {
+        std::cout << "Nodes rejected by filter: " << handler.nodes_rejected_by_filter_ << std::endl;
+    }
+    if (!config.normalize_multivalue_keys.empty()) {
+        std::cout << "Normalized multi-value tags: " << handler.multi_values_normalized_ << std::endl;
+    }
+    if (config.normalize_units) {
+        std::cout << "Tags converted to metric units: " << unit_normalizer.conversions() << std::endl;
+    }
+    if (config.lowercase_keys) {
+        std::cout << "Tag keys lowercased: " << key_case_normalizer.keys_lowercased() << ", tags merged: " << key_case_normalizer.tags_merged() << std::endl;
+    }
+    if (config.dedup_tags) {
+        std::cout << "Duplicate tags removed: " << handler.duplicate_tags_removed_ << std::endl;
+    }
+    if (config.report_stripped_elements) {
+        std::cout << "Ways without tags after removing tags: " << handler.ways_stripped_of_tags_ << std::endl;
+        std::cout << "Relations without tags after removing tags: " << handler.relations_stripped_of_tags_ << std::endl;
+    }
 
     const auto end = chrono::steady_clock::now();
     printf("Processed in %.3f s\n", chrono::duration_cast<chrono::milliseconds>(end - start).count() / 1000.0);
 
     const auto insize = std::filesystem::file_size(config.filename);
-    const auto outsize = std::filesystem::file_size(output);
+    std::uintmax_t outsize = 0;
+    for (const auto &file: output_files) {
+        outsize += std::filesystem::file_size(file);
+    }
     const auto reduction = insize - outsize;
     printf("\nOriginal: %20ju b\nReduced: %21lu b\nReduction: %19ju b (= %3.2f %%)\n", insize, outsize,
            reduction, static_cast<float>(reduction) / static_cast<float>(insize) * 100);
@@ -292,6 +937,9 @@ void second_pass(Config &config, boost::regex &remove_tag_regex,
         printf("All Nodes: %19lu Nodes\n", valid_nodes);
         if (config.interpolate) {
             printf("Added Nodes: %17llu Nodes\n",handler.nodes_added_by_interpolation_);
+            for (const auto &[resolution, count]: handler.nodes_added_by_resolution_) {
+                printf("  from %5ld m tiffs: %9llu Nodes\n", resolution, count);
+            }
         }
         printf("Elevation found: %13.2f %% (%llu)\n",
                static_cast<double>(handler.nodes_with_elevation_) /
@@ -305,13 +953,63 @@ void second_pass(Config &config, boost::regex &remove_tag_regex,
         printf("GMTED Elevation: %13.2f %% (%llu)\n",
                static_cast<double>(location_elevation_service.found_gmted_) /
                        static_cast<double>(valid_nodes) * 100, location_elevation_service.found_gmted_);
+        if (config.pixel_cache_limit > 0) {
+            printf("Pixel cache hits: %12llu\n", location_elevation_service.pixel_cache_hits());
+        }
+        if (location_elevation_service.failed_tiff_loads_ > 0) {
+            printf("Failed geotiff loads: %8llu\n", location_elevation_service.failed_tiff_loads_);
+        }
+        if (config.elevation_seam_blend) {
+            printf("Blended at seams: %12llu\n", location_elevation_service.seam_blends_);
+        }
+        if (config.elevation_only_tagged) {
+            printf("Untagged skipped: %12.2f %% (%llu)\n",
+                   static_cast<double>(handler.untagged_nodes_without_elevation_) /
+                           static_cast<double>(valid_nodes) * 100, handler.untagged_nodes_without_elevation_);
+        }
+        if (config.elevation_relation_labels) {
+            printf("Relation labels: %13llu of %llu with elevation\n", handler.label_nodes_with_elevation_, static_cast<unsigned long long>(label_nodes.size()));
+        }
         printf("Failed Elevation: %12.2f %% (%llu)\n",
                static_cast<double>(handler.nodes_with_elevation_not_found_) /
                        static_cast<double>(valid_nodes) * 100, handler.nodes_with_elevation_not_found_);
-        if (valid_nodes > handler.nodes_with_elevation_ + handler.nodes_with_elevation_not_found_) {
+        if (valid_nodes > handler.nodes_with_elevation_ + handler.nodes_with_elevation_not_found_ + handler.untagged_nodes_without_elevation_) {
             std::cout << "\nNotice: More nodes were referenced in ways & relations than were found in the data. This typically happens\n"
                          "with OSM extracts with nodes omitted for ways & relations extending beyond the extent of the extract.\n";
         }
     }
     cout << endl;
+
+    ostringstream summary;
+    summary << "{\"input\": " << json_string(config.filename)
+            << ", \"output\": " << json_string(output)
+            << ", \"input_size\": " << insize
+            << ", \"output_size\": " << outsize
+            << ", \"nodes\": " << handler.accepted_nodes_
+            << ", \"ways\": " << handler.accepted_ways_
+            << ", \"relations\": " << handler.accepted_relations_
+            << ", \"nodes_added_by_interpolation\": " << handler.nodes_added_by_interpolation_
+            << ", \"nodes_with_elevation\": " << handler.nodes_with_elevation_
+            << ", \"nodes_with_elevation_not_found\": " << handler.nodes_with_elevation_not_found_
+            << ", \"node_ids\": [" << handler.min_ids_[0] << ", " << handler.max_ids_[0] << "]"
+            << ", \"way_ids\": [" << handler.min_ids_[1] << ", " << handler.max_ids_[1] << "]"
+            << ", \"relation_ids\": [" << handler.min_ids_[2] << ", " << handler.max_ids_[2] << "]";
+    if (config.output_shards > 0) {
+        summary << ", \"output_shards\": " << json_array(output_files);
+    }
+    if (config.report_stripped_elements) {
+        summary << ", \"ways_stripped_of_tags\": " << handler.ways_stripped_of_tags_
+                << ", \"relations_stripped_of_tags\": " << handler.relations_stripped_of_tags_;
+    }
+    summary << ", \"bbox\": ";
+    if (handler.output_bbox_.valid()) {
+        const auto &extent = handler.output_bbox_;
+        summary << "[" << setprecision(10) << extent.bottom_left().lon() << ", " << extent.bottom_left().lat() << ", "
+                << extent.top_right().lon() << ", " << extent.top_right().lat() << "]";
+    } else {
+        summary << "null";
+    }
+    summary << ", \"seconds\": " << fixed << setprecision(3) << chrono::duration_cast<chrono::milliseconds>(end - start).count() / 1000.0
+            << "}";
+    return summary.str();
 }
@@ -0,0 +1,59 @@
+#ifndef OSM_TRANSFORM_OUTPUT_LIMIT_H
+#define OSM_TRANSFORM_OUTPUT_LIMIT_H
+
+#include <array>
+#include <cstddef>
+#include <utility>
+
+#include <osmium/memory/buffer.hpp>
+#include <osmium/osm/item_type.hpp>
+#include <osmium/osm/object.hpp>
+
+// Drops the output elements after the given number of elements in total or of their type were written, e.g. to cut
+// small samples from large inputs. Elements are counted in the order they are passed, a limit of 0 means no limit.
+class OutputLimit {
+    unsigned long long max_total_;
+    unsigned long long max_per_type_;
+    unsigned long long total_ = 0;
+    // written nodes, ways and relations
+    std::array<unsigned long long, 3> per_type_{};
+
+    bool accept(const osmium::item_type type) {
+        auto &count = per_type_[osmium::item_type_to_nwr_index(type)];
+        if ((max_total_ > 0 && total_ >= max_total_) || (max_per_type_ > 0 && count >= max_per_type_)) {
+            return false;
+        }
+        total_++;
+        count++;
+        return true;
+    }
+
+public:
+    unsigned long long dropped_ = 0;
+
+    OutputLimit(const unsigned long long max_total, const unsigned long long max_per_type) : max_total_(max_total), max_per_type_(max_per_type) {
+    }
+
+    bool enabled() const {
+        return max_total_ > 0 || max_per_type_ > 0;
+    }
+
+    // the elements of the buffer within the limits, the buffer itself if there are no limits
+    osmium::memory::Buffer apply(osmium::memory::Buffer &&buffer) {
+        if (!enabled()) {
+            return std::move(buffer);
+        }
+        osmium::memory::Buffer kept{buffer.committed(), osmium::memory::Buffer::auto_grow::yes};
+        for (const auto &object: buffer.select<osmium::OSMObject>()) {
+            if (accept(object.type())) {
+                kept.add_item(object);
+                kept.commit();
+            } else {
+                dropped_++;
+            }
+        }
+        return kept;
+    }
+};
+
+#endif//OSM_TRANSFORM_OUTPUT_LIMIT_H
@@ -0,0 +1,47 @@
+#ifndef OSM_TRANSFORM_QUIET_OUTPUT_H
+#define OSM_TRANSFORM_QUIET_OUTPUT_H
+
+#include <cstdio>
+#include <stdexcept>
+#include <unistd.h>
+
+#include "warnings.h"
+
+// Discards everything written to stdout, including warnings, while it is alive or until restore() is called. The
+// stdout file descriptor is redirected, so the output of libraries like GDAL is suppressed as well.
+class QuietOutput {
+    int stdout_fd_ = -1;
+
+public:
+    QuietOutput() {
+        std::fflush(stdout);
+        stdout_fd_ = dup(fileno(stdout));
+        if (stdout_fd_ < 0 || std::freopen("/dev/null", "w", stdout) == nullptr) {
+            if (stdout_fd_ >= 0) {
+                close(stdout_fd_);
+            }
+            throw std::runtime_error("could not suppress output for summary_only");
+        }
+        WarningLog::instance().set_quiet(true);
+    }
+
+    QuietOutput(const QuietOutput &) = delete;
+    QuietOutput &operator=(const QuietOutput &) = delete;
+
+    ~QuietOutput() {
+        restore();
+    }
+
+    void restore() {
+        if (stdout_fd_ < 0) {
+            return;
+        }
+        std::fflush(stdout);
+        dup2(stdout_fd_, fileno(stdout));
+        close(stdout_fd_);
+        stdout_fd_ = -1;
+        WarningLog::instance().set_quiet(false);
+    }
+};
+
+#endif//OSM_TRANSFORM_QUIET_OUTPUT_H
@@ -0,0 +1,48 @@
+#ifndef OSM_TRANSFORM_RELATION_ROLES_H
+#define OSM_TRANSFORM_RELATION_ROLES_H
+
+#include <map>
+#include <ostream>
+#include <string>
+
+#include <osmium/osm/relation.hpp>
+
+// Number of relation members per relation type and role, e.g. to see how route relations use forward, backward, stop
+// and platform roles. Relations without a type tag are counted under "(none)", members without a role, too.
+class RelationRoles {
+    struct counts {
+        unsigned long long relations = 0;
+        std::map<std::string, unsigned long long> roles;
+    };
+    std::map<std::string, counts> types_;
+
+public:
+    void add(const osmium::Relation &relation) {
+        const char *type = relation.tags().get_value_by_key("type");
+        auto &c = types_[type != nullptr && *type != '\0' ? type : "(none)"];
+        c.relations++;
+        for (const auto &member: relation.members()) {
+            c.roles[*member.role() != '\0' ? member.role() : "(none)"]++;
+        }
+    }
+
+    std::size_t size() const {
+        return types_.size();
+    }
+
+    // one line per relation type with the number of relations and the members per role, e.g.
+    //   route: 2 relations, members: (none) 12, backward 3, forward 3, stop 4
+    void print(std::ostream &out) const {
+        for (const auto &[type, c]: types_) {
+            out << "  " << type << ": " << c.relations << " relations, members:";
+            const char *separator = " ";
+            for (const auto &[role, count]: c.roles) {
+                out << separator << role << " " << count;
+                separator = ", ";
+            }
+            out << "\n";
+        }
+    }
+};
+
+#endif//OSM_TRANSFORM_RELATION_ROLES_H
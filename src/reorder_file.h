@@ -0,0 +1,47 @@
+#ifndef OSM_TRANSFORM_REORDER_FILE_H
+#define OSM_TRANSFORM_REORDER_FILE_H
+
+#include <algorithm>
+#include <utility>
+#include <vector>
+
+#include <osmium/io/file.hpp>
+#include <osmium/io/header.hpp>
+#include <osmium/io/output_iterator.hpp>
+#include <osmium/io/reader.hpp>
+#include <osmium/io/writer.hpp>
+#include <osmium/memory/buffer.hpp>
+#include <osmium/object_pointer_collection.hpp>
+#include <osmium/osm/object_comparisons.hpp>
+#include <osmium/visitor.hpp>
+
+// reads the whole file into memory and rewrites it sorted by type and id and/or with relations before ways before nodes
+inline void reorder_file(const osmium::io::File &file, osmium::io::Header header, const bool sort, const bool relations_first) {
+    std::vector<osmium::memory::Buffer> buffers;
+    osmium::ObjectPointerCollection objects;
+    osmium::io::Reader reader{file.filename()};
+    while (osmium::memory::Buffer buffer = reader.read()) {
+        osmium::apply(buffer, objects);
+        buffers.push_back(std::move(buffer));
+    }
+    reader.close();
+    if (sort) {
+        objects.sort(osmium::object_order_type_id_version());
+    }
+    if (relations_first) {
+        // stable, so the order within each type is kept
+        std::stable_sort(objects.ptr_begin(), objects.ptr_end(), [](const osmium::OSMObject *a, const osmium::OSMObject *b) {
+            return a->type() > b->type();
+        });
+    }
+
+    if (sort && !relations_first) {
+        header.set("sorting", "Type_then_ID");
+    }
+    osmium::io::Writer writer{file, header, osmium::io::overwrite::allow};
+    auto out = osmium::io::make_output_iterator(writer);
+    std::copy(objects.begin(), objects.end(), out);
+    writer.close();
+}
+
+#endif//OSM_TRANSFORM_REORDER_FILE_H
@@ -0,0 +1,29 @@
+#ifndef OSM_TRANSFORM_REQUIRED_KEYS_FILTER_H
+#define OSM_TRANSFORM_REQUIRED_KEYS_FILTER_H
+
+#include <algorithm>
+#include <string>
+#include <vector>
+
+#include <osmium/osm/entity_bits.hpp>
+#include <osmium/osm/object.hpp>
+
+// Accepts only elements having all of the keys. Elements of types not selected are always accepted.
+class RequiredKeysFilter {
+    std::vector<std::string> keys_;
+    osmium::osm_entity_bits::type types_;
+
+public:
+    RequiredKeysFilter(std::vector<std::string> keys, const osmium::osm_entity_bits::type types) :
+        keys_(std::move(keys)), types_(types) {
+    }
+
+    bool accept(const osmium::OSMObject &object) const {
+        if ((osmium::osm_entity_bits::from_item_type(object.type()) & types_) == osmium::osm_entity_bits::nothing) {
+            return true;
+        }
+        return std::all_of(keys_.begin(), keys_.end(), [&object](const std::string &key) { return object.tags().has_key(key.c_str()); });
+    }
+};
+
+#endif//OSM_TRANSFORM_REQUIRED_KEYS_FILTER_H
@@ -1,4 +1,10 @@
 #include "rewrite_handler.h"
+#include "warnings.h"
+
+#include <algorithm>
+#include <charconv>
+#include <cmath>
+#include <set>
 
 #include <boost/algorithm/string.hpp>
 #include <osmium/builder/osm_object_builder.hpp>
@@ -7,52 +13,294 @@
 #include <osmium/osm/tag.hpp>
 #include <osmium/osm/way.hpp>
 
-void RewriteHandler::copy_tags(osmium::builder::Builder &parent, const osmium::TagList &tags)  {
+std::string RewriteHandler::normalize_multi_value(const std::string &value) {
+    std::vector<std::string> values;
+    boost::split(values, value, boost::is_any_of(";"));
+    std::set<std::string> normalized;
+    for (auto &v: values) {
+        boost::trim(v);
+        if (!v.empty()) {
+            normalized.insert(v);
+        }
+    }
+    return boost::algorithm::join(normalized, ";");
+}
+
+std::string RewriteHandler::format_elevation(const double ele, const char decimal_separator) {
+    char buffer[64];
+    const auto [end, ec] = std::to_chars(buffer, buffer + sizeof(buffer), ele, std::chars_format::fixed, 6);
+    std::string result(buffer, ec == std::errc() ? end : buffer);
+    if (decimal_separator != '.') {
+        std::replace(result.begin(), result.end(), '.', decimal_separator);
+    }
+    return result;
+}
+
+std::string RewriteHandler::tag_hash(std::vector<std::pair<std::string, std::string>> tags) {
+    std::sort(tags.begin(), tags.end());
+    std::uint64_t hash = 14695981039346656037ULL;
+    const auto add = [&hash](const std::string &value) {
+        for (const unsigned char c: value) {
+            hash = (hash ^ c) * 1099511628211ULL;
+        }
+        // separate the strings, so that e.g. a=bc and ab=c differ
+        hash = (hash ^ 0xff) * 1099511628211ULL;
+    };
+    for (const auto &[key, value]: tags) {
+        add(key);
+        add(value);
+    }
+    char buffer[17];
+    snprintf(buffer, sizeof(buffer), "%016llx", static_cast<unsigned long long>(hash));
+    return buffer;
+}
+
+bool RewriteHandler::parse_elevation(std::string_view value, const double default_factor, double &meters) {
+    const auto is_space = [](const char c) { return c == ' ' || c == '\t'; };
+    while (!value.empty() && is_space(value.front())) value.remove_prefix(1);
+    while (!value.empty() && is_space(value.back())) value.remove_suffix(1);
+    double number;
+    const auto [end, ec] = std::from_chars(value.data(), value.data() + value.size(), number);
+    if (ec != std::errc() || std::isnan(number) || std::isinf(number)) {
+        return false;
+    }
+    std::string unit(end, value.data() + value.size());
+    boost::trim(unit);
+    boost::to_lower(unit);
+    if (unit.empty()) {
+        meters = number * default_factor;
+    } else if (unit == "m" || unit == "meter" || unit == "meters" || unit == "metre" || unit == "metres") {
+        meters = number;
+    } else if (unit == "ft" || unit == "feet" || unit == "foot" || unit == "'") {
+        meters = number * 0.3048;
+    } else {
+        return false;
+    }
+    return true;
+}
+
+void RewriteHandler::add_normalized_tag(osmium::builder::TagListBuilder &builder, const char *key, const char *value) {
+    if (unit_normalizer_ != nullptr) {
+        if (const auto metric = unit_normalizer_->normalize(key, value)) {
+            builder.add_tag(key, metric->c_str());
+            return;
+        }
+    }
+    if (!normalize_multivalue_keys_.empty() && normalize_multivalue_keys_.contains(key)) {
+        const auto normalized = normalize_multi_value(value);
+        if (normalized != value) {
+            multi_values_normalized_++;
+        }
+        builder.add_tag(key, normalized);
+        return;
+    }
+    builder.add_tag(key, value);
+}
+
+std::string RewriteHandler::highway_rank(const osmium::TagList &tags, const std::vector<std::pair<std::string, std::string>> *merged_tags) const {
+    const char *highway = tags.get_value_by_key("highway");
+    if (highway == nullptr && merged_tags != nullptr) {
+        const auto it = std::find_if(merged_tags->begin(), merged_tags->end(), [](const auto &tag) { return tag.first == "highway"; });
+        highway = it != merged_tags->end() ? it->second.c_str() : nullptr;
+    }
+    if (const auto rank = highway != nullptr ? highway_ranks_.find(highway) : highway_ranks_.end(); rank != highway_ranks_.end()) {
+        return std::to_string(rank->second);
+    }
+    return {};
+}
+
+std::string RewriteHandler::format_elevation_profile(const elevation_profile &profile, const std::size_t first, const std::size_t last, bool &truncated) {
+    std::string result;
+    truncated = false;
+    for (const auto &[position, ele]: profile) {
+        if (position < first || position > last) {
+            continue;
+        }
+        const auto value = std::to_string(std::lround(ele));
+        if (result.size() + value.size() + 1 > static_cast<std::size_t>(osmium::max_osm_string_length)) {
+            truncated = true;
+            break;
+        }
+        if (!result.empty()) {
+            result += ',';
+        }
+        result += value;
+    }
+    return result;
+}
+
+void RewriteHandler::copy_tags(osmium::builder::Builder &parent, const osmium::TagList &input_tags, const bool normalize_oneway,
+                               const std::vector<std::pair<std::string, std::string>> *merged_tags,
+                               const std::vector<std::pair<std::string, std::string>> &additional_tags)  {
+    const auto &tags = key_case_normalizer_ != nullptr ? key_case_normalizer_->normalize(input_tags) : input_tags;
     osmium::builder::TagListBuilder builder{parent};
     for (const auto &tag: tags) {
         total_tags_++;
+        if (dedup_tags_ != dedup_tags_mode::off && is_duplicate_tag(tags, tag)) {
+            duplicate_tags_removed_++;
+            continue;
+        }
         if (!boost::regex_match(tag.key(), remove_tags_)) {
             const auto key = tag.key();
             valid_tags_++;
-            builder.add_tag(tag);
+            if (normalize_oneway && strcmp(key, "oneway") == 0) {
+                builder.add_tag("oneway", "yes");
+                continue;
+            }
+            add_normalized_tag(builder, key, tag.value());
         }
     }
+    for (const auto &[key, value]: additional_tags) {
+        builder.add_tag(key, value);
+    }
+    if (merged_tags == nullptr) {
+        return;
+    }
+    // tags of merged duplicates are only added if the key is not present yet, so the first way wins on conflicts
+    std::set<std::string> merged_keys;
+    for (const auto &[key, value]: *merged_tags) {
+        if (tags.has_key(key.c_str()) || merged_keys.contains(key) || boost::regex_match(key, remove_tags_)) {
+            continue;
+        }
+        merged_keys.insert(key);
+        add_normalized_tag(builder, key.c_str(), value.c_str());
+    }
 }
 
-void RewriteHandler::copy_tags(osmium::builder::Builder &parent, const osmium::TagList &tags, const double ele, const std::vector<std::string>& countries) {
+void RewriteHandler::copy_tags(osmium::builder::Builder &parent, const osmium::TagList &input_tags, const double ele, const std::vector<std::string>& countries,
+                               const std::vector<std::pair<std::string, std::string>> &additional_tags) {
+    const auto &tags = key_case_normalizer_ != nullptr ? key_case_normalizer_->normalize(input_tags) : input_tags;
     osmium::builder::TagListBuilder builder{parent};
     for (const auto &tag: tags) {
         total_tags_++;
+        if (dedup_tags_ != dedup_tags_mode::off && is_duplicate_tag(tags, tag)) {
+            duplicate_tags_removed_++;
+            continue;
+        }
         if (!boost::regex_match(tag.key(), remove_tags_)) {
             const auto key = tag.key();
-            if (strcmp(key, "country") == 0 || (strcmp(key, "ele") == 0 && add_elevation_)) {
+            const bool replace_ele = add_elevation_ && ele_tag_;
+            if (replace_ele && !preserve_ele_key_.empty() && strcmp(key, "ele") == 0) {
+                valid_tags_++;
+                ele_tags_preserved_++;
+                builder.add_tag(preserve_ele_key_, tag.value());
                 continue;
             }
+            // an existing tag with the key of the preserved ele is replaced by it
+            if (replace_ele && !preserve_ele_key_.empty() && preserve_ele_key_ == key && tags.has_key("ele")) {
+                continue;
+            }
+            if (strcmp(key, "country") == 0 || (strcmp(key, "ele") == 0 && replace_ele) ||
+                (tag_country_confidence_ && strcmp(key, "country:confidence") == 0)) {
+                continue;
+            }
+            if (normalize_ele_units_ && strcmp(key, "ele") == 0) {
+                double meters;
+                if (parse_elevation(tag.value(), ele_default_unit_factor_, meters)) {
+                    auto value = format_elevation(std::round(meters * 100) / 100, decimal_separator_);
+                    // strip trailing zeros of the six decimals, e.g. 100.500000 => 100.5
+                    value.erase(value.find_last_not_of('0') + 1);
+                    if (value.back() == decimal_separator_) value.pop_back();
+                    if (value != tag.value()) {
+                        ele_tags_normalized_++;
+                    }
+                    valid_tags_++;
+                    builder.add_tag("ele", value);
+                    continue;
+                }
+                ele_tags_unparsable_++;
+            }
 
             valid_tags_++;
-            builder.add_tag(tag);
+            add_normalized_tag(builder, key, tag.value());
         }
     }
-    if (ele > kNoDataValue) { builder.add_tag("ele", std::to_string(ele)); }
+    if (ele_tag_ && ele > kNoDataValue) { builder.add_tag("ele", format_elevation(ele, decimal_separator_)); }
     if (!countries.empty()) { builder.add_tag("country", boost::algorithm::join(countries, ",")); }
+    for (const auto &[key, value]: additional_tags) {
+        builder.add_tag(key, value);
+    }
 }
 
 void RewriteHandler::node(const osmium::Node &node) {
     if (node.id() < 0) return;
+    node_count_++;
+    count_id(node);
+    max_node_id_ = std::max(max_node_id_, node.id());
+    if (tag_key_typos_ != nullptr) {
+        tag_key_typos_->add(node.tags(), osmium::item_type::node, node.id());
+    }
+    if (drop_invisible_ && !node.visible()) {
+        invisible_nodes_++;
+        return;
+    }
+    if (!exclude_changesets_.empty() && exclude_changesets_.contains(node.changeset())) {
+        nodes_excluded_by_changeset_++;
+        return;
+    }
     if (valid_ids_.nodes().get(node.id())) {
+        if (element_filter_ && !element_filter_(node)) {
+            nodes_rejected_by_filter_++;
+            return;
+        }
+        auto location = node.location();
+        if (!location.valid()) {
+            invalid_coordinates_++;
+            if (invalid_coordinate_mode_ == invalid_coordinate_mode::drop || (invalid_coordinate_mode_ == invalid_coordinate_mode::clamp && !clamp_location(location))) {
+                return;
+            }
+            if (invalid_coordinate_mode_ == invalid_coordinate_mode::keep) {
+                // written unchanged without elevation and area lookups, which need a valid location, so the existing
+                // ele and country tags are kept
+                processed_elements_++;
+                accepted_nodes_++;
+                {
+                    osmium::builder::NodeBuilder builder{*node_buffer_};
+                    builder.set_id(node.id());
+                    builder.set_location(location);
+                    copy_tags(builder, node.tags());
+                }
+                node_buffer_->commit();
+                return;
+            }
+        }
+        if (min_node_spacing_ > 0 && node.tags().empty() && (protected_nodes_ == nullptr || !protected_nodes_->get(node.id()))) {
+            spacing_candidates_.set(node.id());
+        }
+        for (const auto &key: lift_node_tags_) {
+            if (const char *value = node.tags().get_value_by_key(key.c_str()); value != nullptr) {
+                lifted_node_tags_[node.id()].emplace_back(key, value);
+            }
+        }
         processed_elements_++;
-        osmium::builder::NodeBuilder builder{*node_buffer_};
-        builder.set_id(node.id());
-        builder.set_location(node.location());
+        accepted_nodes_++;
         double ele = kNoDataValue;
-        if (add_elevation_ && location_elevation_.is_initialized()) { //&& !no_elevation_.nodes().get(node.id())) {
-            if ((ele = location_elevation_.elevation(node.location(), true)) != kNoDataValue) {
+        // marker for nodes without elevation, nullptr if the lookup succeeded or was not done at all
+        const char *ele_status = nullptr;
+        const bool is_label = label_nodes_ != nullptr && label_nodes_->get(node.id());
+        if (add_elevation_ && location_elevation_.is_initialized() && elevation_only_tagged_ && !is_label && !has_kept_tags(node.tags())) {
+            untagged_nodes_without_elevation_++;
+            ele_status = "skipped";
+        } else if (add_elevation_ && location_elevation_.is_initialized()) { //&& !no_elevation_.nodes().get(node.id())) {
+            if (node_cache_ != nullptr && node_cache_->get(node.id(), location, ele)) {
+                nodes_with_elevation_++;
+            } else if ((ele = location_elevation_.elevation(location, true)) != kNoDataValue) {
                 nodes_with_elevation_++;
+                if (node_cache_ != nullptr) {
+                    node_cache_->put(node.id(), location, ele);
+                }
             } else {
                 nodes_with_elevation_not_found_++;
+                ele_status = "nodata";
+                WarningLog::instance().record("missing_elevation", "no elevation found at " + std::to_string(location.lon()) + "," +
+                                              std::to_string(location.lat()), osmium::item_type::node, node.id());
             }
         }
-        auto countries = location_area_.get_area(node.location());
+        if (is_label && ele != kNoDataValue) {
+            label_nodes_with_elevation_++;
+        }
+        bool country_ambiguous;
+        auto countries = location_area_.get_area(location, &country_ambiguous);
         switch (countries.size()) {
             case 0:
                 nodes_with_no_country_++;
@@ -63,45 +311,455 @@ void RewriteHandler::node(const osmium::Node &node) {
             default:
                 nodes_with_multiple_countries_++;
         }
-        copy_tags(builder, node.tags(), ele, countries);
-        if (interpolate_) {
-            location_index_->set(static_cast<osmium::unsigned_object_id_type>(node.id()), node.location());
+        if (ele != kNoDataValue) {
+            write_elevation_csv(node.id(), location, ele);
+            if (elevation_histogram_ != nullptr) {
+                elevation_histogram_->add(ele);
+            }
+        }
+        write_country_points_csv(node.id(), location, countries);
+        if (needs_locations()) {
+            location_index_->set(static_cast<osmium::unsigned_object_id_type>(node.id()), location);
+        }
+        if (tile_density_ != nullptr) {
+            tile_density_->add_node(location);
+        }
+        if (output_only_modified_ && ele == kNoDataValue && countries.empty()) {
+            unmodified_elements_skipped_++;
+        } else {
+            std::vector<std::pair<std::string, std::string>> additional_tags;
+            if (annotate_utm_zone_) {
+                additional_tags.emplace_back("_utm", utm_zone(location));
+            }
+            if (annotate_tag_hash_) {
+                additional_tags.emplace_back("_tag_hash", kept_tags_hash(node.tags()));
+            }
+            if (tag_country_confidence_ && !countries.empty()) {
+                additional_tags.emplace_back("country:confidence", country_ambiguous ? "ambiguous" : "certain");
+            }
+            if (country_attributes_ != nullptr && !countries.empty()) {
+                // values for nodes in several countries are joined in the order of the country tag
+                for (const auto index: country_attribute_indices_) {
+                    std::vector<std::string> values;
+                    for (const auto &country: countries) {
+                        values.push_back(country_attributes_->get(country, index));
+                    }
+                    if (std::any_of(values.begin(), values.end(), [](const auto &value) { return !value.empty(); })) {
+                        additional_tags.emplace_back(country_attributes_->names()[index], boost::algorithm::join(values, ","));
+                    }
+                }
+            }
+            if (way_nodes_ != nullptr && way_nodes_->get(node.id())) {
+                additional_tags.emplace_back("_on_way", "yes");
+            }
+            if (junction_nodes_ != nullptr && junction_nodes_->get(node.id())) {
+                additional_tags.emplace_back("_junction", "yes");
+            }
+            if (node_degrees_ != nullptr) {
+                if (const auto degree = node_degrees_->get(node.id()); degree > 0) {
+                    additional_tags.emplace_back("_degree", std::to_string(degree));
+                }
+            }
+            if (tag_elevation_resolution_ && ele != kNoDataValue) {
+                if (const auto resolution = location_elevation_.resolution(location); resolution > 0) {
+                    additional_tags.emplace_back("ele:resolution", std::to_string(std::max(1L, std::lround(resolution))));
+                }
+            }
+            if (tag_elevation_status_ && ele_status != nullptr) {
+                additional_tags.emplace_back("ele:status", ele_status);
+            }
+            if (debug_elevation_raw_ && add_elevation_ && location_elevation_.is_initialized()) {
+                int x, y;
+                double raw;
+                if (location_elevation_.raw_elevation(location, x, y, raw)) {
+                    additional_tags.emplace_back("ele:px", std::to_string(x) + "," + std::to_string(y));
+                    additional_tags.emplace_back("ele:raw", format_elevation(raw, '.'));
+                }
+            }
+            osmium::builder::NodeBuilder builder{*node_buffer_};
+            builder.set_id(node.id());
+            builder.set_location(location);
+            output_bbox_.extend(location);
+            if (keep_metadata_ & osmium::osm_entity_bits::node) {
+                copy_metadata(builder, node);
+            } else if (keep_metadata_fields_.any()) {
+                copy_metadata(builder, node, keep_metadata_fields_);
+            }
+            copy_tags(builder, node.tags(), ele, countries, additional_tags);
         }
     }
 
     node_buffer_->commit();
 }
 
+// the nodes of each way are decided in order: a node is dropped if it lies within min_node_spacing_ meters of the
+// previous kept node of the way, way endpoints, shared nodes, relation members and tagged nodes are always kept
+void RewriteHandler::drop_close_nodes(std::vector<osmium::NodeRef> &refs) {
+    if (refs.size() < 3) {
+        return;
+    }
+    std::vector<osmium::NodeRef> kept{refs.front()};
+    std::vector<osmium::object_id_type> dropped;
+    auto kept_location = get_node_location(refs.front().ref());
+    for (std::size_t i = 1; i + 1 < refs.size(); i++) {
+        const auto location = get_node_location(refs[i].ref());
+        if (spacing_candidates_.get(refs[i].ref()) && kept_location.valid() && location.valid() &&
+            osmium::geom::haversine::distance(osmium::geom::Coordinates(kept_location), osmium::geom::Coordinates(location)) < min_node_spacing_) {
+            dropped.push_back(refs[i].ref());
+            continue;
+        }
+        kept.push_back(refs[i]);
+        kept_location = location;
+    }
+    kept.push_back(refs.back());
+    // closed ways keep all nodes if they would have less than three distinct ones, instead of collapsing to [a, a]
+    if (dropped.empty() || (kept.front().ref() == kept.back().ref() && kept.size() < 4)) {
+        return;
+    }
+    for (const auto id: dropped) {
+        dropped_nodes_.set(id);
+    }
+    // the dropped nodes were counted as accepted when they were read
+    nodes_dropped_by_spacing_ += dropped.size();
+    accepted_nodes_ -= dropped.size();
+    refs = std::move(kept);
+}
+
+double RewriteHandler::way_length(std::span<const osmium::NodeRef> refs) {
+    double length = 0;
+    int known = 0;
+    osmium::Location previous;
+    for (const auto &ref: refs) {
+        const auto location = get_node_location(ref.ref());
+        if (!location.valid()) {
+            continue;
+        }
+        if (known++ > 0) {
+            length += osmium::geom::haversine::distance(osmium::geom::Coordinates(previous), osmium::geom::Coordinates(location));
+        }
+        previous = location;
+    }
+    return known < 2 ? -1 : length;
+}
+
+void RewriteHandler::add_lifted_tags(const osmium::Way &way, std::vector<std::pair<std::string, std::string>> &tags) {
+    bool lifted = false;
+    for (const auto &key: lift_node_tags_) {
+        // the own value of the way takes precedence
+        if (way.tags().has_key(key.c_str())) {
+            continue;
+        }
+        std::vector<std::string> values;
+        for (const auto &ref: way.nodes()) {
+            const auto it = lifted_node_tags_.find(ref.ref());
+            if (it == lifted_node_tags_.end()) {
+                continue;
+            }
+            for (const auto &[node_key, value]: it->second) {
+                if (node_key == key && std::find(values.begin(), values.end(), value) == values.end()) {
+                    values.push_back(value);
+                }
+            }
+        }
+        if (!values.empty()) {
+            tags.emplace_back(key, boost::algorithm::join(values, ";"));
+            lifted = true;
+        }
+    }
+    if (lifted) {
+        ways_with_lifted_tags_++;
+    }
+}
+
+void RewriteHandler::add_grade_tags(std::span<const osmium::NodeRef> refs, std::vector<std::pair<std::string, std::string>> &tags) {
+    const auto start = get_node_location(refs.front().ref());
+    const auto end = get_node_location(refs.back().ref());
+    if (!start.valid() || !end.valid()) {
+        return;
+    }
+    const auto start_ele = location_elevation_.elevation(start, false);
+    const auto end_ele = location_elevation_.elevation(end, false);
+    if (start_ele == kNoDataValue || end_ele == kNoDataValue) {
+        return;
+    }
+    tags.emplace_back("ele:start", format_elevation(start_ele, decimal_separator_));
+    tags.emplace_back("ele:end", format_elevation(end_ele, decimal_separator_));
+    if (const auto length = way_length(refs); length > 0) {
+        char grade[32];
+        snprintf(grade, sizeof(grade), "%.1f", (end_ele - start_ele) / length * 100);
+        tags.emplace_back("grade", grade);
+    }
+    ways_with_grade_++;
+}
+
+// closed ways and ways with unknown endpoint locations get no tag
+void RewriteHandler::add_sinuosity_tag(std::span<const osmium::NodeRef> refs, std::vector<std::pair<std::string, std::string>> &tags) {
+    if (refs.size() < 2 || refs.front().ref() == refs.back().ref()) {
+        return;
+    }
+    const auto start = get_node_location(refs.front().ref());
+    const auto end = get_node_location(refs.back().ref());
+    if (!start.valid() || !end.valid()) {
+        return;
+    }
+    const auto distance = osmium::geom::haversine::distance(osmium::geom::Coordinates(start), osmium::geom::Coordinates(end));
+    const auto length = way_length(refs);
+    if (distance <= 0 || length < 0) {
+        return;
+    }
+    char sinuosity[32];
+    snprintf(sinuosity, sizeof(sinuosity), "%.3f", length / distance);
+    tags.emplace_back("_sinuosity", sinuosity);
+    ways_with_sinuosity_++;
+}
+
+// closed ways and ways with unknown or identical endpoint locations get no tag
+void RewriteHandler::add_bearing_tag(std::span<const osmium::NodeRef> refs, std::vector<std::pair<std::string, std::string>> &tags) {
+    if (refs.size() < 2 || refs.front().ref() == refs.back().ref()) {
+        return;
+    }
+    const auto start = get_node_location(refs.front().ref());
+    const auto end = get_node_location(refs.back().ref());
+    if (!start.valid() || !end.valid() || start == end) {
+        return;
+    }
+    const double lat1 = osmium::geom::deg_to_rad(start.lat());
+    const double lat2 = osmium::geom::deg_to_rad(end.lat());
+    const double lon_delta = osmium::geom::deg_to_rad(end.lon() - start.lon());
+    const double y = std::sin(lon_delta) * std::cos(lat2);
+    const double x = std::cos(lat1) * std::sin(lat2) - std::sin(lat1) * std::cos(lat2) * std::cos(lon_delta);
+    double bearing = std::fmod(osmium::geom::rad_to_deg(std::atan2(y, x)) + 360.0, 360.0);
+    // values just below 360 would be printed as 360.0
+    if (bearing >= 359.95) {
+        bearing = 0;
+    }
+    char formatted[32];
+    snprintf(formatted, sizeof(formatted), "%.1f", bearing);
+    tags.emplace_back("_bearing", formatted);
+    ways_with_bearing_++;
+}
+
+bool RewriteHandler::clamp_location(osmium::Location &location) {
+    if (!location.is_defined()) {
+        return false;
+    }
+    location.set_x(std::clamp(location.x(), -180 * osmium::detail::coordinate_precision, 180 * osmium::detail::coordinate_precision));
+    location.set_y(std::clamp(location.y(), -90 * osmium::detail::coordinate_precision, 90 * osmium::detail::coordinate_precision));
+    return true;
+}
+
+double RewriteHandler::signed_area(const std::vector<osmium::Location> &ring) {
+    double area = 0;
+    for (std::size_t i = 0; i + 1 < ring.size(); i++) {
+        area += ring[i].lon() * ring[i + 1].lat() - ring[i + 1].lon() * ring[i].lat();
+    }
+    return area;
+}
+
+bool RewriteHandler::is_clockwise_area(const osmium::Way &way) {
+    if (way.nodes().size() < 4 || !way.is_closed()) {
+        return false;
+    }
+    // reversing would change the driving direction of roundabouts and other oneways
+    const char *junction = way.tags().get_value_by_key("junction");
+    if (is_oneway(way.tags()) ||
+        (junction != nullptr && (strcmp(junction, "roundabout") == 0 || strcmp(junction, "circular") == 0))) {
+        return false;
+    }
+    std::vector<osmium::Location> ring;
+    ring.reserve(way.nodes().size());
+    for (const auto &ref: way.nodes()) {
+        const auto location = get_node_location(ref.ref());
+        if (!location.valid()) {
+            return false;
+        }
+        ring.push_back(location);
+    }
+    return signed_area(ring) < 0;
+}
+
 void RewriteHandler::way(const osmium::Way &way) {
+    // all nodes are read before the first way, the sparse index types have to be sorted before any lookup
+    if (!locations_sorted_) {
+        locations_sorted_ = true;
+        location_index_->sort();
+    }
     if (way.id() < 0) return;
+    way_count_++;
+    count_id(way);
+    if (tag_key_typos_ != nullptr) {
+        tag_key_typos_->add(way.tags(), osmium::item_type::way, way.id());
+    }
     if (valid_ids_.ways().get(way.id())) {
         processed_elements_++;
+        if (min_way_length_ > 0) {
+            if (const auto length = way_length({way.nodes().begin(), way.nodes().end()}); length >= 0 && length < min_way_length_) {
+                ways_dropped_by_length_++;
+                return;
+            }
+        }
+        accepted_ways_++;
+        if (connectivity_ != nullptr) {
+            connectivity_->add_way({way.nodes().begin(), way.nodes().end()});
+        }
+        if (tile_density_ != nullptr) {
+            for (const auto &ref: way.nodes()) {
+                if (const auto location = get_node_location(ref.ref()); location.valid()) {
+                    tile_density_->add_way(location);
+                    break;
+                }
+            }
+        }
+        // oneway=-1 ways are rewritten as oneway=yes with reversed refs, which changes the geometry direction
+        const bool reverse = normalize_oneway_direction_ && is_reversed_oneway(way.tags());
+        if (reverse) {
+            ways_with_normalized_oneway_++;
+        }
+        // closed ways are reversed to counterclockwise order, their tags are not changed
+        const bool rewind = normalize_winding_ && !reverse && is_clockwise_area(way);
+        if (rewind) {
+            ways_with_reversed_winding_++;
+        }
+        elevation_profile profile;
+        const auto refs = collect_refs(way, reverse || rewind, annotate_elevation_profile_ ? &profile : nullptr);
+        const std::vector<std::pair<std::string, std::string>> *merged_tags = nullptr;
+        if (merged_way_tags_ != nullptr) {
+            if (const auto it = merged_way_tags_->find(way.id()); it != merged_way_tags_->end()) {
+                merged_tags = &it->second;
+            }
+        }
+        if (count_stripped_elements_ && all_tags_removed(way.tags(), merged_tags)) {
+            ways_stripped_of_tags_++;
+        }
+        const bool split = max_way_nodes_ > 1 && refs.size() > max_way_nodes_;
+        if (output_only_modified_ && !reverse && !rewind && !split && merged_tags == nullptr && profile.empty() && refs.size() == way.nodes().size()) {
+            unmodified_elements_skipped_++;
+        } else if (split) {
+            // consecutive segments share their split node and keep the direction of the original way
+            ways_split_by_length_++;
+            for (size_t start = 0; start + 1 < refs.size(); start += max_way_nodes_ - 1) {
+                const auto count = std::min<size_t>(max_way_nodes_, refs.size() - start);
+                if (start == 0) {
+                    add_way(way, way.id(), std::span(refs).subspan(start, count), reverse, merged_tags, profile, start);
+                } else {
+                    add_way(way, next_way_id_++, std::span(refs).subspan(start, count), reverse, merged_tags, profile, start);
+                    ways_added_by_length_split_++;
+                }
+            }
+        } else {
+            add_way(way, way.id(), refs, reverse, merged_tags, profile, 0);
+        }
+    }
+    buffer_->commit();
+}
+
+void RewriteHandler::add_way(const osmium::Way &way, const osmium::object_id_type id, std::span<const osmium::NodeRef> refs, const bool normalize_oneway,
+                             const std::vector<std::pair<std::string, std::string>> *merged_tags, const elevation_profile &profile,
+                             const std::size_t offset) {
+    std::vector<std::pair<std::string, std::string>> additional_tags;
+    if (way_index_ != nullptr) {
+        // nodes added by interpolation are not in the location index, but lie between the others anyway
+        osmium::Box bbox;
+        for (const auto &ref: refs) {
+            bbox.extend(get_node_location(ref.ref()));
+        }
+        way_index_->add(id, bbox);
+    }
+    if (!highway_ranks_.empty()) {
+        if (auto rank = highway_rank(way.tags(), merged_tags); !rank.empty()) {
+            additional_tags.emplace_back("_highway_rank", std::move(rank));
+            ways_with_highway_rank_++;
+        }
+    }
+    if (annotate_tag_hash_) {
+        additional_tags.emplace_back("_tag_hash", kept_tags_hash(way.tags()));
+    }
+    if (!lifted_node_tags_.empty()) {
+        add_lifted_tags(way, additional_tags);
+    }
+    if (annotate_node_count_) {
+        // the written refs, including interpolated nodes and only the part of a split way
+        additional_tags.emplace_back("_node_count", std::to_string(refs.size()));
+    }
+    if (annotate_way_grade_ && location_elevation_.is_initialized() && !refs.empty()) {
+        add_grade_tags(refs, additional_tags);
+    }
+    if (annotate_sinuosity_) {
+        add_sinuosity_tag(refs, additional_tags);
+    }
+    if (annotate_bearing_) {
+        add_bearing_tag(refs, additional_tags);
+    }
+    if (!profile.empty()) {
+        bool truncated;
+        if (auto values = format_elevation_profile(profile, 2 * offset, 2 * (offset + refs.size() - 1), truncated); !values.empty()) {
+            additional_tags.emplace_back("_ele_profile", std::move(values));
+            ways_with_elevation_profile_++;
+            truncated_elevation_profiles_ += truncated;
+        }
+    }
+    {
         osmium::builder::WayBuilder builder{*buffer_};
-        builder.set_id(way.id());
-        copy_tags(builder, way.tags());
-        add_refs(way, builder);
+        builder.set_id(id);
+        if (keep_metadata_ & osmium::osm_entity_bits::way) {
+            copy_metadata(builder, way);
+        } else if (keep_metadata_fields_.any()) {
+            copy_metadata(builder, way, keep_metadata_fields_);
+        }
+        copy_tags(builder, way.tags(), normalize_oneway, merged_tags, additional_tags);
+        osmium::builder::WayNodeListBuilder wnl_builder{builder};
+        for (const auto &ref: refs) {
+            wnl_builder.add_node_ref(ref);
+        }
     }
     buffer_->commit();
 }
-void RewriteHandler::add_refs(const osmium::Way &way, osmium::builder::Builder &builder) {
-    osmium::builder::WayNodeListBuilder wnl_builder{builder};
-    if (interpolate_ && location_elevation_.is_initialized() && !no_elevation_.ways().get(way.id())) {
-        interpolate(way, wnl_builder);
-        return;
+
+std::vector<osmium::NodeRef> RewriteHandler::collect_refs(const osmium::Way &way, const bool reverse, elevation_profile *profile) {
+    std::vector<osmium::NodeRef> refs(way.nodes().begin(), way.nodes().end());
+    if (validate_refs_) {
+        // the upper bound only matters if nodes are generated
+        std::erase_if(refs, [this](const osmium::NodeRef &ref) { return ref.ref() <= 0 || (interpolate_ && ref.ref() >= generated_id_base_); });
     }
-    for (auto& ref : way.nodes()) {
-        wnl_builder.add_node_ref(ref);
+    if (min_node_spacing_ > 0) {
+        drop_close_nodes(refs);
     }
+    if (reverse) {
+        std::reverse(refs.begin(), refs.end());
+    }
+    if (interpolate_ && refs.size() >= 2 && location_elevation_.is_initialized() && !no_elevation_.ways().get(way.id())) {
+        double split_distance = -1;
+        if (const char *highway = way.tags().get_value_by_key("highway"); highway != nullptr) {
+            if (const auto it = split_distances_by_highway_.find(highway); it != split_distances_by_highway_.end()) {
+                split_distance = it->second;
+            }
+        }
+        return interpolate(refs, profile, split_distance);
+    }
+    return refs;
 }
 
-void RewriteHandler::interpolate(const osmium::Way &way, osmium::builder::WayNodeListBuilder &wnl_builder) {
-    auto from = way.nodes()[0];
+std::vector<osmium::NodeRef> RewriteHandler::interpolate(const std::vector<osmium::NodeRef> &refs, elevation_profile *profile, const double split_distance) {
+    // all nodes are read before the first way, so the input ids are known when the first node is generated
+    if (!generated_ids_checked_ && split_interpolated_) {
+        generated_ids_checked_ = true;
+        if (max_node_id_ >= next_node_id_) {
+            Warning("generated_ids") << "the input contains node ids up to " << max_node_id_ << ", generated nodes starting at "
+                      << next_node_id_ << " will collide with them, set generated_id_base to a higher value";
+        }
+    }
+    std::vector<osmium::NodeRef> result;
+    auto from = refs[0];
     auto from_location = get_node_location(from.ref());
-    wnl_builder.add_node_ref(from);
-    for (int i = 1; i < way.nodes().size(); i++) {
-        auto to = way.nodes()[i];
+    result.push_back(from);
+    for (int i = 1; i < refs.size(); i++) {
+        auto to = refs[i];
         auto to_location = get_node_location(to.ref());
-        auto les = location_elevation_.interpolate(from_location, to_location);
+        auto les = split_distance < 0 ? location_elevation_.interpolate(from_location, to_location)
+                                       : location_elevation_.interpolate(from_location, to_location, split_distance);
+        if (profile != nullptr && i == 1 && !les.empty() && les.front().ele != kNoDataValue) {
+            profile->emplace_back(0, les.front().ele);
+        }
         for (int index = 1; index < les.size() -1; ++index) {
             auto before_ele = les.at(index - 1).ele;
             auto after_ele = les.at(index + 1).ele;
@@ -110,15 +768,26 @@ void RewriteHandler::interpolate(const osmium::Way &way, osmium::builder::WayNod
                 continue;
             }
             if (abs(le.ele - (before_ele + after_ele) / 2) >= interpolate_threshold_) {
-                auto new_node_id = next_node_id_++;
-                newNode(new_node_id, le);
-                wnl_builder.add_node_ref(new_node_id);
+                if (split_interpolated_) {
+                    if (profile != nullptr) {
+                        profile->emplace_back(2 * result.size(), le.ele);
+                    }
+                    auto new_node_id = next_node_id_++;
+                    newNode(new_node_id, le);
+                    result.emplace_back(new_node_id);
+                } else if (profile != nullptr) {
+                    profile->emplace_back(2 * result.size() - 1, le.ele);
+                }
             }
         }
+        if (profile != nullptr && les.size() > 1 && les.back().ele != kNoDataValue) {
+            profile->emplace_back(2 * result.size(), les.back().ele);
+        }
         from_location = to_location;
-        wnl_builder.add_node_ref(to);
+        result.push_back(to);
         from = to;
     }
+    return result;
 }
 
 void RewriteHandler::newNode(osmium::object_id_type id, LocationElevation &le) {
@@ -126,23 +795,70 @@ void RewriteHandler::newNode(osmium::object_id_type id, LocationElevation &le) {
         osmium::builder::NodeBuilder nodeBuilder(*node_buffer_);
         nodeBuilder.set_id(id);
         nodeBuilder.set_location(le.location);
+        output_bbox_.extend(le.location);
         {
             osmium::builder::TagListBuilder nodeTagsBuilder{nodeBuilder};
-            nodeTagsBuilder.add_tag("ele", std::to_string(le.ele));
+            if (split_node_tags_ != split_node_tags::none) {
+                if (ele_tag_) {
+                    nodeTagsBuilder.add_tag("ele", format_elevation(le.ele, decimal_separator_));
+                }
+                if (split_node_tags_ == split_node_tags::marker) {
+                    nodeTagsBuilder.add_tag("_generated", "yes");
+                }
+                if (way_nodes_ != nullptr) {
+                    nodeTagsBuilder.add_tag("_on_way", "yes");
+                }
+                if (node_degrees_ != nullptr) {
+                    nodeTagsBuilder.add_tag("_degree", "1");
+                }
+            }
         }
     }
     nodes_added_by_interpolation_++;
+    if (count_nodes_by_resolution_) {
+        nodes_added_by_resolution_[std::lround(location_elevation_.resolution(le.location))]++;
+    }
+    write_elevation_csv(id, le.location, le.ele);
+    if (elevation_histogram_ != nullptr) {
+        elevation_histogram_->add(le.ele);
+    }
     node_buffer_->commit();
 }
 
 void RewriteHandler::relation(const osmium::Relation &relation) {
     if (relation.id() < 0) return;
+    relation_count_++;
+    count_id(relation);
+    if (tag_key_typos_ != nullptr) {
+        tag_key_typos_->add(relation.tags(), osmium::item_type::relation, relation.id());
+    }
+    if (relation_roles_ != nullptr) {
+        relation_roles_->add(relation);
+    }
     if (valid_ids_.relations().get(relation.id())) {
         processed_elements_++;
+        accepted_relations_++;
+        if (count_stripped_elements_ && all_tags_removed(relation.tags())) {
+            relations_stripped_of_tags_++;
+        }
+        if (output_only_modified_) {
+            // relations are never modified by the enrichment
+            unmodified_elements_skipped_++;
+            return;
+        }
         osmium::builder::RelationBuilder builder{*buffer_};
         builder.set_id(relation.id());
+        if (keep_metadata_ & osmium::osm_entity_bits::relation) {
+            copy_metadata(builder, relation);
+        } else if (keep_metadata_fields_.any()) {
+            copy_metadata(builder, relation, keep_metadata_fields_);
+        }
         builder.add_item(relation.members());
-        copy_tags(builder, relation.tags());
+        if (annotate_tag_hash_) {
+            copy_tags(builder, relation.tags(), false, nullptr, {{"_tag_hash", kept_tags_hash(relation.tags())}});
+        } else {
+            copy_tags(builder, relation.tags());
+        }
     }
     buffer_->commit();
 }
@@ -1,22 +1,52 @@
 #ifndef REWRITEHANDLER_H
 #define REWRITEHANDLER_H
 
+#include <algorithm>
+#include <array>
+#include <cmath>
+#include <cstring>
 #include <filesystem>
+#include <functional>
 #include <iostream>
+#include <map>
+#include <set>
+#include <span>
+#include <string_view>
+#include <unordered_map>
+#include <unordered_set>
 
+#include <boost/algorithm/string.hpp>
 #include <boost/regex.hpp>
 
 #include <osmium/builder/osm_object_builder.hpp>
+#include <osmium/geom/haversine.hpp>
 #include <osmium/handler.hpp>
 #include <osmium/index/id_set.hpp>
 #include <osmium/index/map/all.hpp>
 #include <osmium/index/node_locations_map.hpp>
 #include <osmium/index/nwr_array.hpp>
 #include <osmium/memory/buffer.hpp>
+#include <osmium/osm/box.hpp>
+#include <osmium/osm/entity_bits.hpp>
+#include <osmium/osm/item_type.hpp>
+#include <osmium/osm/metadata_options.hpp>
 
+#include "connectivity.h"
+#include "elevation_histogram.h"
+#include "node_degrees.h"
+#include "relation_roles.h"
+#include "tag_key_case_normalizer.h"
+#include "tag_key_typos.h"
+#include "tile_density.h"
+#include "unit_normalizer.h"
+#include "way_index.h"
+#include "country_attributes.h"
 #include "geotiff.h"
 #include "location_elevation_service.h"
 #include "location_area_service.h"
+#include "node_elevation_cache.h"
+
+static constexpr osmium::object_id_type kHighestWayId = 10000000000;
 
 class RewriteHandler : public osmium::handler::Handler {
 
@@ -29,36 +59,312 @@ class RewriteHandler : public osmium::handler::Handler {
 
     osmium::memory::Buffer *node_buffer_;
     osmium::object_id_type next_node_id_;
+    const osmium::object_id_type generated_id_base_;
+    osmium::object_id_type max_node_id_ = 0;
+    bool generated_ids_checked_ = false;
+    bool locations_sorted_ = false;
     std::unique_ptr<osmium::index::map::Map<osmium::unsigned_object_id_type, osmium::Location>> &location_index_;
     LocationElevationService &location_elevation_;
     LocationAreaService &location_area_;
     bool interpolate_;
     double interpolate_threshold_;
 
-    void copy_tags(osmium::builder::Builder &parent, const osmium::TagList &tags, double ele, const std::vector<std::string>& countries);
-    void copy_tags(osmium::builder::Builder &parent, const osmium::TagList &tags);
+    // untagged nodes which are neither way endpoints, shared nor relation members, the ways decide which of them
+    // min_node_spacing drops
+    osmium::index::IdSetDense<osmium::unsigned_object_id_type> spacing_candidates_;
+    osmium::index::IdSetDense<osmium::unsigned_object_id_type> dropped_nodes_;
+
+    void drop_close_nodes(std::vector<osmium::NodeRef> &refs);
+
+    // geodesic length in meters over the way nodes with known locations, negative if less than two are known
+    double way_length(std::span<const osmium::NodeRef> refs);
+
+    // closed way without oneway semantics whose nodes are in clockwise order, false if a node location is unknown
+    bool is_clockwise_area(const osmium::Way &way);
+
+    // distinct values of the lift_node_tags_ keys of the nodes of the way in way order, joined with ';'
+    void add_lifted_tags(const osmium::Way &way, std::vector<std::pair<std::string, std::string>> &tags);
+
+    // ele:start, ele:end and grade in percent of the way, omitted if an endpoint location or elevation is unknown
+    void add_grade_tags(std::span<const osmium::NodeRef> refs, std::vector<std::pair<std::string, std::string>> &tags);
+
+    void add_sinuosity_tag(std::span<const osmium::NodeRef> refs, std::vector<std::pair<std::string, std::string>> &tags);
+
+    // initial bearing from the first to the last node in degrees clockwise from north, omitted for closed ways
+    void add_bearing_tag(std::span<const osmium::NodeRef> refs, std::vector<std::pair<std::string, std::string>> &tags);
+
+    void copy_tags(osmium::builder::Builder &parent, const osmium::TagList &tags, double ele, const std::vector<std::string>& countries,
+                   const std::vector<std::pair<std::string, std::string>> &additional_tags);
+    void copy_tags(osmium::builder::Builder &parent, const osmium::TagList &tags, bool normalize_oneway = false,
+                   const std::vector<std::pair<std::string, std::string>> *merged_tags = nullptr,
+                   const std::vector<std::pair<std::string, std::string>> &additional_tags = {});
+
+    // elevations along a way, keyed by twice the index of a way node, or for samples between two way nodes, by twice the
+    // index of the following way node minus one
+    typedef std::vector<std::pair<std::size_t, double>> elevation_profile;
+
+    std::string highway_rank(const osmium::TagList &tags, const std::vector<std::pair<std::string, std::string>> *merged_tags) const;
+
+    // hash of the tags which are kept from the input, enrichment tags are not included
+    std::string kept_tags_hash(const osmium::TagList &tags) const {
+        std::vector<std::pair<std::string, std::string>> kept;
+        for (const auto &tag: tags) {
+            if (!boost::regex_match(tag.key(), remove_tags_)) {
+                kept.emplace_back(tag.key(), tag.value());
+            }
+        }
+        return tag_hash(std::move(kept));
+    }
+
+    // true if the element has tags, but all of them and of merged duplicates are removed by the remove tags regex
+    bool all_tags_removed(const osmium::TagList &tags, const std::vector<std::pair<std::string, std::string>> *merged_tags = nullptr) const {
+        if (tags.empty()) {
+            return false;
+        }
+        for (const auto &tag: tags) {
+            if (!boost::regex_match(tag.key(), remove_tags_)) {
+                return false;
+            }
+        }
+        if (merged_tags != nullptr) {
+            for (const auto &[key, value]: *merged_tags) {
+                if (!boost::regex_match(key, remove_tags_)) {
+                    return false;
+                }
+            }
+        }
+        return true;
+    }
+
+    // true if another tag with the same key comes before the tag (keep first) or after it (keep last)
+    bool is_duplicate_tag(const osmium::TagList &tags, const osmium::Tag &tag) const {
+        bool after = false;
+        for (const auto &other: tags) {
+            if (&other == &tag) {
+                if (dedup_tags_ == dedup_tags_mode::first) {
+                    return false;
+                }
+                after = true;
+            } else if ((after || dedup_tags_ == dedup_tags_mode::first) && strcmp(other.key(), tag.key()) == 0) {
+                return true;
+            }
+        }
+        return false;
+    }
+
+    bool has_kept_tags(const osmium::TagList &tags) const {
+        return std::any_of(tags.begin(), tags.end(), [this](const osmium::Tag &tag) {
+            return !boost::regex_match(tag.key(), remove_tags_) && strcmp(tag.key(), "ele") != 0 && strcmp(tag.key(), "country") != 0;
+        });
+    }
+
+    // the options working on way geometries read the node locations from the location index, independent of the
+    // elevation lookup
+    bool needs_locations() const {
+        return interpolate_ || min_way_length_ > 0 || min_node_spacing_ > 0 || annotate_way_grade_ || annotate_sinuosity_ || annotate_bearing_ || normalize_winding_ || tile_density_ != nullptr || way_index_ != nullptr;
+    }
 
     auto get_node_location(const osmium::object_id_type id) -> osmium::Location {
         return location_index_->get_noexcept(static_cast<osmium::unsigned_object_id_type>(id));
     }
 
-    void add_refs(const osmium::Way &way, osmium::builder::Builder &builder);
+    void add_normalized_tag(osmium::builder::TagListBuilder &builder, const char *key, const char *value);
+
+    // oneway=yes, true, 1 or -1, other values like no, reversible or alternating have no fixed direction
+    static bool is_oneway(const osmium::TagList &tags) {
+        const char *oneway = tags.get_value_by_key("oneway");
+        return oneway != nullptr && (strcmp(oneway, "yes") == 0 || strcmp(oneway, "true") == 0 || strcmp(oneway, "1") == 0 || strcmp(oneway, "-1") == 0);
+    }
+
+    static bool is_reversed_oneway(const osmium::TagList &tags) {
+        const char *oneway = tags.get_value_by_key("oneway");
+        return oneway != nullptr && strcmp(oneway, "-1") == 0;
+    }
+
+    // copies all metadata fields, or only the given ones (version, changeset, timestamp, uid, user)
+    template <typename TBuilder>
+    static void copy_metadata(TBuilder &builder, const osmium::OSMObject &object, const osmium::metadata_options fields = osmium::metadata_options{"all"}) {
+        if (fields.version()) builder.set_version(object.version());
+        if (fields.changeset()) builder.set_changeset(object.changeset());
+        if (fields.timestamp()) builder.set_timestamp(object.timestamp());
+        if (fields.uid()) builder.set_uid(object.uid());
+        // the user has to be set before any tags or other sub items are added
+        if (fields.user()) builder.set_user(object.user());
+    }
+
+    void add_way(const osmium::Way &way, osmium::object_id_type id, std::span<const osmium::NodeRef> refs, bool normalize_oneway,
+                 const std::vector<std::pair<std::string, std::string>> *merged_tags, const elevation_profile &profile, std::size_t offset);
+
+    std::vector<osmium::NodeRef> collect_refs(const osmium::Way &way, bool reverse = false, elevation_profile *profile = nullptr);
 
-    void interpolate(const osmium::Way &way, osmium::builder::WayNodeListBuilder &wnl_builder);
+    std::vector<osmium::NodeRef> interpolate(const std::vector<osmium::NodeRef> &refs, elevation_profile *profile = nullptr, double split_distance = -1);
 
     void newNode(osmium::object_id_type id, LocationElevation &le);
 
+    void write_country_points_csv(const osmium::object_id_type id, const osmium::Location location, const std::vector<std::string> &countries) {
+        if (country_points_csv_ != nullptr && !countries.empty()) {
+            // several countries are joined like in the country tag
+            *country_points_csv_ << id << "," << location.lon() << "," << location.lat() << ","
+                                 << quote_csv(boost::algorithm::join(countries, ",")) << "\n";
+        }
+    }
+
+    void write_elevation_csv(const osmium::object_id_type id, const osmium::Location location, const double ele) {
+        if (elevation_csv_ != nullptr) {
+            *elevation_csv_ << id << "," << location.lon() << "," << location.lat() << "," << ele << "\n";
+        }
+    }
+
+    void count_id(const osmium::OSMObject &object) {
+        const auto i = osmium::item_type_to_nwr_index(object.type());
+        if (min_ids_[i] == 0 || object.id() < min_ids_[i]) {
+            min_ids_[i] = object.id();
+        }
+        max_ids_[i] = std::max(max_ids_[i], object.id());
+    }
+
 public:
     unsigned long long processed_elements_ = 0;
+    unsigned long long node_count_ = 0;
+    unsigned long long way_count_ = 0;
+    unsigned long long relation_count_ = 0;
+    // smallest and largest id of the nodes, ways and relations read, 0 if there were none
+    std::array<osmium::object_id_type, 3> min_ids_{};
+    std::array<osmium::object_id_type, 3> max_ids_{};
+    unsigned long long accepted_nodes_ = 0;
+    unsigned long long accepted_ways_ = 0;
+    unsigned long long accepted_relations_ = 0;
     unsigned long long total_tags_ = 0;
     unsigned long long valid_tags_ = 0;
     bool add_elevation_ = false;
+    bool normalize_oneway_direction_ = false;
+    std::uint32_t max_way_nodes_ = 0;
+    bool output_only_modified_ = false;
+    bool annotate_utm_zone_ = false;
+    std::map<std::string, int, std::less<>> highway_ranks_;
+    // split distance of the interpolation per highway value, the one of the elevation service for other ways
+    std::map<std::string, double, std::less<>> split_distances_by_highway_;
+    bool split_interpolated_ = true;
+    bool annotate_elevation_profile_ = false;
+    char decimal_separator_ = '.';
+    bool elevation_only_tagged_ = false;
+    bool normalize_ele_units_ = false;
+    double ele_default_unit_factor_ = 1.0;
+    bool annotate_tag_hash_ = false;
+    double min_way_length_ = 0;
+    bool tag_elevation_resolution_ = false;
+    // tag nodes with a country with country:confidence, ambiguous if their grid tile intersects several areas
+    bool tag_country_confidence_ = false;
+    // tag nodes without elevation with ele:status=nodata if the lookup failed or skipped if it was not done
+    bool tag_elevation_status_ = false;
+    // tag nodes with the pixel coordinate and unscaled value of the geotiff, ele:px=x,y and ele:raw
+    bool debug_elevation_raw_ = false;
+    bool annotate_way_grade_ = false;
+    // length of the way divided by the distance of its endpoints, _sinuosity
+    bool annotate_sinuosity_ = false;
+    // initial bearing from the first to the last node, _bearing
+    bool annotate_bearing_ = false;
+    bool count_nodes_by_resolution_ = false;
+    bool normalize_winding_ = false;
+    bool annotate_node_count_ = false;
+    // false to only write the elevations to the elevation csv, existing ele tags are kept then
+    bool ele_tag_ = true;
+    // key the existing ele tags replaced by the DEM elevation are kept under, e.g. ele:osm, empty drops them
+    std::string preserve_ele_key_;
+    enum class invalid_coordinate_mode { drop, clamp, keep };
+    // handling of nodes with undefined or out of range coordinates, clamp drops nodes without coordinates
+    invalid_coordinate_mode invalid_coordinate_mode_ = invalid_coordinate_mode::keep;
+    enum class split_node_tags { ele, marker, none };
+    // tags of the nodes added by interpolation, marker adds _generated=yes, none writes them without tags
+    split_node_tags split_node_tags_ = split_node_tags::ele;
+    enum class dedup_tags_mode { off, first, last };
+    // which of several tags with the same key on one element is kept, off keeps all of them
+    dedup_tags_mode dedup_tags_ = dedup_tags_mode::off;
+    // drop node refs which are not positive or collide with the ids of generated nodes
+    bool validate_refs_ = false;
+    // count accepted ways and relations whose tags are all removed by the remove tags regex
+    bool count_stripped_elements_ = false;
+    // components of the accepted ways, only collected if set
+    Connectivity *connectivity_ = nullptr;
+    // nodes and ways per grid tile, only collected if set
+    TileDensity *tile_density_ = nullptr;
+    // bounding boxes of the written ways, only collected if set
+    WayIndex *way_index_ = nullptr;
+    // converts imperial values of some keys to metric if set
+    UnitNormalizer *unit_normalizer_ = nullptr;
+    // lowercases the tag keys of the written elements if set
+    TagKeyCaseNormalizer *key_case_normalizer_ = nullptr;
+    // nodes per elevation bin including interpolated ones, only collected if set
+    ElevationHistogram *elevation_histogram_ = nullptr;
+    // keys of all input elements close to common keys, only collected if set
+    TagKeyTypos *tag_key_typos_ = nullptr;
+    // member roles of all input relations, only collected if set
+    RelationRoles *relation_roles_ = nullptr;
+    // representative points of relations, which get an elevation even with elevation_only_tagged
+    const osmium::index::IdSetDense<osmium::unsigned_object_id_type> *label_nodes_ = nullptr;
+    unsigned long long label_nodes_with_elevation_ = 0;
+    // node tag keys copied to the ways containing the node
+    std::vector<std::string> lift_node_tags_;
+    const CountryAttributes *country_attributes_ = nullptr;
+    // indices of the country attributes added as tags
+    std::vector<int> country_attribute_indices_;
+    osmium::osm_entity_bits::type keep_metadata_ = osmium::osm_entity_bits::nothing;
+    // metadata fields kept for the element types not in keep_metadata_
+    osmium::metadata_options keep_metadata_fields_{"none"};
+    std::ostream *elevation_csv_ = nullptr;
+    // id, lon, lat and country of all nodes with a country, a derived lookup table
+    std::ostream *country_points_csv_ = nullptr;
+    std::set<std::string, std::less<>> normalize_multivalue_keys_;
+    NodeElevationCache *node_cache_ = nullptr;
+    const std::unordered_map<osmium::object_id_type, std::vector<std::pair<std::string, std::string>>> *merged_way_tags_ = nullptr;
+    // tags with lift_node_tags_ keys of the accepted nodes, collected before the ways are read
+    std::unordered_map<osmium::object_id_type, std::vector<std::pair<std::string, std::string>>> lifted_node_tags_;
+    osmium::object_id_type next_way_id_ = kHighestWayId + 1;
+    std::unordered_set<osmium::changeset_id_type> exclude_changesets_;
+    bool drop_invisible_ = false;
+    double min_node_spacing_ = 0;
+    const osmium::index::IdSetDense<osmium::unsigned_object_id_type> *protected_nodes_ = nullptr;
+    // nodes of accepted ways and nodes shared by them, tagged with _on_way and _junction if set
+    const osmium::index::IdSetDense<osmium::unsigned_object_id_type> *way_nodes_ = nullptr;
+    const osmium::index::IdSetDense<osmium::unsigned_object_id_type> *junction_nodes_ = nullptr;
+    // number of accepted ways referencing each node, tagged with _degree if set
+    const NodeDegrees *node_degrees_ = nullptr;
+    std::function<bool(const osmium::OSMObject &)> element_filter_;
     unsigned long long nodes_with_elevation_ = 0;
     unsigned long long nodes_with_elevation_not_found_ = 0;
     unsigned long long nodes_added_by_interpolation_ = 0;
+    // interpolated nodes by the rounded pixel size in meters of the geotiff providing their elevation, only counted if count_nodes_by_resolution_ is set
+    std::map<long, unsigned long long> nodes_added_by_resolution_;
+    unsigned long long untagged_nodes_without_elevation_ = 0;
+    osmium::Box output_bbox_;
+    unsigned long long ele_tags_normalized_ = 0;
+    unsigned long long ele_tags_unparsable_ = 0;
     unsigned long long nodes_with_single_country_ = 0;
     unsigned long long nodes_with_multiple_countries_ = 0;
     unsigned long long nodes_with_no_country_ = 0;
+    unsigned long long ways_with_normalized_oneway_ = 0;
+    unsigned long long ways_split_by_length_ = 0;
+    unsigned long long ways_added_by_length_split_ = 0;
+    unsigned long long unmodified_elements_skipped_ = 0;
+    unsigned long long nodes_excluded_by_changeset_ = 0;
+    unsigned long long invisible_nodes_ = 0;
+    unsigned long long nodes_rejected_by_filter_ = 0;
+    unsigned long long nodes_dropped_by_spacing_ = 0;
+    unsigned long long ways_dropped_by_length_ = 0;
+    unsigned long long ways_with_grade_ = 0;
+    unsigned long long ways_with_sinuosity_ = 0;
+    unsigned long long ways_with_bearing_ = 0;
+    unsigned long long ways_with_reversed_winding_ = 0;
+    unsigned long long ways_with_lifted_tags_ = 0;
+    unsigned long long invalid_coordinates_ = 0;
+    unsigned long long multi_values_normalized_ = 0;
+    unsigned long long duplicate_tags_removed_ = 0;
+    unsigned long long ways_stripped_of_tags_ = 0;
+    unsigned long long ele_tags_preserved_ = 0;
+    unsigned long long relations_stripped_of_tags_ = 0;
+    unsigned long long ways_with_highway_rank_ = 0;
+    unsigned long long ways_with_elevation_profile_ = 0;
+    unsigned long long truncated_elevation_profiles_ = 0;
 
     explicit RewriteHandler(const osmium::object_id_type next_node_id,
                             std::unique_ptr<osmium::index::map::Map<osmium::unsigned_object_id_type, osmium::Location>> &location_index,
@@ -70,6 +376,7 @@ public:
                             bool interpolate,
                             double interpolate_threshold
                             ) : next_node_id_(next_node_id),
+                                generated_id_base_(next_node_id),
                                 location_index_(location_index),
                                 location_elevation_(elevation_service),
                                 location_area_(area_service),
@@ -80,6 +387,51 @@ public:
                                 interpolate_threshold_(interpolate_threshold) {
     }
 
+    static std::string normalize_multi_value(const std::string &value);
+
+    // formats independent of the current locale with six decimals, like std::to_string
+    static std::string format_elevation(double ele, char decimal_separator = '.');
+
+    // moves an out of range location to the nearest valid one, false if it is undefined
+    static bool clamp_location(osmium::Location &location);
+
+    // twice the signed area of a ring by the shoelace formula in degrees, negative for clockwise rings
+    static double signed_area(const std::vector<osmium::Location> &ring);
+
+    // FNV-1a hash of the sorted key=value pairs as 16 hex digits, independent of the tag order
+    static std::string tag_hash(std::vector<std::pair<std::string, std::string>> tags);
+
+    // parses ele values like "100", "100 m" or "328 ft" to meters, values without unit are multiplied by default_factor
+    static bool parse_elevation(std::string_view value, double default_factor, double &meters);
+
+    // elevations rounded to full meters, cut off after the last value that fits into the maximum osm string length
+    static std::string format_elevation_profile(const elevation_profile &profile, std::size_t first, std::size_t last, bool &truncated);
+
+    static std::string utm_zone(const osmium::Location &location) {
+        const auto zone = std::clamp(static_cast<int>(std::floor((location.lon() + 180.0) / 6.0)) + 1, 1, 60);
+        return std::to_string(zone) + (location.lat() < 0 ? "S" : "N");
+    }
+
+    // quotes a csv field and doubles the quotes in it, area ids can contain commas and quotes
+    static std::string quote_csv(const std::string &value) {
+        return "\"" + boost::algorithm::replace_all_copy(value, "\"", "\"\"") + "\"";
+    }
+
+    static std::map<std::string, int, std::less<>> default_highway_ranks() {
+        return {{"motorway", 1}, {"motorway_link", 1}, {"trunk", 2}, {"trunk_link", 2},
+                {"primary", 3}, {"primary_link", 3}, {"secondary", 4}, {"secondary_link", 4},
+                {"tertiary", 5}, {"tertiary_link", 5}, {"unclassified", 6}, {"road", 7},
+                {"residential", 8}, {"service", 9}, {"living_street", 10}, {"track", 11},
+                {"pedestrian", 12}, {"footway", 13}, {"cycleway", 13}, {"bridleway", 13},
+                {"path", 13}, {"steps", 13}};
+    }
+
+    // nodes removed from their way by min_node_spacing, they are written before the ways and have to be removed from
+    // the node output afterwards
+    const osmium::index::IdSetDense<osmium::unsigned_object_id_type> &dropped_nodes() const {
+        return dropped_nodes_;
+    }
+
     void set_buffers(osmium::memory::Buffer *output_buffer, osmium::memory::Buffer *output_node_buffer) {
         buffer_ = output_buffer;
         node_buffer_ = output_node_buffer;
@@ -94,6 +446,17 @@ public:
 
     void relation(const osmium::Relation &relation);
 
+    void printPassStats() {
+        std::cout << "Pass 2: read " << node_count_ << " nodes, accepted " << accepted_nodes_ << "; "
+                  << "read " << way_count_ << " ways, accepted " << accepted_ways_ << "; "
+                  << "read " << relation_count_ << " relations, accepted " << accepted_relations_
+                  << std::endl;
+        std::cout << "Pass 2: ids of nodes " << min_ids_[0] << "-" << max_ids_[0] << ", "
+                  << "ways " << min_ids_[1] << "-" << max_ids_[1] << ", "
+                  << "relations " << min_ids_[2] << "-" << max_ids_[2]
+                  << std::endl;
+    };
+
     void printCountryStats() {
         std::cout << "Nodes with no country: " << nodes_with_no_country_ << ", "
                   << "single country: " << nodes_with_single_country_ << ", "
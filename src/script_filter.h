@@ -0,0 +1,73 @@
+#ifndef OSM_TRANSFORM_SCRIPT_FILTER_H
+#define OSM_TRANSFORM_SCRIPT_FILTER_H
+
+#ifdef OSM_TRANSFORM_WITH_LUA
+
+#include <stdexcept>
+#include <string>
+
+#include <lua.hpp>
+
+#include <osmium/osm/item_type.hpp>
+#include <osmium/osm/object.hpp>
+
+// Accepts or rejects elements with a user supplied Lua script. The script has to define a global function
+//   filter(type, id, tags)
+// which is called with the element type ("node", "way" or "relation"), the element id and a table of its tags,
+// and returns true to keep the element.
+class ScriptFilter {
+    lua_State *lua_;
+
+    // the error object on top of the stack, which is not necessarily a string
+    std::string error_message() const {
+        const char *message = lua_tostring(lua_, -1);
+        return message != nullptr ? message : "(non-string error)";
+    }
+
+public:
+    explicit ScriptFilter(const std::string &path) : lua_(luaL_newstate()) {
+        luaL_openlibs(lua_);
+        if (luaL_dofile(lua_, path.c_str()) != LUA_OK) {
+            const std::string message = error_message();
+            lua_close(lua_);
+            throw std::runtime_error("could not load filter script " + path + ": " + message);
+        }
+        lua_getglobal(lua_, "filter");
+        const bool is_function = lua_isfunction(lua_, -1);
+        lua_pop(lua_, 1);
+        if (!is_function) {
+            lua_close(lua_);
+            throw std::runtime_error("filter script " + path + " does not define a function 'filter'");
+        }
+    }
+
+    ScriptFilter(const ScriptFilter &) = delete;
+    ScriptFilter &operator=(const ScriptFilter &) = delete;
+
+    ~ScriptFilter() {
+        lua_close(lua_);
+    }
+
+    bool accept(const osmium::OSMObject &object) {
+        lua_getglobal(lua_, "filter");
+        lua_pushstring(lua_, osmium::item_type_to_name(object.type()));
+        lua_pushinteger(lua_, object.id());
+        lua_createtable(lua_, 0, static_cast<int>(object.tags().size()));
+        for (const auto &tag: object.tags()) {
+            lua_pushstring(lua_, tag.value());
+            lua_setfield(lua_, -2, tag.key());
+        }
+        if (lua_pcall(lua_, 3, 1, 0) != LUA_OK) {
+            const std::string message = error_message();
+            lua_pop(lua_, 1);
+            throw std::runtime_error("filter script failed for " + std::string(osmium::item_type_to_name(object.type())) + " " + std::to_string(object.id()) + ": " + message);
+        }
+        const bool result = lua_toboolean(lua_, -1);
+        lua_pop(lua_, 1);
+        return result;
+    }
+};
+
+#endif//OSM_TRANSFORM_WITH_LUA
+
+#endif//OSM_TRANSFORM_SCRIPT_FILTER_H
@@ -0,0 +1,161 @@
+#ifndef OSM_TRANSFORM_SHARDED_WRITER_H
+#define OSM_TRANSFORM_SHARDED_WRITER_H
+
+#include <algorithm>
+#include <cstddef>
+#include <cstdio>
+#include <filesystem>
+#include <iterator>
+#include <memory>
+#include <string>
+#include <utility>
+#include <vector>
+
+#include <osmium/builder/osm_object_builder.hpp>
+#include <osmium/io/file.hpp>
+#include <osmium/io/header.hpp>
+#include <osmium/io/writer.hpp>
+#include <osmium/memory/buffer.hpp>
+#include <osmium/osm/object.hpp>
+#include <osmium/osm/types.hpp>
+#include <osmium/osm/way.hpp>
+
+// Distributes the elements over several output files in turns of chunk_size consecutive elements, so all shards get
+// about the same number of elements and keep the order of their elements. The shards are not self-contained, ways and
+// relations can reference elements in other shards, unless the boundary way policy writes the ways to the shards of
+// their nodes.
+class ShardedWriter {
+    std::vector<std::unique_ptr<osmium::io::Writer>> writers_;
+    std::vector<unsigned long long> counts_;
+    std::size_t chunk_size_;
+    unsigned long long elements_ = 0;
+    // first node id of each run of nodes written to the same shard, the nodes have to be sorted by id
+    std::vector<std::pair<osmium::object_id_type, std::size_t>> node_runs_;
+
+    void add(std::vector<osmium::memory::Buffer> &shards, const std::size_t shard, const osmium::OSMObject &object) {
+        shards[shard].add_item(object);
+        shards[shard].commit();
+        counts_[shard]++;
+    }
+
+    // the shard of the run the node id falls into, the number of shards for ids before the first node
+    std::size_t node_shard(const osmium::object_id_type id) const {
+        const auto it = std::upper_bound(node_runs_.begin(), node_runs_.end(), id, [](const auto value, const auto &run) { return value < run.first; });
+        return it == node_runs_.begin() ? writers_.size() : std::prev(it)->second;
+    }
+
+    // the way with only its refs in the given shard
+    void add_clipped(std::vector<osmium::memory::Buffer> &shards, const std::size_t shard, const osmium::Way &way, const std::vector<std::size_t> &ref_shards) {
+        {
+            osmium::builder::WayBuilder builder{shards[shard]};
+            builder.set_id(way.id());
+            builder.set_version(way.version());
+            builder.set_changeset(way.changeset());
+            builder.set_timestamp(way.timestamp());
+            builder.set_uid(way.uid());
+            builder.set_visible(way.visible());
+            // the user has to be set before any tags or other sub items are added
+            builder.set_user(way.user());
+            builder.add_item(way.tags());
+            osmium::builder::WayNodeListBuilder wnl_builder{builder};
+            for (std::size_t i = 0; i < ref_shards.size(); i++) {
+                if (ref_shards[i] == shard) {
+                    wnl_builder.add_node_ref(way.nodes()[i]);
+                }
+            }
+        }
+        shards[shard].commit();
+        counts_[shard]++;
+    }
+
+    void add_way(std::vector<osmium::memory::Buffer> &shards, const osmium::Way &way) {
+        std::vector<std::size_t> ref_shards;
+        std::vector<std::size_t> way_shards;
+        for (const auto &ref: way.nodes()) {
+            const auto shard = node_shard(ref.ref());
+            ref_shards.push_back(shard);
+            if (shard < writers_.size() && std::find(way_shards.begin(), way_shards.end(), shard) == way_shards.end()) {
+                way_shards.push_back(shard);
+            }
+        }
+        if (way_shards.empty()) {
+            // none of the nodes is in the output, so the way is distributed like the other elements
+            add(shards, (elements_++ / chunk_size_) % writers_.size(), way);
+            return;
+        }
+        if (way_shards.size() == 1 || boundary_way_policy_ == boundary_way_policy::assign_to_first) {
+            boundary_ways_ += way_shards.size() > 1;
+            add(shards, way_shards.front(), way);
+            return;
+        }
+        boundary_ways_++;
+        for (const auto shard: way_shards) {
+            if (boundary_way_policy_ == boundary_way_policy::duplicate) {
+                add(shards, shard, way);
+            } else if (std::count(ref_shards.begin(), ref_shards.end(), shard) >= 2) {
+                add_clipped(shards, shard, way, ref_shards);
+            }
+        }
+    }
+
+public:
+    // how ways with nodes in several shards are written: none distributes the ways like all other elements without
+    // looking at their nodes, the other policies write each way to the shard of its nodes and a way crossing the
+    // boundary between shards to all of them (duplicate), to the shard of its first node (assign_to_first) or to all
+    // of them with only the nodes in the shard, leaving out parts with less than two nodes (clip)
+    enum class boundary_way_policy { none, duplicate, assign_to_first, clip };
+
+    boundary_way_policy boundary_way_policy_ = boundary_way_policy::none;
+    // ways with nodes in more than one shard, only counted with a boundary way policy
+    unsigned long long boundary_ways_ = 0;
+
+    ShardedWriter(const std::vector<osmium::io::File> &files, const osmium::io::Header &header, const std::size_t chunk_size) : counts_(files.size()), chunk_size_(chunk_size) {
+        for (const auto &file: files) {
+            writers_.push_back(std::make_unique<osmium::io::Writer>(file, header, osmium::io::overwrite::allow));
+        }
+    }
+
+    // the path with the zero padded shard number before the extension, e.g. out.pbf => out.002.pbf
+    static std::string shard_path(const std::string &path, const std::size_t index) {
+        const std::filesystem::path p(path);
+        char number[24];
+        snprintf(number, sizeof(number), ".%03zu", index);
+        return (p.parent_path() / (p.stem().string() + number + p.extension().string())).string();
+    }
+
+    void operator()(osmium::memory::Buffer &&buffer) {
+        std::vector<osmium::memory::Buffer> shards;
+        for (std::size_t i = 0; i < writers_.size(); i++) {
+            shards.emplace_back(buffer.committed(), osmium::memory::Buffer::auto_grow::yes);
+        }
+        for (const auto &object: buffer.select<osmium::OSMObject>()) {
+            if (object.type() == osmium::item_type::way && boundary_way_policy_ != boundary_way_policy::none) {
+                add_way(shards, static_cast<const osmium::Way &>(object));
+                continue;
+            }
+            const auto shard = (elements_++ / chunk_size_) % writers_.size();
+            if (object.type() == osmium::item_type::node && (node_runs_.empty() || node_runs_.back().second != shard)) {
+                node_runs_.emplace_back(object.id(), shard);
+            }
+            add(shards, shard, object);
+        }
+        for (std::size_t i = 0; i < writers_.size(); i++) {
+            if (shards[i].committed() > 0) {
+                (*writers_[i])(std::move(shards[i]));
+            }
+        }
+    }
+
+    void close() {
+        for (auto &writer: writers_) {
+            writer->close();
+        }
+    }
+
+    // elements written to each shard, ways written to several shards are counted in each of them
+    const std::vector<unsigned long long> &counts() const {
+        return counts_;
+    }
+};
+
+#endif//OSM_TRANSFORM_SHARDED_WRITER_H
@@ -0,0 +1,119 @@
+#ifndef OSM_TRANSFORM_TAG_KEY_CASE_NORMALIZER_H
+#define OSM_TRANSFORM_TAG_KEY_CASE_NORMALIZER_H
+
+#include <algorithm>
+#include <cstring>
+#include <functional>
+#include <locale>
+#include <set>
+#include <string>
+#include <string_view>
+#include <vector>
+
+#include <boost/algorithm/string.hpp>
+
+#include <osmium/builder/osm_object_builder.hpp>
+#include <osmium/memory/buffer.hpp>
+#include <osmium/osm/tag.hpp>
+#include <osmium/osm/types.hpp>
+
+// Lowercases the ASCII letters of tag keys, e.g. Highway to highway, for all keys or only the given lowercase keys,
+// except for the excluded keys, which are compared as they are. If a lowercased key collides with another key of the
+// element, the tags are merged into the first one and the values joined with ; like other multiple values, skipping
+// values which are present already.
+class TagKeyCaseNormalizer {
+    std::set<std::string, std::less<>> keys_;
+    std::set<std::string, std::less<>> excluded_keys_;
+    osmium::memory::Buffer buffer_{1024, osmium::memory::Buffer::auto_grow::yes};
+    unsigned long long keys_lowercased_ = 0;
+    unsigned long long tags_merged_ = 0;
+
+    struct entry {
+        std::string key;
+        std::string value;
+        bool lowercased;
+    };
+
+    static bool has_uppercase(const char *key) {
+        for (; *key != '\0'; key++) {
+            if (*key >= 'A' && *key <= 'Z') {
+                return true;
+            }
+        }
+        return false;
+    }
+
+    static bool has_value(const std::string &values, const std::string_view value) {
+        std::vector<std::string> split;
+        boost::split(split, values, boost::is_any_of(";"));
+        return std::find(split.begin(), split.end(), value) != split.end();
+    }
+
+public:
+    // all keys are lowercased if keys is empty
+    TagKeyCaseNormalizer(const std::vector<std::string> &keys, const std::vector<std::string> &excluded_keys)
+        : keys_(keys.begin(), keys.end()), excluded_keys_(excluded_keys.begin(), excluded_keys.end()) {
+    }
+
+    // the lowercased key, the key as it is if it is excluded or not configured
+    std::string normalized_key(const std::string_view key) const {
+        std::string lowercase(key);
+        boost::to_lower(lowercase, std::locale::classic());
+        if (excluded_keys_.contains(key) || (!keys_.empty() && !keys_.contains(lowercase))) {
+            return std::string(key);
+        }
+        return lowercase;
+    }
+
+    // the tags with normalized keys, the tags themselves if no key changes. The returned list is only valid until the
+    // next call.
+    const osmium::TagList &normalize(const osmium::TagList &tags) {
+        // most elements have lowercase keys only, they are returned without allocating
+        if (std::none_of(tags.begin(), tags.end(), [this](const osmium::Tag &tag) { return has_uppercase(tag.key()) && normalized_key(tag.key()) != tag.key(); })) {
+            return tags;
+        }
+        std::vector<entry> entries;
+        for (const auto &tag: tags) {
+            auto key = has_uppercase(tag.key()) ? normalized_key(tag.key()) : std::string(tag.key());
+            const bool lowercased = key != tag.key();
+            if (lowercased) {
+                keys_lowercased_++;
+            }
+            // keys which were equal before are left to dedup_tags
+            const auto existing = std::find_if(entries.begin(), entries.end(), [&](const entry &e) {
+                return e.key == key && (lowercased || e.lowercased);
+            });
+            if (existing == entries.end()) {
+                entries.push_back({std::move(key), tag.value(), lowercased});
+                continue;
+            }
+            tags_merged_++;
+            existing->lowercased = true;
+            // values which would exceed the maximum length of osm strings are dropped
+            if (!has_value(existing->value, tag.value()) &&
+                existing->value.size() + 1 + strlen(tag.value()) <= static_cast<std::size_t>(osmium::max_osm_string_length)) {
+                existing->value += ';';
+                existing->value += tag.value();
+            }
+        }
+        buffer_.clear();
+        {
+            osmium::builder::TagListBuilder builder{buffer_};
+            for (const auto &e: entries) {
+                builder.add_tag(e.key, e.value);
+            }
+        }
+        buffer_.commit();
+        return buffer_.get<osmium::TagList>(0);
+    }
+
+    unsigned long long keys_lowercased() const {
+        return keys_lowercased_;
+    }
+
+    unsigned long long tags_merged() const {
+        return tags_merged_;
+    }
+};
+
+#endif//OSM_TRANSFORM_TAG_KEY_CASE_NORMALIZER_H
@@ -0,0 +1,159 @@
+#ifndef OSM_TRANSFORM_TAG_KEY_TYPOS_H
+#define OSM_TRANSFORM_TAG_KEY_TYPOS_H
+
+#include <algorithm>
+#include <array>
+#include <cstddef>
+#include <ostream>
+#include <string>
+#include <string_view>
+#include <unordered_map>
+#include <vector>
+
+#include <osmium/osm/item_type.hpp>
+#include <osmium/osm/tag.hpp>
+#include <osmium/osm/types.hpp>
+
+// Tag keys which are near-misses of common keys, e.g. hihgway or oneway:bicyle. The whole key and each part of a
+// namespaced key are compared with a built-in list of keys. Keys and parts with 5 to 7 characters may differ in 1 edit,
+// longer ones in 2, shorter ones are not checked. Swapping two adjacent characters counts as 1 edit.
+class TagKeyTypos {
+    static constexpr std::array<std::string_view, 169> known_keys_{
+        "abandoned", "access", "addr:city", "addr:country", "addr:housenumber", "addr:postcode", "addr:street",
+        "admin_level", "agricultural", "alt_name", "amenity", "area", "attraction", "attribution", "backward",
+        "barrier", "bicycle", "bicycle_road", "both", "boundary", "brand", "bridge", "building", "building:levels",
+        "building:part", "bus", "cables", "capacity", "colour", "condition", "conditional", "construction", "country",
+        "county", "covered", "craft", "created_by", "crossing", "cuisine", "cycleway", "denomination", "denotation",
+        "description", "destination", "direction", "disused", "door", "ele", "electrified", "email", "emergency",
+        "entrance", "fax", "fixme", "foot", "footway", "ford", "forward", "frequency", "gauge", "generator", "goods",
+        "handrail", "hazard", "healthcare", "height", "hgv", "highway", "historic", "horse", "image", "incline",
+        "indoor", "int_name", "intermittent", "is_in", "junction", "kerb", "landuse", "lane", "lanes", "layer",
+        "leaf_type", "left", "leisure", "level", "levels", "line", "lit", "loc_name", "location", "man_made",
+        "maxheight", "maxspeed", "maxstay", "maxweight", "maxwidth", "min_level", "mofa", "moped", "motor_vehicle",
+        "motorcar", "motorcycle", "motorroad", "mtb:scale", "name", "natural", "network", "note", "office",
+        "official_name", "old_name", "oneway", "opening_hours", "operator", "parking", "phone", "place", "placement",
+        "plant", "population", "postal_code", "power", "psv", "public_transport", "railway", "ramp", "ref", "religion",
+        "restriction", "right", "roof:shape", "rooms", "route", "sac_scale", "seasonal", "segregated", "service",
+        "shape", "shop", "short_name", "sidewalk", "smoothness", "source", "sport", "start_date", "surface",
+        "tactile_paving", "toll", "tourism", "tower", "tracktype", "traffic_signals", "trail_visibility", "tunnel",
+        "turn", "type", "url", "usage", "vehicle", "voltage", "water", "waterway", "website", "wetland", "wheelchair",
+        "width", "wikidata", "wikipedia"};
+
+    struct typo {
+        std::string_view known;
+        unsigned long long count = 0;
+        osmium::item_type example_type;
+        osmium::object_id_type example_id;
+    };
+    // closest known key of every key seen, empty if the key is known or not close to any known key
+    std::unordered_map<std::string, std::string_view> checked_;
+    std::unordered_map<std::string, typo> typos_;
+
+    // optimal string alignment distance, stops early if it exceeds max
+    static std::size_t distance(const std::string_view a, const std::string_view b, const std::size_t max) {
+        if ((a.size() > b.size() ? a.size() - b.size() : b.size() - a.size()) > max) {
+            return max + 1;
+        }
+        std::vector<std::vector<std::size_t>> d(a.size() + 1, std::vector<std::size_t>(b.size() + 1));
+        for (std::size_t i = 0; i <= a.size(); i++) {
+            d[i][0] = i;
+        }
+        for (std::size_t j = 0; j <= b.size(); j++) {
+            d[0][j] = j;
+        }
+        for (std::size_t i = 1; i <= a.size(); i++) {
+            std::size_t row_min = max + 1;
+            for (std::size_t j = 1; j <= b.size(); j++) {
+                const std::size_t cost = a[i - 1] == b[j - 1] ? 0 : 1;
+                d[i][j] = std::min({d[i - 1][j] + 1, d[i][j - 1] + 1, d[i - 1][j - 1] + cost});
+                if (i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1]) {
+                    d[i][j] = std::min(d[i][j], d[i - 2][j - 2] + 1);
+                }
+                row_min = std::min(row_min, d[i][j]);
+            }
+            if (row_min > max) {
+                return max + 1;
+            }
+        }
+        return d[a.size()][b.size()];
+    }
+
+    static bool is_known(const std::string_view key) {
+        return std::binary_search(known_keys_.begin(), known_keys_.end(), key);
+    }
+
+    // closest known key within the allowed distance, empty if there is none
+    static std::string_view closest(const std::string_view key) {
+        if (key.size() < 5) {
+            return {};
+        }
+        const std::size_t max = key.size() < 8 ? 1 : 2;
+        std::string_view best;
+        std::size_t best_distance = max + 1;
+        for (const auto known: known_keys_) {
+            if (const auto d = distance(key, known, max); d > 0 && d < best_distance) {
+                best = known;
+                best_distance = d;
+            }
+        }
+        return best;
+    }
+
+    static std::string_view check(const std::string_view key) {
+        if (is_known(key)) {
+            return {};
+        }
+        if (const auto whole = closest(key); !whole.empty()) {
+            return whole;
+        }
+        // parts of namespaced keys, e.g. bicyle in oneway:bicyle
+        std::size_t start = 0;
+        while (start < key.size()) {
+            const auto end = std::min(key.find(':', start), key.size());
+            if (const auto part = key.substr(start, end - start); part.size() < key.size() && !is_known(part)) {
+                if (const auto known = closest(part); !known.empty()) {
+                    return known;
+                }
+            }
+            start = end + 1;
+        }
+        return {};
+    }
+
+public:
+    void add(const osmium::TagList &tags, const osmium::item_type type, const osmium::object_id_type id) {
+        for (const auto &tag: tags) {
+            auto [it, inserted] = checked_.try_emplace(tag.key());
+            if (inserted) {
+                it->second = check(it->first);
+            }
+            if (it->second.empty()) {
+                continue;
+            }
+            // the first element with the key is kept as example
+            typos_.try_emplace(it->first, typo{it->second, 0, type, id}).first->second.count++;
+        }
+    }
+
+    std::size_t size() const {
+        return typos_.size();
+    }
+
+    // one line per suspicious key with the known key, the number of tags and the first element, most frequent first
+    void print(std::ostream &out, const std::size_t limit) const {
+        std::vector<std::pair<std::string, typo>> sorted(typos_.begin(), typos_.end());
+        std::sort(sorted.begin(), sorted.end(), [](const auto &a, const auto &b) {
+            return a.second.count != b.second.count ? a.second.count > b.second.count : a.first < b.first;
+        });
+        for (std::size_t i = 0; i < sorted.size() && i < limit; i++) {
+            const auto &[key, t] = sorted[i];
+            out << "  " << key << " (" << t.known << "?): " << t.count << ", e.g. "
+                << osmium::item_type_to_char(t.example_type) << t.example_id << '\n';
+        }
+        if (sorted.size() > limit) {
+            out << "  ... " << sorted.size() - limit << " more\n";
+        }
+    }
+};
+
+#endif//OSM_TRANSFORM_TAG_KEY_TYPOS_H
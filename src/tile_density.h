@@ -0,0 +1,56 @@
+#ifndef OSM_TRANSFORM_TILE_DENSITY_H
+#define OSM_TRANSFORM_TILE_DENSITY_H
+
+#include <map>
+#include <ostream>
+#include <utility>
+
+#include <osmium/osm/location.hpp>
+
+#include "location_area_service.h"
+
+// Number of nodes and ways per 1x1 degree tile, the tiles of the area mapping grid. Ways are counted in the tile of
+// their first node with a known location.
+class TileDensity {
+    struct counts {
+        unsigned long long nodes = 0;
+        unsigned long long ways = 0;
+    };
+    GridBBox grid_bbox_;
+    // keyed by the lon and lat of the lower left corner of the tile
+    std::map<std::pair<int, int>, counts> tiles_;
+
+    bool tile(const osmium::Location l, std::pair<int, int> &tile) const {
+        return grid_bbox_.tile(l, tile.first, tile.second);
+    }
+
+public:
+    explicit TileDensity(const GridBBox grid_bbox = {}) : grid_bbox_(grid_bbox) {
+    }
+
+    void add_node(const osmium::Location l) {
+        if (std::pair<int, int> t; tile(l, t)) {
+            tiles_[t].nodes++;
+        }
+    }
+
+    void add_way(const osmium::Location first) {
+        if (std::pair<int, int> t; tile(first, t)) {
+            tiles_[t].ways++;
+        }
+    }
+
+    std::size_t size() const {
+        return tiles_.size();
+    }
+
+    // writes tile_lon,tile_lat,node_count,way_count for all tiles with elements
+    void write_csv(std::ostream &out) const {
+        out << "tile_lon,tile_lat,node_count,way_count\n";
+        for (const auto &[t, c]: tiles_) {
+            out << t.first << "," << t.second << "," << c.nodes << "," << c.ways << "\n";
+        }
+    }
+};
+
+#endif//OSM_TRANSFORM_TILE_DENSITY_H
@@ -0,0 +1,134 @@
+#ifndef OSM_TRANSFORM_UNIT_NORMALIZER_H
+#define OSM_TRANSFORM_UNIT_NORMALIZER_H
+
+#include <cctype>
+#include <charconv>
+#include <cmath>
+#include <cstdio>
+#include <functional>
+#include <optional>
+#include <set>
+#include <string>
+#include <string_view>
+#include <vector>
+
+#include <boost/algorithm/string.hpp>
+
+// Converts imperial values of the configured tag keys to metric numbers, e.g. maxheight=10'6" to 3.2 and
+// maxspeed=30 mph to 48. Keys starting with maxspeed or minspeed are speeds in mph, converted to whole km/h, all other
+// keys are lengths in feet and inches, converted to meters rounded to centimeters. Metric values, values without unit
+// and values which can't be parsed, e.g. none or 10 yards, are kept as they are.
+class UnitNormalizer {
+    std::set<std::string, std::less<>> keys_;
+    unsigned long long conversions_ = 0;
+
+    static void skip_spaces(std::string_view &value) {
+        while (!value.empty() && (value.front() == ' ' || value.front() == '\t')) value.remove_prefix(1);
+    }
+
+    static bool parse_number(std::string_view &value, double &number) {
+        skip_spaces(value);
+        const auto [end, ec] = std::from_chars(value.data(), value.data() + value.size(), number);
+        if (ec != std::errc() || !std::isfinite(number) || number < 0) {
+            return false;
+        }
+        value.remove_prefix(end - value.data());
+        return true;
+    }
+
+    // ' and " are units on their own, otherwise the letters up to the next number or space
+    static std::string parse_unit(std::string_view &value) {
+        skip_spaces(value);
+        std::size_t length = 0;
+        if (!value.empty() && (value.front() == '\'' || value.front() == '"')) {
+            length = 1;
+        } else {
+            while (length < value.size() && std::isalpha(static_cast<unsigned char>(value[length]))) length++;
+        }
+        std::string unit(value.substr(0, length));
+        value.remove_prefix(length);
+        boost::to_lower(unit);
+        return unit;
+    }
+
+    static std::optional<double> parse_mph(std::string_view value) {
+        double number;
+        if (!parse_number(value, number) || parse_unit(value) != "mph") {
+            return std::nullopt;
+        }
+        skip_spaces(value);
+        return value.empty() ? std::optional(number) : std::nullopt;
+    }
+
+    // feet, inches or feet followed by inches, e.g. 10', 6", 10'6" or 10 ft 6 in
+    static std::optional<double> parse_feet_inches(std::string_view value) {
+        double meters = 0;
+        bool feet = false;
+        bool inches = false;
+        skip_spaces(value);
+        while (!value.empty()) {
+            double number;
+            if (!parse_number(value, number)) {
+                return std::nullopt;
+            }
+            const auto unit = parse_unit(value);
+            if (!feet && !inches && (unit == "'" || unit == "ft" || unit == "feet" || unit == "foot")) {
+                meters += number * 0.3048;
+                feet = true;
+            } else if (!inches && (unit == "\"" || unit == "in" || unit == "inch" || unit == "inches")) {
+                meters += number * 0.0254;
+                inches = true;
+            } else {
+                return std::nullopt;
+            }
+            skip_spaces(value);
+        }
+        return feet || inches ? std::optional(meters) : std::nullopt;
+    }
+
+public:
+    explicit UnitNormalizer(const std::vector<std::string> &keys) : keys_(keys.begin(), keys.end()) {
+    }
+
+    static bool is_speed_key(const std::string_view key) {
+        return key.starts_with("maxspeed") || key.starts_with("minspeed");
+    }
+
+    // metric value of an imperial value of the key, empty if the value is not imperial or can't be parsed
+    static std::optional<std::string> to_metric(const std::string_view key, const std::string_view value) {
+        if (is_speed_key(key)) {
+            const auto mph = parse_mph(value);
+            return mph ? std::optional(std::to_string(std::lround(*mph * 1.609344))) : std::nullopt;
+        }
+        const auto meters = parse_feet_inches(value);
+        if (!meters) {
+            return std::nullopt;
+        }
+        char buffer[32];
+        snprintf(buffer, sizeof(buffer), "%.2f", *meters);
+        std::string result(buffer);
+        result.erase(result.find_last_not_of('0') + 1);
+        if (result.back() == '.') {
+            result.pop_back();
+        }
+        return result;
+    }
+
+    // metric value if the key is configured and the value imperial, counted as conversion
+    std::optional<std::string> normalize(const std::string_view key, const std::string_view value) {
+        if (!keys_.contains(key)) {
+            return std::nullopt;
+        }
+        auto metric = to_metric(key, value);
+        if (metric) {
+            conversions_++;
+        }
+        return metric;
+    }
+
+    unsigned long long conversions() const {
+        return conversions_;
+    }
+};
+
+#endif//OSM_TRANSFORM_UNIT_NORMALIZER_H
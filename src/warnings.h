@@ -0,0 +1,118 @@
+#ifndef OSM_TRANSFORM_WARNINGS_H
+#define OSM_TRANSFORM_WARNINGS_H
+
+#include <fstream>
+#include <iostream>
+#include <map>
+#include <sstream>
+#include <stdexcept>
+#include <string>
+
+#include <osmium/osm/item_type.hpp>
+#include <osmium/osm/types.hpp>
+
+#include "json.h"
+
+// Writes warnings as JSON lines with a category, the element if known and a message to the file given with
+// --warnings_file, e.g. {"category": "geotiff", "message": "Failed to open geotiff ..."}. Only the first limit
+// warnings of a category are written, the others are counted and summarized in one line per category on close().
+class WarningLog {
+    std::ofstream out_;
+    std::map<std::string, unsigned long long> counts_;
+    unsigned long long limit_ = 0;
+    bool quiet_ = false;
+
+    WarningLog() = default;
+
+public:
+    static WarningLog &instance() {
+        static WarningLog log;
+        return log;
+    }
+
+    // limit is the number of warnings written per category (0 = no limit)
+    void open(const std::string &path, const unsigned long long limit = 0) {
+        out_.open(path);
+        if (!out_) {
+            throw std::runtime_error("could not write warnings file " + path);
+        }
+        counts_.clear();
+        limit_ = limit;
+    }
+
+    // writes the number of omitted warnings of the categories above the limit
+    void close() {
+        if (!out_.is_open()) {
+            return;
+        }
+        for (const auto &[category, count]: counts_) {
+            if (limit_ > 0 && count > limit_) {
+                out_ << "{\"category\": " << json_string(category) << ", \"omitted\": " << count - limit_
+                     << ", \"message\": " << json_string(std::to_string(count - limit_) + " further warnings omitted") << "}\n";
+            }
+        }
+        out_.close();
+    }
+
+    bool is_open() const {
+        return out_.is_open();
+    }
+
+    void record(const std::string &category, const std::string &message,
+                const osmium::item_type type = osmium::item_type::undefined, const osmium::object_id_type id = 0) {
+        if (!out_.is_open()) {
+            return;
+        }
+        if (++counts_[category] > limit_ && limit_ > 0) {
+            return;
+        }
+        out_ << "{\"category\": " << json_string(category);
+        if (type != osmium::item_type::undefined) {
+            out_ << ", \"type\": \"" << osmium::item_type_to_name(type) << "\", \"id\": " << id;
+        }
+        out_ << ", \"message\": " << json_string(message) << "}\n";
+    }
+
+    // suppresses printing warnings, they are still recorded in the warnings file
+    void set_quiet(const bool quiet) {
+        quiet_ = quiet;
+    }
+
+    bool quiet() const {
+        return quiet_;
+    }
+
+    // number of recorded warnings per category
+    const std::map<std::string, unsigned long long> &counts() const {
+        return counts_;
+    }
+};
+
+// Prints a warning when it goes out of scope and records it in the warnings file:
+//   Warning("geotiff") << "Failed to open geotiff " << filename;
+class Warning {
+    std::string category_;
+    std::ostringstream message_;
+
+public:
+    explicit Warning(std::string category) : category_(std::move(category)) {
+    }
+
+    Warning(const Warning &) = delete;
+    Warning &operator=(const Warning &) = delete;
+
+    template <typename T>
+    Warning &operator<<(const T &value) {
+        message_ << value;
+        return *this;
+    }
+
+    ~Warning() {
+        if (!WarningLog::instance().quiet()) {
+            std::cout << "WARNING: " << message_.str() << std::endl;
+        }
+        WarningLog::instance().record(category_, message_.str());
+    }
+};
+
+#endif//OSM_TRANSFORM_WARNINGS_H
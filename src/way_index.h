@@ -0,0 +1,87 @@
+#ifndef OSM_TRANSFORM_WAY_INDEX_H
+#define OSM_TRANSFORM_WAY_INDEX_H
+
+#include <cstdint>
+#include <istream>
+#include <ostream>
+#include <stdexcept>
+#include <string>
+#include <utility>
+#include <vector>
+
+#include <boost/geometry.hpp>
+#include <boost/geometry/geometries/box.hpp>
+#include <boost/geometry/geometries/point.hpp>
+#include <boost/geometry/index/rtree.hpp>
+
+#include <osmium/osm/box.hpp>
+#include <osmium/osm/types.hpp>
+
+// Bounding boxes of the written ways for spatial lookups by consumers of the output. The file starts with the
+// signature line, followed by one binary record per way: the id as int64 and min_x, min_y, max_x, max_y as int32 in
+// 1e-7 degrees like in the pbf. The records are in the leaf order of an rtree packed from all boxes, so consecutive
+// records are close to each other and bulk loading them yields the same tree again.
+class WayIndex {
+public:
+    typedef boost::geometry::model::point<double, 2, boost::geometry::cs::cartesian> point;
+    typedef boost::geometry::model::box<point> box;
+    typedef std::pair<box, osmium::object_id_type> entry;
+    typedef boost::geometry::index::rtree<entry, boost::geometry::index::quadratic<16>> rtree;
+
+    static constexpr const char *kSignature = "osm-transform way index 1";
+
+private:
+    std::vector<std::pair<osmium::object_id_type, osmium::Box>> ways_;
+
+    static box to_box(const osmium::Box &bbox) {
+        return {point(bbox.bottom_left().lon(), bbox.bottom_left().lat()), point(bbox.top_right().lon(), bbox.top_right().lat())};
+    }
+
+public:
+    // ways without any known node location are not added
+    void add(const osmium::object_id_type id, const osmium::Box &bbox) {
+        if (bbox.valid()) {
+            ways_.emplace_back(id, bbox);
+        }
+    }
+
+    std::size_t size() const {
+        return ways_.size();
+    }
+
+    void write(std::ostream &out) const {
+        // the range constructor packs the tree, the values are the positions in ways_
+        std::vector<std::pair<box, std::size_t>> entries;
+        entries.reserve(ways_.size());
+        for (std::size_t i = 0; i < ways_.size(); i++) {
+            entries.emplace_back(to_box(ways_[i].second), i);
+        }
+        const boost::geometry::index::rtree<std::pair<box, std::size_t>, boost::geometry::index::quadratic<16>> tree(entries.begin(), entries.end());
+        out << kSignature << '\n';
+        for (const auto &[b, i]: tree) {
+            const auto &[id, bbox] = ways_[i];
+            const std::int32_t coordinates[4] = {bbox.bottom_left().x(), bbox.bottom_left().y(), bbox.top_right().x(), bbox.top_right().y()};
+            out.write(reinterpret_cast<const char *>(&id), sizeof(id));
+            out.write(reinterpret_cast<const char *>(coordinates), sizeof(coordinates));
+        }
+    }
+
+    static rtree read(std::istream &in) {
+        std::string signature;
+        getline(in, signature);
+        if (signature != kSignature) {
+            throw std::runtime_error("not an osm-transform way index");
+        }
+        std::vector<entry> entries;
+        osmium::object_id_type id;
+        std::int32_t coordinates[4];
+        while (in.read(reinterpret_cast<char *>(&id), sizeof(id)) &&
+               in.read(reinterpret_cast<char *>(coordinates), sizeof(coordinates))) {
+            const osmium::Box bbox{osmium::Location(coordinates[0], coordinates[1]), osmium::Location(coordinates[2], coordinates[3])};
+            entries.emplace_back(to_box(bbox), id);
+        }
+        return rtree(entries.begin(), entries.end());
+    }
+};
+
+#endif//OSM_TRANSFORM_WAY_INDEX_H
@@ -0,0 +1,72 @@
+# Runs osm-transform with the options handled by the executable itself, count_only, filter_only, export_accept_ids,
+# prefetch and output_source, and checks their output. Called by ctest with OSM_TRANSFORM, the executable, and WORK_DIR,
+# a scratch directory.
+file(REMOVE_RECURSE ${WORK_DIR})
+file(MAKE_DIRECTORY ${WORK_DIR})
+file(WRITE ${WORK_DIR}/input.osm
+    "<?xml version='1.0' encoding='UTF-8'?>\n"
+    "<osm version=\"0.6\" generator=\"test\">\n"
+    "  <node id=\"1\" lat=\"50.0\" lon=\"8.0\"/>\n"
+    "  <node id=\"2\" lat=\"50.1\" lon=\"8.1\"/>\n"
+    "  <node id=\"3\" lat=\"50.2\" lon=\"8.2\"/>\n"
+    "  <way id=\"10\">\n"
+    "    <nd ref=\"1\"/>\n"
+    "    <nd ref=\"2\"/>\n"
+    "    <tag k=\"highway\" v=\"primary\"/>\n"
+    "  </way>\n"
+    "</osm>\n")
+
+# runs osm-transform on the input with the given arguments, stdout is stored in output
+function(run_osm_transform)
+    execute_process(COMMAND ${OSM_TRANSFORM} -p input.osm -e ${ARGN}
+                    WORKING_DIRECTORY ${WORK_DIR}
+                    RESULT_VARIABLE result OUTPUT_VARIABLE stdout ERROR_VARIABLE error)
+    if(NOT result EQUAL 0)
+        message(FATAL_ERROR "osm-transform ${ARGN} failed with ${result}: ${error}")
+    endif()
+    set(output "${stdout}" PARENT_SCOPE)
+endfunction()
+
+# all elements of the input are counted, nothing is written
+run_osm_transform(--count_only --summary_only)
+if(NOT output MATCHES "^{\"nodes\": 3, \"ways\": 1, \"relations\": 0}\n$")
+    message(FATAL_ERROR "unexpected count_only output: ${output}")
+endif()
+if(EXISTS ${WORK_DIR}/input.ors.pbf)
+    message(FATAL_ERROR "count_only wrote input.ors.pbf")
+endif()
+
+# the untagged node 3 is not part of an accepted way
+run_osm_transform(--filter_only --export_accept_ids ids.txt)
+if(NOT output MATCHES "Accepted nodes: 2, ways: 1, relations: 0")
+    message(FATAL_ERROR "unexpected filter_only output: ${output}")
+endif()
+if(EXISTS ${WORK_DIR}/input.ors.pbf)
+    message(FATAL_ERROR "filter_only wrote input.ors.pbf")
+endif()
+file(READ ${WORK_DIR}/ids.txt ids)
+if(NOT ids STREQUAL "n1\nn2\nw10\n")
+    message(FATAL_ERROR "unexpected exported accept ids: ${ids}")
+endif()
+
+# the exported ids give the same result as the first pass
+run_osm_transform(--accept_ids_file ids.txt --summary_only)
+if(NOT output MATCHES "\"nodes\": 2, \"ways\": 1,")
+    message(FATAL_ERROR "unexpected summary with accept_ids_file: ${output}")
+endif()
+
+# reading ahead doesn't change the result
+run_osm_transform(--prefetch 1 --summary_only)
+if(NOT output MATCHES "\"nodes\": 2, \"ways\": 1,")
+    message(FATAL_ERROR "unexpected summary with prefetch: ${output}")
+endif()
+
+# the source is not stored in pbf headers, but in the sidecar file
+run_osm_transform(--output_source "OpenStreetMap contributors, SRTM" --write_config_sidecar config.json --summary_only)
+file(READ ${WORK_DIR}/config.json sidecar)
+if(NOT sidecar MATCHES "\"output_source\": \"OpenStreetMap contributors, SRTM\"")
+    message(FATAL_ERROR "output_source missing in the config sidecar: ${sidecar}")
+endif()
+if(NOT EXISTS ${WORK_DIR}/input.ors.pbf)
+    message(FATAL_ERROR "missing output input.ors.pbf")
+endif()
@@ -0,0 +1,47 @@
+# Runs osm-transform with two input files and an output_dir and checks that an output file and a summary line are
+# written for each of them. Called by ctest with OSM_TRANSFORM, the executable, and WORK_DIR, a scratch directory.
+file(REMOVE_RECURSE ${WORK_DIR})
+file(MAKE_DIRECTORY ${WORK_DIR}/out)
+foreach(name first second)
+    file(WRITE ${WORK_DIR}/${name}.osm
+        "<?xml version='1.0' encoding='UTF-8'?>\n"
+        "<osm version=\"0.6\" generator=\"test\">\n"
+        "  <node id=\"1\" lat=\"50.0\" lon=\"8.0\"/>\n"
+        "  <node id=\"2\" lat=\"50.1\" lon=\"8.1\"/>\n"
+        "  <way id=\"10\">\n"
+        "    <nd ref=\"1\"/>\n"
+        "    <nd ref=\"2\"/>\n"
+        "    <tag k=\"highway\" v=\"primary\"/>\n"
+        "  </way>\n"
+        "</osm>\n")
+endforeach()
+
+execute_process(COMMAND ${OSM_TRANSFORM} -p first.osm -p second.osm -e --output_dir out --summary_only --summary_file summary.jsonl
+                WORKING_DIRECTORY ${WORK_DIR}
+                RESULT_VARIABLE result OUTPUT_VARIABLE output ERROR_VARIABLE error)
+if(NOT result EQUAL 0)
+    message(FATAL_ERROR "osm-transform failed with ${result}: ${error}")
+endif()
+file(READ ${WORK_DIR}/summary.jsonl summary_file)
+
+foreach(summary output summary_file)
+    string(REGEX MATCHALL "[^\n]+" lines "${${summary}}")
+    list(LENGTH lines count)
+    if(NOT count EQUAL 2)
+        message(FATAL_ERROR "expected 2 summary lines in ${summary}, got ${count}: ${${summary}}")
+    endif()
+    list(GET lines 0 first_line)
+    list(GET lines 1 second_line)
+    if(NOT first_line MATCHES "^{\"input\": \"first.osm\", \"output\": \"out/first.ors.pbf\".*\"nodes\": 2, \"ways\": 1,")
+        message(FATAL_ERROR "unexpected first summary line in ${summary}: ${first_line}")
+    endif()
+    if(NOT second_line MATCHES "^{\"input\": \"second.osm\", \"output\": \"out/second.ors.pbf\".*\"nodes\": 2, \"ways\": 1,")
+        message(FATAL_ERROR "unexpected second summary line in ${summary}: ${second_line}")
+    endif()
+endforeach()
+
+foreach(name first second)
+    if(NOT EXISTS ${WORK_DIR}/out/${name}.ors.pbf)
+        message(FATAL_ERROR "missing output out/${name}.ors.pbf")
+    endif()
+endforeach()
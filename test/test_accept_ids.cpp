@@ -0,0 +1,49 @@
+#include <boost/test/unit_test.hpp>
+
+#include <sstream>
+#include <stdexcept>
+
+#include "accept_ids.h"
+
+BOOST_AUTO_TEST_SUITE( test_accept_ids )
+
+BOOST_AUTO_TEST_CASE( read ) {
+    std::istringstream in("n1\r\nw2 highway\r\n\r\n# comment\nr3#route\n4\tuntyped node\n");
+    osmium::nwr_array<osmium::index::IdSetDense<osmium::unsigned_object_id_type>> ids;
+    read_accept_ids(in, "ids.txt", ids);
+
+    BOOST_TEST(ids.nodes().size() == 2);
+    BOOST_TEST(ids.nodes().get(1));
+    BOOST_TEST(ids.nodes().get(4));
+    BOOST_TEST(ids.ways().size() == 1);
+    BOOST_TEST(ids.ways().get(2));
+    BOOST_TEST(ids.relations().size() == 1);
+    BOOST_TEST(ids.relations().get(3));
+}
+
+BOOST_AUTO_TEST_CASE( read_invalid ) {
+    for (const auto *content: {"n1\nx2\n", "n1\nw\n", "n-5\n", "wabc\n"}) {
+        std::istringstream in(content);
+        osmium::nwr_array<osmium::index::IdSetDense<osmium::unsigned_object_id_type>> ids;
+        BOOST_CHECK_THROW(read_accept_ids(in, "ids.txt", ids), std::runtime_error);
+    }
+}
+
+BOOST_AUTO_TEST_CASE( write_and_read ) {
+    osmium::nwr_array<osmium::index::IdSetDense<osmium::unsigned_object_id_type>> ids;
+    ids.nodes().set(5);
+    ids.nodes().set(2);
+    ids.ways().set(7);
+    ids.relations().set(9);
+    std::stringstream out;
+    write_accept_ids(out, ids);
+    BOOST_TEST(out.str() == "n2\nn5\nw7\nr9\n");
+
+    osmium::nwr_array<osmium::index::IdSetDense<osmium::unsigned_object_id_type>> read;
+    read_accept_ids(out, "ids.txt", read);
+    BOOST_TEST(read.nodes().size() == 2);
+    BOOST_TEST(read.ways().get(7));
+    BOOST_TEST(read.relations().get(9));
+}
+
+BOOST_AUTO_TEST_SUITE_END()
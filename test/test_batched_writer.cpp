@@ -0,0 +1,40 @@
+#include <boost/test/unit_test.hpp>
+
+#include <osmium/builder/osm_object_builder.hpp>
+#include <osmium/io/opl_input.hpp>
+#include <osmium/io/opl_output.hpp>
+#include <osmium/io/reader.hpp>
+#include <osmium/memory/buffer.hpp>
+
+#include "batched_writer.h"
+
+BOOST_AUTO_TEST_SUITE( test_batched_writer )
+
+BOOST_AUTO_TEST_CASE( flush_remainder ) {
+    {
+        osmium::io::Writer writer{"batched_writer_test.opl", osmium::io::overwrite::allow};
+        BatchedWriter batch{writer, 2};
+        for (const osmium::object_id_type id: {1, 2, 3}) {
+            osmium::memory::Buffer buffer{1024, osmium::memory::Buffer::auto_grow::yes};
+            {
+                osmium::builder::NodeBuilder builder{buffer};
+                builder.set_id(id);
+                builder.set_location(osmium::Location(8.0, 49.0));
+            }
+            buffer.commit();
+            batch(std::move(buffer));
+        }
+        batch.flush();
+        writer.close();
+    }
+
+    osmium::io::Reader reader{"batched_writer_test.opl"};
+    std::size_t count = 0;
+    while (const auto buffer = reader.read()) {
+        count += std::distance(buffer.begin(), buffer.end());
+    }
+    reader.close();
+    BOOST_TEST(count == 3);
+}
+
+BOOST_AUTO_TEST_SUITE_END()
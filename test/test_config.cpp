@@ -0,0 +1,32 @@
+#include <boost/test/unit_test.hpp>
+
+#include <set>
+#include <sstream>
+#include <string>
+
+#include "config.h"
+
+BOOST_AUTO_TEST_SUITE( test_config )
+
+BOOST_AUTO_TEST_CASE( sidecar_contains_all_options ) {
+    Config config;
+    std::ostringstream out;
+    config.write_json(out);
+    const auto json = out.str();
+
+    // skip_elevation is written as its negation add_elevation
+    const std::set<std::string> renamed{"skip_elevation"};
+    for (const auto &description: {config.options(), config.hidden_options()}) {
+        for (const auto &option: description.options()) {
+            const auto &name = option->long_name();
+            if (renamed.contains(name)) {
+                continue;
+            }
+            BOOST_TEST_INFO("option " << name);
+            BOOST_CHECK(json.find("\"" + name + "\": ") != std::string::npos);
+        }
+    }
+    BOOST_CHECK(json.find("\"add_elevation\": true") != std::string::npos);
+}
+
+BOOST_AUTO_TEST_SUITE_END()
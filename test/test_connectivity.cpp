@@ -0,0 +1,24 @@
+#include <boost/test/unit_test.hpp>
+
+#include <vector>
+
+#include "connectivity.h"
+
+BOOST_AUTO_TEST_SUITE( test_connectivity )
+
+BOOST_AUTO_TEST_CASE( components ) {
+    Connectivity connectivity;
+    const std::vector<osmium::NodeRef> way1{1, 2, 3};
+    const std::vector<osmium::NodeRef> way2{3, 4};
+    const std::vector<osmium::NodeRef> way3{5, 6};
+    const std::vector<osmium::NodeRef> way4{4, 7, 1};
+    connectivity.add_way(way1);
+    connectivity.add_way(way2);
+    connectivity.add_way(way3);
+    connectivity.add_way(way4);
+    BOOST_TEST(connectivity.components() == 2);
+    BOOST_TEST(connectivity.largest() == 3);
+    BOOST_TEST(connectivity.ways() == 4);
+}
+
+BOOST_AUTO_TEST_SUITE_END()
@@ -0,0 +1,49 @@
+#include <boost/test/unit_test.hpp>
+
+#include <filesystem>
+#include <fstream>
+#include <stdexcept>
+#include <string>
+
+#include "country_attributes.h"
+
+BOOST_AUTO_TEST_SUITE( test_country_attributes )
+
+static std::string write_file(const std::string &content) {
+    const auto path = (std::filesystem::temp_directory_path() / "osm_transform_test_country_attributes.csv").string();
+    std::ofstream out(path);
+    out << content;
+    return path;
+}
+
+BOOST_AUTO_TEST_CASE( load ) {
+    const auto path = write_file("country;driving_side;maxspeed:unit\nDEU;right;kmh\n\nGBR;left;mph\n");
+    CountryAttributes attributes;
+    attributes.load(path);
+    std::filesystem::remove(path);
+
+    BOOST_TEST(attributes.size() == 2);
+    BOOST_TEST(attributes.names().size() == 2);
+    BOOST_TEST(attributes.index("maxspeed:unit") == 1);
+    BOOST_TEST(attributes.index("unknown") == -1);
+    BOOST_TEST(attributes.get("GBR", 0) == "left");
+    BOOST_TEST(attributes.get("FRA", 0) == "");
+}
+
+BOOST_AUTO_TEST_CASE( header_without_attributes ) {
+    for (const auto *content: {"\nDEU;right\n", "country\nDEU\n"}) {
+        const auto path = write_file(content);
+        CountryAttributes attributes;
+        BOOST_CHECK_THROW(attributes.load(path), std::runtime_error);
+        std::filesystem::remove(path);
+    }
+}
+
+BOOST_AUTO_TEST_CASE( empty_file ) {
+    const auto path = write_file("");
+    CountryAttributes attributes;
+    BOOST_CHECK_THROW(attributes.load(path), std::runtime_error);
+    std::filesystem::remove(path);
+}
+
+BOOST_AUTO_TEST_SUITE_END()
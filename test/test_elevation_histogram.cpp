@@ -0,0 +1,28 @@
+#include <boost/test/unit_test.hpp>
+
+#include <sstream>
+
+#include "elevation_histogram.h"
+
+BOOST_AUTO_TEST_SUITE( test_elevation_histogram )
+
+BOOST_AUTO_TEST_CASE( bins ) {
+    ElevationHistogram histogram(100);
+    histogram.add(0);
+    histogram.add(99.9);
+    histogram.add(100);
+    histogram.add(-0.5);
+    histogram.add(-9999);
+    BOOST_TEST(histogram.count() == 5);
+    BOOST_TEST(histogram.size() == 4);
+
+    std::ostringstream out;
+    histogram.write_csv(out);
+    BOOST_TEST(out.str() == "lower,upper,node_count\n-10000,-9900,1\n-100,0,1\n0,100,2\n100,200,1\n");
+
+    std::ostringstream printed;
+    histogram.print(printed);
+    BOOST_TEST(printed.str() == "  [-10000, -9900): 1\n  [-100, 0): 1\n  [0, 100): 2\n  [100, 200): 1\n");
+}
+
+BOOST_AUTO_TEST_SUITE_END()
@@ -8,7 +8,9 @@
 #include <osmium/visitor.hpp>
 
 #define private public
+#include "any_tag_value_filter.h"
 #include "firstpass_handler.h"
+#include "required_keys_filter.h"
 #include "test_utils.h"
 
 BOOST_AUTO_TEST_SUITE( test_first_pass )
@@ -58,4 +60,266 @@ BOOST_AUTO_TEST_CASE(bla) {
 
 }
 
+BOOST_AUTO_TEST_CASE(merge_duplicate_ways) {
+    boost::regex remove_tags("(.*:)?source(:.*)?|(.*:)?note(:.*)?|url|created_by|fixme|wikipedia", boost::regex::icase);
+    osmium::nwr_array<osmium::index::IdSetDense<osmium::unsigned_object_id_type>> valid_ids;
+    osmium::nwr_array<osmium::index::IdSetSmall<osmium::unsigned_object_id_type>> no_elevation;
+    FirstPassHandler handler(remove_tags, valid_ids, no_elevation);
+    handler.merge_duplicate_ways_ = true;
+
+    osmium::memory::Buffer buffer{1024, osmium::memory::Buffer::auto_grow::yes};
+    add_way(buffer, 12, {{"highway", "residential"}, {"surface", "asphalt"}}, {123, 234, 345});
+    add_way(buffer, 13, {{"highway", "residential"}, {"maxspeed", "30"}}, {123, 234, 345});
+    add_way(buffer, 14, {{"highway", "residential"}}, {345, 234, 123});
+    buffer.commit();
+
+    osmium::apply(buffer, handler);
+    BOOST_TEST(valid_ids.ways().get(12));
+    BOOST_TEST(!valid_ids.ways().get(13));
+    BOOST_TEST(valid_ids.ways().get(14));
+    BOOST_TEST(handler.merged_ways_ == 1);
+    BOOST_TEST(handler.merged_way_tags_.size() == 1);
+    BOOST_TEST(handler.merged_way_tags_[12].size() == 2);
+}
+
+BOOST_AUTO_TEST_CASE(merge_duplicate_ways_rejected_first) {
+    boost::regex remove_tags("(.*:)?source(:.*)?|(.*:)?note(:.*)?|url|created_by|fixme|wikipedia", boost::regex::icase);
+    osmium::nwr_array<osmium::index::IdSetDense<osmium::unsigned_object_id_type>> valid_ids;
+    osmium::nwr_array<osmium::index::IdSetSmall<osmium::unsigned_object_id_type>> no_elevation;
+    FirstPassHandler handler(remove_tags, valid_ids, no_elevation);
+    handler.merge_duplicate_ways_ = true;
+    handler.element_filter_ = [](const osmium::OSMObject &object) { return object.id() != 12; };
+
+    osmium::memory::Buffer buffer{1024, osmium::memory::Buffer::auto_grow::yes};
+    add_way(buffer, 12, {{"highway", "residential"}}, {123, 234, 345});
+    add_way(buffer, 13, {{"highway", "residential"}}, {123, 234, 345});
+    buffer.commit();
+
+    osmium::apply(buffer, handler);
+    BOOST_TEST(!valid_ids.ways().get(12));
+    BOOST_TEST(valid_ids.ways().get(13));
+    BOOST_TEST(handler.merged_ways_ == 0);
+}
+
+BOOST_AUTO_TEST_CASE(drop_relation_member_ways) {
+    boost::regex remove_tags("(.*:)?source(:.*)?|(.*:)?note(:.*)?|url|created_by|fixme|wikipedia", boost::regex::icase);
+    osmium::nwr_array<osmium::index::IdSetDense<osmium::unsigned_object_id_type>> valid_ids;
+    osmium::nwr_array<osmium::index::IdSetSmall<osmium::unsigned_object_id_type>> no_elevation;
+    FirstPassHandler handler(remove_tags, valid_ids, no_elevation);
+    handler.drop_relation_member_ways_ = true;
+
+    osmium::memory::Buffer buffer{1024, osmium::memory::Buffer::auto_grow::yes};
+    add_way(buffer, 12, {{"name", "Market Square"}}, {123, 234, 345, 123});
+    add_way(buffer, 13, {{"highway", "pedestrian"}, {"name", "Market Square"}}, {123, 234, 345, 123});
+    add_way(buffer, 14, {{"name", "Old Town"}}, {456, 567, 678, 456});
+    {
+        osmium::builder::RelationBuilder builder{buffer};
+        builder.set_id(20);
+        {
+            osmium::builder::TagListBuilder tags{builder};
+            tags.add_tag("type", "multipolygon");
+            tags.add_tag("highway", "pedestrian");
+        }
+        osmium::builder::RelationMemberListBuilder members{builder};
+        members.add_member(osmium::item_type::way, 12, "outer");
+        members.add_member(osmium::item_type::way, 13, "outer");
+    }
+    buffer.commit();
+
+    osmium::apply(buffer, handler);
+    BOOST_TEST(valid_ids.relations().get(20));
+    BOOST_TEST(!valid_ids.ways().get(12));
+    BOOST_TEST(valid_ids.ways().get(13));
+    BOOST_TEST(valid_ids.ways().get(14));
+    BOOST_TEST(handler.relation_member_ways_dropped_ == 1);
+}
+
+BOOST_AUTO_TEST_CASE(any_tag_value_filter) {
+    boost::regex remove_tags("(.*:)?source(:.*)?|(.*:)?note(:.*)?|url|created_by|fixme|wikipedia", boost::regex::icase);
+    osmium::nwr_array<osmium::index::IdSetDense<osmium::unsigned_object_id_type>> valid_ids;
+    osmium::nwr_array<osmium::index::IdSetSmall<osmium::unsigned_object_id_type>> no_elevation;
+    FirstPassHandler handler(remove_tags, valid_ids, no_elevation);
+    osmium::osm_entity_bits::type types;
+    BOOST_TEST(AnyTagValueFilter::parse_types("way, relation", types));
+    BOOST_TEST(!AnyTagValueFilter::parse_types("way,area", types));
+    BOOST_TEST(AnyTagValueFilter::parse_types("way", types));
+    const AnyTagValueFilter filter("construction", false, types);
+    handler.element_filter_ = [&filter](const osmium::OSMObject &object) { return filter.accept(object); };
+
+    osmium::memory::Buffer buffer{1024, osmium::memory::Buffer::auto_grow::yes};
+    add_way(buffer, 12, {{"highway", "construction"}}, {123, 234});
+    add_way(buffer, 13, {{"highway", "residential"}, {"name", "Construction Road"}}, {234, 345});
+    add_way(buffer, 14, {{"highway", "residential"}, {"construction", "yes"}}, {345, 456});
+    osmium::apply(buffer, handler);
+    BOOST_TEST(!valid_ids.ways().get(12));
+    BOOST_TEST(valid_ids.ways().get(13));
+    BOOST_TEST(valid_ids.ways().get(14));
+    BOOST_TEST(handler.ways_rejected_by_filter_ == 1);
+}
+
+BOOST_AUTO_TEST_CASE(node_roles) {
+    boost::regex remove_tags("(.*:)?source(:.*)?|(.*:)?note(:.*)?|url|created_by|fixme|wikipedia", boost::regex::icase);
+    osmium::nwr_array<osmium::index::IdSetDense<osmium::unsigned_object_id_type>> valid_ids;
+    osmium::nwr_array<osmium::index::IdSetSmall<osmium::unsigned_object_id_type>> no_elevation;
+    osmium::index::IdSetDense<osmium::unsigned_object_id_type> way_nodes;
+    osmium::index::IdSetDense<osmium::unsigned_object_id_type> junction_nodes;
+    FirstPassHandler handler(remove_tags, valid_ids, no_elevation);
+    handler.way_nodes_ = &way_nodes;
+    handler.junction_nodes_ = &junction_nodes;
+
+    osmium::memory::Buffer buffer{1024, osmium::memory::Buffer::auto_grow::yes};
+    add_way(buffer, 12, {{"highway", "residential"}}, {123, 234, 345});
+    add_way(buffer, 13, {{"highway", "service"}}, {345, 456, 567, 345});
+    osmium::apply(buffer, handler);
+    BOOST_TEST(way_nodes.size() == 5);
+    BOOST_TEST(junction_nodes.size() == 1);
+    BOOST_TEST(junction_nodes.get(345));
+}
+
+BOOST_AUTO_TEST_CASE(node_degrees) {
+    boost::regex remove_tags("(.*:)?source(:.*)?|(.*:)?note(:.*)?|url|created_by|fixme|wikipedia", boost::regex::icase);
+    osmium::nwr_array<osmium::index::IdSetDense<osmium::unsigned_object_id_type>> valid_ids;
+    osmium::nwr_array<osmium::index::IdSetSmall<osmium::unsigned_object_id_type>> no_elevation;
+    NodeDegrees node_degrees;
+    FirstPassHandler handler(remove_tags, valid_ids, no_elevation);
+    handler.node_degrees_ = &node_degrees;
+
+    osmium::memory::Buffer buffer{1024, osmium::memory::Buffer::auto_grow::yes};
+    add_way(buffer, 12, {{"highway", "residential"}}, {123, 234, 345});
+    add_way(buffer, 13, {{"highway", "service"}}, {345, 456, 567, 345});
+    add_way(buffer, 14, {{"highway", "footway"}}, {234, 345});
+    osmium::apply(buffer, handler);
+    node_degrees.finish();
+    BOOST_TEST(node_degrees.get(123) == 1);
+    BOOST_TEST(node_degrees.get(234) == 2);
+    BOOST_TEST(node_degrees.get(345) == 3);
+    BOOST_TEST(node_degrees.get(456) == 1);
+    BOOST_TEST(node_degrees.get(999) == 0);
+    BOOST_TEST(node_degrees.shared_nodes() == 2);
+
+    NodeDegrees capped(2);
+    capped.add_way({1, 2});
+    capped.add_way({2, 3});
+    capped.add_way({2, 4});
+    capped.finish();
+    BOOST_TEST(capped.get(2) == 2);
+}
+
+BOOST_AUTO_TEST_CASE(validate_refs) {
+    boost::regex remove_tags("(.*:)?source(:.*)?|(.*:)?note(:.*)?|url|created_by|fixme|wikipedia", boost::regex::icase);
+    osmium::nwr_array<osmium::index::IdSetDense<osmium::unsigned_object_id_type>> valid_ids;
+    osmium::nwr_array<osmium::index::IdSetSmall<osmium::unsigned_object_id_type>> no_elevation;
+    FirstPassHandler handler(remove_tags, valid_ids, no_elevation);
+    handler.validate_refs_ = true;
+    handler.generated_id_base_ = 1000;
+
+    osmium::memory::Buffer buffer{1024, osmium::memory::Buffer::auto_grow::yes};
+    add_way(buffer, 12, {{"highway", "residential"}}, {-5, 123, 234});
+    add_way(buffer, 13, {{"highway", "residential"}}, {0, 345, 2000});
+    osmium::apply(buffer, handler);
+    BOOST_TEST(valid_ids.ways().get(12));
+    BOOST_TEST(!valid_ids.ways().get(13));
+    BOOST_TEST(valid_ids.nodes().size() == 2);
+    BOOST_TEST(valid_ids.nodes().get(123));
+    BOOST_TEST(valid_ids.nodes().get(234));
+    BOOST_TEST(handler.invalid_refs_ == 3);
+    BOOST_TEST(handler.ways_with_invalid_refs_ == 2);
+    BOOST_TEST(handler.ways_dropped_by_invalid_refs_ == 1);
+}
+
+BOOST_AUTO_TEST_CASE(validate_refs_without_interpolation) {
+    boost::regex remove_tags("(.*:)?source(:.*)?|(.*:)?note(:.*)?|url|created_by|fixme|wikipedia", boost::regex::icase);
+    osmium::nwr_array<osmium::index::IdSetDense<osmium::unsigned_object_id_type>> valid_ids;
+    osmium::nwr_array<osmium::index::IdSetSmall<osmium::unsigned_object_id_type>> no_elevation;
+    FirstPassHandler handler(remove_tags, valid_ids, no_elevation);
+    handler.validate_refs_ = true;
+
+    osmium::memory::Buffer buffer{1024, osmium::memory::Buffer::auto_grow::yes};
+    add_way(buffer, 12, {{"highway", "residential"}}, {12000000000, 12000000001, -1});
+    osmium::apply(buffer, handler);
+    BOOST_TEST(valid_ids.ways().get(12));
+    BOOST_TEST(valid_ids.nodes().size() == 2);
+    BOOST_TEST(handler.invalid_refs_ == 1);
+}
+
+BOOST_AUTO_TEST_CASE(max_relation_members) {
+    boost::regex remove_tags("(.*:)?source(:.*)?|(.*:)?note(:.*)?|url|created_by|fixme|wikipedia", boost::regex::icase);
+    osmium::nwr_array<osmium::index::IdSetDense<osmium::unsigned_object_id_type>> valid_ids;
+    osmium::nwr_array<osmium::index::IdSetSmall<osmium::unsigned_object_id_type>> no_elevation;
+    FirstPassHandler handler(remove_tags, valid_ids, no_elevation);
+    handler.max_relation_members_ = 2;
+
+    osmium::memory::Buffer buffer{1024, osmium::memory::Buffer::auto_grow::yes};
+    for (const osmium::object_id_type id: {20, 21}) {
+        osmium::builder::RelationBuilder builder{buffer};
+        builder.set_id(id);
+        {
+            osmium::builder::TagListBuilder tags{builder};
+            tags.add_tag("type", "route");
+            tags.add_tag("route", "bicycle");
+        }
+        osmium::builder::RelationMemberListBuilder members{builder};
+        members.add_member(osmium::item_type::node, id * 10 + 1, "");
+        members.add_member(osmium::item_type::node, id * 10 + 2, "");
+        if (id == 21) {
+            members.add_member(osmium::item_type::node, id * 10 + 3, "");
+        }
+    }
+    buffer.commit();
+
+    osmium::apply(buffer, handler);
+    BOOST_TEST(valid_ids.relations().get(20));
+    BOOST_TEST(!valid_ids.relations().get(21));
+    BOOST_TEST(valid_ids.nodes().size() == 2);
+    BOOST_TEST(handler.relations_dropped_by_member_count_ == 1);
+}
+
+BOOST_AUTO_TEST_CASE(required_keys_filter) {
+    boost::regex remove_tags("(.*:)?source(:.*)?|(.*:)?note(:.*)?|url|created_by|fixme|wikipedia", boost::regex::icase);
+    osmium::nwr_array<osmium::index::IdSetDense<osmium::unsigned_object_id_type>> valid_ids;
+    osmium::nwr_array<osmium::index::IdSetSmall<osmium::unsigned_object_id_type>> no_elevation;
+    FirstPassHandler handler(remove_tags, valid_ids, no_elevation);
+    const RequiredKeysFilter filter({"highway", "name"}, osmium::osm_entity_bits::way);
+    handler.element_filter_ = [&filter](const osmium::OSMObject &object) { return filter.accept(object); };
+
+    osmium::memory::Buffer buffer{1024, osmium::memory::Buffer::auto_grow::yes};
+    add_way(buffer, 12, {{"highway", "residential"}}, {123, 234});
+    add_way(buffer, 13, {{"highway", "residential"}, {"name", "Main Street"}}, {234, 345});
+    osmium::apply(buffer, handler);
+    BOOST_TEST(!valid_ids.ways().get(12));
+    BOOST_TEST(valid_ids.ways().get(13));
+    BOOST_TEST(handler.ways_rejected_by_filter_ == 1);
+}
+
+BOOST_AUTO_TEST_CASE(relation_label_nodes) {
+    boost::regex remove_tags("(.*:)?source(:.*)?|(.*:)?note(:.*)?|url|created_by|fixme|wikipedia", boost::regex::icase);
+    osmium::nwr_array<osmium::index::IdSetDense<osmium::unsigned_object_id_type>> valid_ids;
+    osmium::nwr_array<osmium::index::IdSetSmall<osmium::unsigned_object_id_type>> no_elevation;
+    osmium::index::IdSetDense<osmium::unsigned_object_id_type> label_nodes;
+    FirstPassHandler handler(remove_tags, valid_ids, no_elevation);
+    handler.label_nodes_ = &label_nodes;
+
+    osmium::memory::Buffer buffer{1024, osmium::memory::Buffer::auto_grow::yes};
+    {
+        osmium::builder::RelationBuilder builder{buffer};
+        builder.set_id(20);
+        {
+            osmium::builder::TagListBuilder tags{builder};
+            tags.add_tag("type", "route");
+            tags.add_tag("route", "hiking");
+        }
+        osmium::builder::RelationMemberListBuilder members{builder};
+        members.add_member(osmium::item_type::node, 101, "label");
+        members.add_member(osmium::item_type::node, 102, "admin_centre");
+        members.add_member(osmium::item_type::node, 103, "stop");
+    }
+    buffer.commit();
+
+    osmium::apply(buffer, handler);
+    BOOST_TEST(label_nodes.size() == 2);
+    BOOST_TEST(label_nodes.get(101));
+    BOOST_TEST(label_nodes.get(102));
+    BOOST_TEST(valid_ids.nodes().get(103));
+}
+
 BOOST_AUTO_TEST_SUITE_END()
@@ -0,0 +1,55 @@
+#include <boost/test/unit_test.hpp>
+
+#include <filesystem>
+#include <fstream>
+
+#include <zlib.h>
+
+#include "input_format.h"
+
+BOOST_AUTO_TEST_SUITE( test_input_format )
+
+BOOST_AUTO_TEST_CASE( text ) {
+    BOOST_TEST(InputFormat::text_format("<?xml version='1.0' encoding='UTF-8'?>\n<osm") == "osm");
+    BOOST_TEST(InputFormat::text_format("\xef\xbb\xbf  <osm version=\"0.6\">") == "osm");
+    BOOST_TEST(InputFormat::text_format("n123 v1 dV c0 t i0 u T x8.0 y50.0") == "opl");
+    BOOST_TEST(InputFormat::text_format("w-1 v1 dV") == "opl");
+    BOOST_TEST(InputFormat::text_format("name,lon,lat").empty());
+    BOOST_TEST(InputFormat::text_format("").empty());
+}
+
+BOOST_AUTO_TEST_CASE( files ) {
+    const auto dir = std::filesystem::temp_directory_path() / "osm-transform-input-format";
+    std::filesystem::create_directories(dir);
+
+    const auto pbf = (dir / "extract").string();
+    std::ofstream(pbf, std::ios::binary) << std::string("\x00\x00\x00\x0d\x0a\x09OSMHeader\x18\x7c", 17);
+    BOOST_TEST(InputFormat::detect(pbf) == "pbf");
+    BOOST_TEST(InputFormat::file(pbf).format() == osmium::io::file_format::pbf);
+
+    const auto xml = (dir / "extract.pbf").string();
+    std::ofstream(xml) << "<?xml version='1.0' encoding='UTF-8'?>\n<osm version=\"0.6\">\n</osm>\n";
+    BOOST_TEST(InputFormat::detect(xml) == "osm");
+    BOOST_TEST(InputFormat::file(xml).format() == osmium::io::file_format::xml);
+
+    const auto gz = (dir / "extract.gz").string();
+    gzFile file = gzopen(gz.c_str(), "wb");
+    gzputs(file, "<osm version=\"0.6\">\n</osm>\n");
+    gzclose(file);
+    BOOST_TEST(InputFormat::detect(gz) == "osm.gz");
+    BOOST_TEST(InputFormat::file(gz).compression() == osmium::io::file_compression::gzip);
+
+    // the extension is kept if the content matches it
+    const auto osh = (dir / "history.osh.pbf").string();
+    std::filesystem::copy_file(pbf, osh);
+    BOOST_TEST(InputFormat::file(osh).has_multiple_object_versions());
+
+    const auto csv = (dir / "areas.csv").string();
+    std::ofstream(csv) << "id,geometry\n";
+    BOOST_TEST(InputFormat::detect(csv).empty());
+    BOOST_TEST(InputFormat::detect((dir / "missing").string()).empty());
+
+    std::filesystem::remove_all(dir);
+}
+
+BOOST_AUTO_TEST_SUITE_END()
@@ -1,5 +1,10 @@
 #include <boost/test/unit_test.hpp>
 
+#include <filesystem>
+#include <fstream>
+#include <iostream>
+#include <sstream>
+
 #include "location_area_service.h"
 
 BOOST_AUTO_TEST_SUITE( test_locacion_area )
@@ -8,7 +13,7 @@ BOOST_AUTO_TEST_CASE( test_location_area_service )
     std::string geo_type("wkt");
     std::string prefix("mapping_");
     LocationAreaService location_area_service(true, 0, 2, geo_type, true, prefix);
-    location_area_service.load("test/mapping_test.csv");
+    location_area_service.load({"test/mapping_test.csv"});
 
     {
         const auto areas = location_area_service.get_area(osmium::Location(6.306152343750001, 50.05713877598692));
@@ -30,4 +35,235 @@ BOOST_AUTO_TEST_CASE( test_location_area_service )
 
 
 }
+
+BOOST_AUTO_TEST_CASE( test_location_area_service_ambiguous )
+{
+    std::string geo_type("wkt");
+    std::string prefix("mapping_");
+    LocationAreaService location_area_service(false, 0, 2, geo_type, true, prefix);
+    location_area_service.load({"test/mapping_test.csv"});
+
+    bool ambiguous = true;
+    auto areas = location_area_service.get_area(osmium::Location(10.5, 51.5), &ambiguous);
+    BOOST_CHECK_EQUAL(areas.size(), 1);
+    BOOST_CHECK_EQUAL(areas[0], "DEU");
+    BOOST_CHECK(!ambiguous);
+
+    // the tile contains the border to Belgium
+    areas = location_area_service.get_area(osmium::Location(6.0900938, 50.7225850), &ambiguous);
+    BOOST_CHECK_EQUAL(areas.size(), 1);
+    BOOST_CHECK_EQUAL(areas[0], "DEU");
+    BOOST_CHECK(ambiguous);
+}
+
+BOOST_AUTO_TEST_CASE( test_location_area_service_grid_bbox )
+{
+    std::string geo_type("wkt");
+    std::string prefix("mapping_bbox_");
+    LocationAreaService location_area_service(false, 0, 2, geo_type, true, prefix, GridBBox{5, 49, 9, 52});
+    location_area_service.load({"test/mapping_test.csv"});
+
+    {
+        const auto areas = location_area_service.get_area(osmium::Location(6.0900938, 50.7225850));
+        BOOST_CHECK_EQUAL(areas.size(), 1);
+        BOOST_CHECK_EQUAL(areas[0], "DEU");
+    }
+
+    {
+        const auto areas = location_area_service.get_area(osmium::Location(6.0902180,  50.7220057));
+        BOOST_CHECK_EQUAL(areas.size(), 1);
+        BOOST_CHECK_EQUAL(areas[0], "BEL");
+    }
+
+    {
+        const auto areas = location_area_service.get_area(osmium::Location(10.0, 50.0));
+        BOOST_CHECK(areas.empty());
+    }
+}
+
+BOOST_AUTO_TEST_CASE( test_location_area_service_grid_index )
+{
+    std::string geo_type("wkt");
+    std::string prefix("mapping_");
+    LocationAreaService location_area_service(false, 0, 2, geo_type, true, prefix);
+    grid_id_t index;
+
+    BOOST_CHECK(location_area_service.grid_index(osmium::Location(-180.0, -90.0), index));
+    BOOST_CHECK_EQUAL(index, 0);
+    BOOST_CHECK(location_area_service.grid_index(osmium::Location(180.0, 0.5), index));
+    BOOST_CHECK_EQUAL(index, 90 * 360 + 359);
+    BOOST_CHECK(location_area_service.grid_index(osmium::Location(179.9999999, 90.0), index));
+    BOOST_CHECK_EQUAL(index, 179 * 360 + 359);
+    BOOST_CHECK(!location_area_service.grid_index(osmium::Location(-180.0000001, 0.5), index));
+    BOOST_CHECK(!location_area_service.grid_index(osmium::Location(), index));
+}
+
+BOOST_AUTO_TEST_CASE( test_location_area_service_multiple_files )
+{
+    // BEL and DEU in the first file, LUX and NLD in the second
+    std::ifstream in("test/mapping_test.csv");
+    std::ofstream first("mapping_test_1.csv");
+    std::ofstream second("mapping_test_2.csv");
+    std::string line;
+    for (int row = 0; getline(in, line); row++) {
+        if (row == 0) {
+            first << line << "\n";
+            second << line << "\n";
+        } else {
+            (row <= 2 ? first : second) << line << "\n";
+        }
+    }
+    first.close();
+    second.close();
+
+    std::string geo_type("wkt");
+    std::string prefix("mapping_multi_");
+    LocationAreaService location_area_service(false, 0, 2, geo_type, true, prefix, GridBBox{5, 49, 9, 52});
+    location_area_service.load({"mapping_test_1.csv", "mapping_test_2.csv"});
+    BOOST_CHECK(location_area_service.is_initialized());
+
+    {
+        const auto areas = location_area_service.get_area(osmium::Location(6.0900938, 50.7225850));
+        BOOST_CHECK_EQUAL(areas.size(), 1);
+        BOOST_CHECK_EQUAL(areas[0], "DEU");
+    }
+
+    {
+        const auto areas = location_area_service.get_area(osmium::Location(6.13, 49.61));
+        BOOST_CHECK_EQUAL(areas.size(), 1);
+        BOOST_CHECK_EQUAL(areas[0], "LUX");
+    }
+
+    std::ofstream other("mapping_test_3.csv");
+    other << "iso;geom\n";
+    other.close();
+    std::string other_prefix("mapping_multi_other_");
+    LocationAreaService mismatch(false, 0, 2, geo_type, true, other_prefix, GridBBox{5, 49, 9, 52});
+    mismatch.load({"mapping_test_1.csv", "mapping_test_3.csv"});
+    BOOST_CHECK(!mismatch.is_initialized());
+}
+
+BOOST_AUTO_TEST_CASE( test_location_area_service_verify_index )
+{
+    std::string geo_type("wkt");
+    std::string prefix("mapping_verify_");
+    LocationAreaService location_area_service(false, 0, 2, geo_type, true, prefix, GridBBox{5, 49, 9, 52});
+    location_area_service.verify_index_ = true;
+    location_area_service.load({"test/mapping_test.csv"});
+    BOOST_CHECK_EQUAL(location_area_service.verify_processed_files(), 0);
+
+    std::ofstream id_file(prefix + "id.csv");
+    id_file << "1;BEL\n";
+    id_file.close();
+    BOOST_CHECK(location_area_service.verify_processed_files() > 0);
+    std::remove((prefix + "id.csv").c_str());
+}
+
+BOOST_AUTO_TEST_CASE( test_location_area_service_compress_index )
+{
+    std::string geo_type("wkt");
+    std::string prefix("mapping_gz_");
+    std::remove((prefix + "info.csv").c_str());
+    {
+        LocationAreaService location_area_service(false, 0, 2, geo_type, true, prefix, GridBBox{5, 49, 9, 52});
+        location_area_service.compress_ = true;
+        location_area_service.load({"test/mapping_test.csv"});
+        BOOST_CHECK(std::filesystem::exists(prefix + "area.csv.gz"));
+        BOOST_CHECK(!std::filesystem::exists(prefix + "area.csv"));
+    }
+
+    // read according to the info file, regardless of the option
+    LocationAreaService location_area_service(false, 0, 2, geo_type, true, prefix, GridBBox{5, 49, 9, 52});
+    location_area_service.load({"test/mapping_test.csv"});
+    BOOST_CHECK(location_area_service.is_initialized());
+    const auto areas = location_area_service.get_area(osmium::Location(6.0902180,  50.7220057));
+    BOOST_CHECK_EQUAL(areas.size(), 1);
+    BOOST_CHECK_EQUAL(areas[0], "BEL");
+}
+
+BOOST_AUTO_TEST_CASE( test_location_area_service_index_threads )
+{
+    std::string geo_type("wkt");
+    const auto read = [](const std::string &path) {
+        std::ifstream file(path);
+        return std::string(std::istreambuf_iterator<char>(file), std::istreambuf_iterator<char>());
+    };
+    for (std::string prefix: {"mapping_single_", "mapping_threads_"}) {
+        std::remove((prefix + "info.csv").c_str());
+        LocationAreaService location_area_service(false, 0, 2, geo_type, true, prefix, GridBBox{5, 49, 9, 52});
+        location_area_service.index_threads_ = prefix == "mapping_single_" ? 1 : 3;
+        location_area_service.load({"test/mapping_test.csv"});
+        const auto areas = location_area_service.get_area(osmium::Location(6.0902180,  50.7220057));
+        BOOST_CHECK_EQUAL(areas.size(), 1);
+        BOOST_CHECK_EQUAL(areas[0], "BEL");
+    }
+    for (const auto &name: {"area.csv", "id.csv", "index.csv"}) {
+        BOOST_CHECK_EQUAL(read(std::string("mapping_single_") + name), read(std::string("mapping_threads_") + name));
+    }
+}
+BOOST_AUTO_TEST_CASE( test_location_area_service_rebuild_on_mismatch )
+{
+    // BEL and DEU only, the processed files built from it are stale for the whole mapping file
+    std::ifstream in("test/mapping_test.csv");
+    std::ofstream partial("mapping_test_partial.csv");
+    std::string line;
+    for (int row = 0; row <= 2 && getline(in, line); row++) {
+        partial << line << "\n";
+    }
+    partial.close();
+
+    std::string geo_type("wkt");
+    std::string prefix("mapping_rebuild_");
+    for (const auto &name: {"area.csv", "id.csv", "index.csv", "info.csv"}) {
+        std::remove((prefix + name).c_str());
+    }
+    {
+        LocationAreaService location_area_service(false, 0, 2, geo_type, true, prefix, GridBBox{5, 49, 9, 52});
+        location_area_service.load({"mapping_test_partial.csv"});
+        BOOST_CHECK(location_area_service.is_initialized());
+    }
+    for (const auto rebuild: {false, true}) {
+        LocationAreaService location_area_service(false, 0, 2, geo_type, true, prefix, GridBBox{5, 49, 9, 52});
+        location_area_service.rebuild_on_mismatch_ = rebuild;
+        location_area_service.load({"test/mapping_test.csv"});
+        const auto areas = location_area_service.get_area(osmium::Location(6.13, 49.61));
+        if (rebuild) {
+            BOOST_CHECK_EQUAL(areas.size(), 1);
+            BOOST_CHECK_EQUAL(areas[0], "LUX");
+        } else {
+            // the stale files are loaded with a warning
+            BOOST_CHECK(areas.empty());
+        }
+    }
+    // the rebuilt files match now and are loaded
+    LocationAreaService location_area_service(false, 0, 2, geo_type, true, prefix, GridBBox{5, 49, 9, 52});
+    location_area_service.load({"test/mapping_test.csv"});
+    const auto areas = location_area_service.get_area(osmium::Location(6.13, 49.61));
+    BOOST_CHECK_EQUAL(areas.size(), 1);
+}
+
+BOOST_AUTO_TEST_CASE( test_location_area_service_progress_interval )
+{
+    std::string geo_type("wkt");
+    std::string prefix("mapping_progress_");
+    for (const std::uint32_t interval: {0, 1, 3}) {
+        std::remove((prefix + "area.csv").c_str());
+        std::remove((prefix + "info.csv").c_str());
+        LocationAreaService location_area_service(false, 0, 2, geo_type, true, prefix, GridBBox{5, 49, 9, 52});
+        location_area_service.progress_interval_ = interval;
+
+        std::ostringstream out;
+        auto *buffer = std::cout.rdbuf(out.rdbuf());
+        location_area_service.load({"test/mapping_test.csv"});
+        std::cout.rdbuf(buffer);
+
+        // 4 areas in the mapping file
+        std::size_t reports = 0;
+        for (auto pos = out.str().find("Areas processed: "); pos != std::string::npos; pos = out.str().find("Areas processed: ", pos + 1)) {
+            reports++;
+        }
+        BOOST_TEST_INFO("interval " << interval);
+        BOOST_CHECK_EQUAL(reports, interval == 0 ? 0u : 4u / interval);
+    }
+}
 BOOST_AUTO_TEST_SUITE_END()
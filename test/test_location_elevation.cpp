@@ -1,7 +1,30 @@
 #include <boost/test/unit_test.hpp>
 
+#include <chrono>
+#include <cmath>
+#include <filesystem>
+#include <fstream>
+
+#include <gdal_priv.h>
+
+#include "geotiff.h"
 #include "location_elevation_service.h"
 
+// writes a 1x2 pixel WGS84 geotiff covering 8-9 E (or west to west + 1), 50-52 N with 100 in the southern and 200 in
+// the northern pixel plus the offset
+void write_two_pixel_tiff(const std::string &filename, const bool south_up, const float offset = 0, const double west = 8.0) {
+    auto *driver = GetGDALDriverManager()->GetDriverByName("GTiff");
+    const auto dataset = GDALDatasetUniquePtr(driver->Create(filename.c_str(), 1, 2, 1, GDT_Float32, nullptr));
+    double north_up_transform[6] = {west, 1.0, 0.0, 52.0, 0.0, -1.0};
+    double south_up_transform[6] = {west, 1.0, 0.0, 50.0, 0.0, 1.0};
+    dataset->SetGeoTransform(south_up ? south_up_transform : north_up_transform);
+    OGRSpatialReference reference;
+    reference.SetWellKnownGeogCS("WGS84");
+    dataset->SetSpatialRef(&reference);
+    float rows[2] = {(south_up ? 100.0f : 200.0f) + offset, (south_up ? 200.0f : 100.0f) + offset};
+    BOOST_REQUIRE(dataset->GetRasterBand(1)->RasterIO(GF_Write, 0, 0, 1, 2, rows, 1, 2, GDT_Float32, 0, 0) == CE_None);
+}
+
 BOOST_AUTO_TEST_SUITE( test_locacion_elevation )
 BOOST_AUTO_TEST_CASE( test_lookup ) {
 
@@ -28,4 +51,256 @@ BOOST_AUTO_TEST_CASE( test_interpolate ) {
 
 
 }
+
+BOOST_AUTO_TEST_CASE( test_interpolate_split_distance ) {
+
+    LocationElevationService location_elevation_service(1 << 20, false);
+    location_elevation_service.load({"files/limburg_an_der_lahn.tif"});
+    location_elevation_service.split_distance_ = 20;
+
+    const osmium::Location from(8.0515393, 50.3873984), to(8.0505023, 50.3868868);
+    const auto coarse = location_elevation_service.interpolate(from, to);
+    const auto fine = location_elevation_service.interpolate(from, to, 5);
+    // ca. 92 m, the end point is added to the samples
+    BOOST_CHECK_EQUAL(coarse.size(), 6);
+    BOOST_CHECK_EQUAL(fine.size(), 20);
+}
+
+BOOST_AUTO_TEST_CASE( test_raw_elevation ) {
+
+    LocationElevationService location_elevation_service(1 << 20, false);
+    location_elevation_service.load({"files/limburg_an_der_lahn.tif"});
+    location_elevation_service.scale_ = 2;
+
+    const osmium::Location location(8.0513629, 50.3876977);
+    int x, y;
+    double raw;
+    BOOST_CHECK(location_elevation_service.raw_elevation(location, x, y, raw));
+    BOOST_CHECK(x >= 0);
+    BOOST_CHECK(y >= 0);
+    BOOST_CHECK_EQUAL(location_elevation_service.elevation(location, false), 2 * raw);
+    BOOST_CHECK(!location_elevation_service.raw_elevation(osmium::Location(0.0, 0.0), x, y, raw));
+}
+
+BOOST_AUTO_TEST_CASE( test_south_up_tiff ) {
+
+    for (const auto south_up: {false, true}) {
+        // registers the gdal drivers
+        LocationElevationService location_elevation_service(1 << 20, false);
+        const auto dir = std::filesystem::temp_directory_path() / (south_up ? "osm-transform-south-up" : "osm-transform-north-up");
+        std::filesystem::create_directories(dir);
+        write_two_pixel_tiff((dir / "dem.tif").string(), south_up);
+
+        location_elevation_service.load({dir.string()});
+        BOOST_CHECK_EQUAL(location_elevation_service.elevation(osmium::Location(8.5, 50.5), false), 100);
+        BOOST_CHECK_EQUAL(location_elevation_service.elevation(osmium::Location(8.5, 51.5), false), 200);
+        std::filesystem::remove_all(dir);
+    }
+}
+BOOST_AUTO_TEST_CASE( test_tiff_priority ) {
+
+    const auto dir = std::filesystem::temp_directory_path() / "osm-transform-tiff-priority";
+    for (const auto &preferred: {"regional", "srtm"}) {
+        // registers the gdal drivers
+        LocationElevationService location_elevation_service(1 << 20, false);
+        std::filesystem::create_directories(dir / "regional");
+        std::filesystem::create_directories(dir / "srtm");
+        write_two_pixel_tiff((dir / "regional" / "dem.tif").string(), false, 1000);
+        write_two_pixel_tiff((dir / "srtm" / "dem.tif").string(), false);
+
+        location_elevation_service.tiff_priority_ = {std::string("/") + preferred + "/"};
+        location_elevation_service.load({dir.string()});
+        BOOST_CHECK_EQUAL(location_elevation_service.elevation(osmium::Location(8.5, 50.5), false), std::string(preferred) == "regional" ? 1100 : 100);
+        std::filesystem::remove_all(dir);
+    }
+}
+BOOST_AUTO_TEST_CASE( test_strict_indexing ) {
+
+    const auto dir = std::filesystem::temp_directory_path() / "osm-transform-strict-indexing";
+    for (const auto strict: {false, true}) {
+        // registers the gdal drivers
+        LocationElevationService location_elevation_service(1 << 20, false);
+        std::filesystem::create_directories(dir);
+        write_two_pixel_tiff((dir / "dem.tif").string(), false);
+        std::ofstream(dir / "corrupt.tif") << "not a geotiff";
+
+        location_elevation_service.strict_indexing_ = strict;
+        if (strict) {
+            BOOST_CHECK_THROW(location_elevation_service.load({dir.string()}), std::runtime_error);
+        } else {
+            location_elevation_service.load({dir.string()});
+            BOOST_CHECK_EQUAL(location_elevation_service.tiffs_failed_to_index_, 1);
+            BOOST_CHECK_EQUAL(location_elevation_service.elevation(osmium::Location(8.5, 50.5), false), 100);
+        }
+        std::filesystem::remove_all(dir);
+    }
+}
+BOOST_AUTO_TEST_CASE( test_seam_blend ) {
+
+    const auto dir = std::filesystem::temp_directory_path() / "osm-transform-seam-blend";
+    for (const auto blend: {false, true}) {
+        // registers the gdal drivers
+        LocationElevationService location_elevation_service(1 << 20, false);
+        std::filesystem::create_directories(dir);
+        write_two_pixel_tiff((dir / "west.tif").string(), false);
+        write_two_pixel_tiff((dir / "east.tif").string(), false, 50, 9.0);
+
+        location_elevation_service.seam_blend_ = blend;
+        location_elevation_service.load({dir.string()});
+        // a quarter pixel west of the seam: 5/8 of the western and 3/8 of the eastern value
+        BOOST_CHECK_CLOSE(location_elevation_service.elevation(osmium::Location(8.75, 50.5), false), blend ? 118.75 : 100, 0.001);
+        BOOST_CHECK_CLOSE(location_elevation_service.elevation(osmium::Location(9.25, 50.5), false), blend ? 131.25 : 150, 0.001);
+        // near the south edge and a quarter pixel from the seam the same as above, not weighted by the distance to the
+        // south edge
+        BOOST_CHECK_CLOSE(location_elevation_service.elevation(osmium::Location(8.75, 50.1), false), blend ? 118.75 : 100, 0.001);
+        // near the south edge, but three quarters of a pixel away from the seam
+        BOOST_CHECK_CLOSE(location_elevation_service.elevation(osmium::Location(8.25, 50.1), false), blend ? 106.25 : 100, 0.001);
+        BOOST_CHECK_EQUAL(location_elevation_service.seam_blends_, blend ? 4 : 0);
+        std::filesystem::remove_all(dir);
+    }
+}
+BOOST_AUTO_TEST_CASE( test_extent ) {
+
+    const auto dir = std::filesystem::temp_directory_path() / "osm-transform-extent";
+    for (const auto limited: {false, true}) {
+        // registers the gdal drivers
+        LocationElevationService location_elevation_service(1 << 20, false);
+        std::filesystem::create_directories(dir);
+        write_two_pixel_tiff((dir / "inside.tif").string(), false);
+        write_two_pixel_tiff((dir / "outside.tif").string(), false, 0, 12.0);
+
+        // without an extent, e.g. for extracts with complete ways, all geotiffs are indexed
+        if (limited) {
+            location_elevation_service.extent_ = osmium::Box(8.2, 50.2, 8.8, 51.8);
+        }
+        location_elevation_service.load({dir.string()});
+        BOOST_CHECK_EQUAL(location_elevation_service.tiffs_outside_extent_, limited ? 1 : 0);
+        BOOST_CHECK_EQUAL(location_elevation_service.elevation(osmium::Location(8.5, 50.5), false), 100);
+        if (!limited) {
+            BOOST_CHECK_EQUAL(location_elevation_service.elevation(osmium::Location(12.5, 50.5), false), 100);
+        }
+        std::filesystem::remove_all(dir);
+    }
+}
+BOOST_AUTO_TEST_CASE( test_settings ) {
+
+    LocationElevationService location_elevation_service(1 << 20, false);
+    location_elevation_service.load({"files/limburg_an_der_lahn.tif"});
+    const auto settings = location_elevation_service.settings();
+    BOOST_TEST(settings.find("files/limburg_an_der_lahn.tif") != std::string::npos);
+    location_elevation_service.scale_ = 0.3048;
+    BOOST_TEST(location_elevation_service.settings() != settings);
+}
+BOOST_AUTO_TEST_CASE( test_resolution ) {
+
+    // registers the gdal drivers
+    LocationElevationService location_elevation_service(1 << 20, false);
+    const auto dir = std::filesystem::temp_directory_path() / "osm-transform-resolution";
+    std::filesystem::create_directories(dir);
+    write_two_pixel_tiff((dir / "dem.tif").string(), false);
+
+    location_elevation_service.load({dir.string()});
+    // the pixels are one degree wide and high, their width is the shorter side at these latitudes
+    BOOST_CHECK_CLOSE(location_elevation_service.resolution(osmium::Location(8.5, 50.5)), 111320.0 * std::cos(50.5 * M_PI / 180.0), 0.001);
+    BOOST_CHECK_CLOSE(location_elevation_service.resolution(osmium::Location(8.5, 51.5)), 111320.0 * std::cos(51.5 * M_PI / 180.0), 0.001);
+    BOOST_CHECK_EQUAL(location_elevation_service.resolution(osmium::Location(10.5, 50.5)), 0);
+    std::filesystem::remove_all(dir);
+}
+BOOST_AUTO_TEST_CASE( test_interpolate_north_south ) {
+
+    LocationElevationService location_elevation_service(1 << 20, false);
+    location_elevation_service.load({"files/limburg_an_der_lahn.tif"});
+
+    const osmium::Location from(8.0515393, 50.3873984), to(8.0515393, 50.3863984);
+    for (const auto split_distance: {0.0, 20.0}) {
+        const auto interpolated = location_elevation_service.interpolate(from, to, split_distance);
+        BOOST_CHECK(interpolated.size() > 2);
+        for (const auto &le: interpolated) {
+            BOOST_CHECK(le.location.valid());
+            BOOST_CHECK_EQUAL(le.location.lon(), 8.0515393);
+            BOOST_CHECK(!std::isnan(le.ele));
+        }
+    }
+    BOOST_CHECK_EQUAL(location_elevation_service.interpolate(from, from).size(), 1);
+}
+BOOST_AUTO_TEST_CASE( test_scale_offset ) {
+
+    // registers the gdal drivers
+    LocationElevationService location_elevation_service(1 << 20, false);
+    const auto dir = std::filesystem::temp_directory_path() / "osm-transform-scale-offset";
+    std::filesystem::create_directories(dir);
+    write_two_pixel_tiff((dir / "dem.tif").string(), false);
+
+    location_elevation_service.scale_ = 0.5;
+    location_elevation_service.offset_ = -10;
+    location_elevation_service.load({dir.string()});
+    BOOST_CHECK_EQUAL(location_elevation_service.elevation(osmium::Location(8.5, 50.5), false), 40);
+    BOOST_CHECK_EQUAL(location_elevation_service.elevation(osmium::Location(8.5, 51.5), false), 90);
+    // no data is not transformed
+    BOOST_CHECK_EQUAL(location_elevation_service.elevation(osmium::Location(10.5, 50.5), false), kNoDataValue);
+    std::filesystem::remove_all(dir);
+}
+BOOST_AUTO_TEST_CASE( test_pixel_cache_limit ) {
+
+    const auto dir = std::filesystem::temp_directory_path() / "osm-transform-pixel-cache";
+    for (const auto limit: {0, 1024}) {
+        // registers the gdal drivers
+        LocationElevationService location_elevation_service(1 << 20, false);
+        std::filesystem::create_directories(dir);
+        write_two_pixel_tiff((dir / "dem.tif").string(), false);
+
+        location_elevation_service.pixel_cache_limit_ = limit;
+        location_elevation_service.load({dir.string()});
+        for (auto i = 0; i < 3; i++) {
+            BOOST_CHECK_EQUAL(location_elevation_service.elevation(osmium::Location(8.5, 50.5), false), 100);
+        }
+        BOOST_CHECK_EQUAL(location_elevation_service.pixel_cache_hits(), limit > 0 ? 2u : 0u);
+        std::filesystem::remove_all(dir);
+    }
+}
+BOOST_AUTO_TEST_CASE( test_open_retries ) {
+
+    const auto dir = std::filesystem::temp_directory_path() / "osm-transform-open-retries";
+    for (const auto strict: {false, true}) {
+        // registers the gdal drivers
+        LocationElevationService location_elevation_service(1 << 20, false);
+        std::filesystem::create_directories(dir);
+        write_two_pixel_tiff((dir / "dem.tif").string(), false);
+
+        location_elevation_service.open_retries_ = 2;
+        location_elevation_service.open_backoff_ms_ = 20;
+        location_elevation_service.strict_loading_ = strict;
+        location_elevation_service.load({dir.string()});
+        // the geotiff is indexed, but can't be opened any more when its elevations are needed
+        std::ofstream(dir / "dem.tif", std::ios::trunc) << "not a geotiff";
+
+        const auto start = std::chrono::steady_clock::now();
+        if (strict) {
+            BOOST_CHECK_THROW(location_elevation_service.elevation(osmium::Location(8.5, 50.5), false), std::runtime_error);
+        } else {
+            BOOST_CHECK_EQUAL(location_elevation_service.elevation(osmium::Location(8.5, 50.5), false), kNoDataValue);
+            BOOST_CHECK_EQUAL(location_elevation_service.failed_tiff_loads_, 1);
+            // failed geotiffs are not opened again
+            BOOST_CHECK_EQUAL(location_elevation_service.elevation(osmium::Location(8.5, 51.5), false), kNoDataValue);
+            BOOST_CHECK_EQUAL(location_elevation_service.failed_tiff_loads_, 1);
+        }
+        // 20 ms before the first and 40 ms before the second retry
+        BOOST_CHECK(std::chrono::steady_clock::now() - start >= std::chrono::milliseconds(60));
+        std::filesystem::remove_all(dir);
+    }
+}
+BOOST_AUTO_TEST_CASE( test_srs_counts ) {
+
+    // registers the gdal drivers
+    LocationElevationService location_elevation_service(1 << 20, false);
+    const auto dir = std::filesystem::temp_directory_path() / "osm-transform-srs-counts";
+    std::filesystem::create_directories(dir);
+    write_two_pixel_tiff((dir / "west.tif").string(), false);
+    write_two_pixel_tiff((dir / "east.tif").string(), false, 0, 9.0);
+
+    location_elevation_service.load({dir.string()});
+    BOOST_CHECK_EQUAL(location_elevation_service.srs_counts_.size(), 1);
+    BOOST_CHECK_EQUAL(location_elevation_service.srs_counts_["EPSG:4326"], 2);
+    std::filesystem::remove_all(dir);
+}
 BOOST_AUTO_TEST_SUITE_END()
@@ -0,0 +1,52 @@
+#include <boost/test/unit_test.hpp>
+
+#include <cstdio>
+#include <fstream>
+
+#include <osmium/osm/location.hpp>
+
+#include "node_elevation_cache.h"
+
+BOOST_AUTO_TEST_SUITE( test_node_elevation_cache )
+
+BOOST_AUTO_TEST_CASE( load_save ) {
+    {
+        std::ofstream input("node_cache_test.osm");
+        input << "<osm/>\n";
+    }
+    const osmium::Location location(8.0, 49.0);
+    {
+        NodeElevationCache cache("node_cache_test.osm", "scale=1;tiffs=a.tif");
+        cache.put(101, location, 123.5);
+        cache.save("node_cache_test.bin");
+    }
+
+    NodeElevationCache same("node_cache_test.osm", "scale=1;tiffs=a.tif");
+    BOOST_TEST(same.load("node_cache_test.bin"));
+    BOOST_TEST(same.size() == 1);
+    double ele = 0;
+    BOOST_TEST(same.get(101, location, ele));
+    BOOST_TEST(ele == 123.5);
+    // the node was moved since the cache was written
+    BOOST_TEST(!same.get(101, osmium::Location(8.1, 49.0), ele));
+    BOOST_TEST(same.hits_ == 1);
+
+    NodeElevationCache other_settings("node_cache_test.osm", "scale=0.3048;tiffs=a.tif");
+    BOOST_TEST(!other_settings.load("node_cache_test.bin"));
+    BOOST_TEST(other_settings.size() == 0);
+
+    NodeElevationCache other_tiffs("node_cache_test.osm", "scale=1;tiffs=a.tif,b.tif");
+    BOOST_TEST(!other_tiffs.load("node_cache_test.bin"));
+
+    {
+        std::ofstream input("node_cache_test.osm", std::ios::app);
+        input << "<osm/>\n";
+    }
+    NodeElevationCache other_input("node_cache_test.osm", "scale=1;tiffs=a.tif");
+    BOOST_TEST(!other_input.load("node_cache_test.bin"));
+
+    std::remove("node_cache_test.osm");
+    std::remove("node_cache_test.bin");
+}
+
+BOOST_AUTO_TEST_SUITE_END()
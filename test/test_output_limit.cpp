@@ -0,0 +1,56 @@
+#include <boost/test/unit_test.hpp>
+
+#include <osmium/memory/buffer.hpp>
+
+#include "output_limit.h"
+
+#include "test_utils.h"
+
+BOOST_AUTO_TEST_SUITE( test_output_limit )
+
+osmium::memory::Buffer nodes_and_ways() {
+    osmium::memory::Buffer buffer{1024, osmium::memory::Buffer::auto_grow::yes};
+    add_node(buffer, 1, {}, 8.0, 49.0);
+    add_node(buffer, 2, {}, 8.1, 49.0);
+    add_node(buffer, 3, {}, 8.2, 49.0);
+    add_way(buffer, 10, {{"highway", "path"}}, {1, 2});
+    add_way(buffer, 11, {{"highway", "path"}}, {2, 3});
+    buffer.commit();
+    return buffer;
+}
+
+std::size_t count(const osmium::memory::Buffer &buffer, const osmium::item_type type) {
+    std::size_t result = 0;
+    for (const auto &object: buffer.select<osmium::OSMObject>()) {
+        result += object.type() == type;
+    }
+    return result;
+}
+
+BOOST_AUTO_TEST_CASE( total ) {
+    OutputLimit limit{4, 0};
+    const auto first = limit.apply(nodes_and_ways());
+    BOOST_TEST(count(first, osmium::item_type::node) == 3);
+    BOOST_TEST(count(first, osmium::item_type::way) == 1);
+    // the limit holds across buffers
+    const auto second = limit.apply(nodes_and_ways());
+    BOOST_TEST(std::distance(second.begin(), second.end()) == 0);
+    BOOST_TEST(limit.dropped_ == 6);
+}
+
+BOOST_AUTO_TEST_CASE( per_type ) {
+    OutputLimit limit{0, 2};
+    const auto kept = limit.apply(nodes_and_ways());
+    BOOST_TEST(count(kept, osmium::item_type::node) == 2);
+    BOOST_TEST(count(kept, osmium::item_type::way) == 2);
+    BOOST_TEST(limit.dropped_ == 1);
+}
+
+BOOST_AUTO_TEST_CASE( disabled ) {
+    OutputLimit limit{0, 0};
+    BOOST_TEST(!limit.enabled());
+    const auto kept = limit.apply(nodes_and_ways());
+    BOOST_TEST(std::distance(kept.begin(), kept.end()) == 5);
+}
+
+BOOST_AUTO_TEST_SUITE_END()
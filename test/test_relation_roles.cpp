@@ -0,0 +1,49 @@
+#include <boost/test/unit_test.hpp>
+
+#include <sstream>
+
+#include <osmium/builder/osm_object_builder.hpp>
+#include <osmium/memory/buffer.hpp>
+
+#include "relation_roles.h"
+
+BOOST_AUTO_TEST_SUITE( test_relation_roles )
+
+void add_relation(osmium::memory::Buffer &buffer, const osmium::object_id_type id, const char *type, const std::vector<const char *> &roles) {
+    {
+        osmium::builder::RelationBuilder builder{buffer};
+        builder.set_id(id);
+        if (type != nullptr) {
+            osmium::builder::TagListBuilder tags{builder};
+            tags.add_tag("type", type);
+        }
+        osmium::builder::RelationMemberListBuilder members{builder};
+        osmium::object_id_type ref = 100;
+        for (const auto role: roles) {
+            members.add_member(osmium::item_type::way, ref++, role);
+        }
+    }
+    buffer.commit();
+}
+
+BOOST_AUTO_TEST_CASE( counts ) {
+    osmium::memory::Buffer buffer{1024, osmium::memory::Buffer::auto_grow::yes};
+    add_relation(buffer, 1, "route", {"forward", "backward", "", "stop"});
+    add_relation(buffer, 2, "route", {"forward", "platform"});
+    add_relation(buffer, 3, "multipolygon", {"outer", "inner"});
+    add_relation(buffer, 4, nullptr, {"label"});
+
+    RelationRoles roles;
+    for (const auto &relation: buffer.select<osmium::Relation>()) {
+        roles.add(relation);
+    }
+    BOOST_TEST(roles.size() == 3);
+
+    std::ostringstream out;
+    roles.print(out);
+    BOOST_TEST(out.str() == "  (none): 1 relations, members: label 1\n"
+                            "  multipolygon: 1 relations, members: inner 1, outer 1\n"
+                            "  route: 2 relations, members: (none) 1, backward 1, forward 2, platform 1, stop 1\n");
+}
+
+BOOST_AUTO_TEST_SUITE_END()
@@ -0,0 +1,72 @@
+#include <boost/test/unit_test.hpp>
+
+#include <string>
+#include <utility>
+#include <vector>
+
+#include <osmium/io/opl_input.hpp>
+#include <osmium/io/opl_output.hpp>
+#include <osmium/io/reader.hpp>
+#include <osmium/io/writer.hpp>
+#include <osmium/memory/buffer.hpp>
+#include <osmium/osm/object.hpp>
+
+#include "reorder_file.h"
+
+#include "test_utils.h"
+
+BOOST_AUTO_TEST_SUITE( test_reorder_file )
+
+using element_list = std::vector<std::pair<osmium::item_type, osmium::object_id_type>>;
+
+// writes the nodes, ways and relation out of order, like the temporary files of the second pass
+void write_unordered(const std::string &filename) {
+    osmium::memory::Buffer buffer{1024, osmium::memory::Buffer::auto_grow::yes};
+    add_node(buffer, 2, {}, 8.0, 49.0);
+    add_way(buffer, 11, {}, {1, 2});
+    add_node(buffer, 1, {}, 8.1, 49.1);
+    add_relation(buffer, 20, {}, {{osmium::item_type::way, 10, ""}});
+    add_way(buffer, 10, {}, {1, 2});
+    buffer.commit();
+    osmium::io::Writer writer{filename, osmium::io::overwrite::allow};
+    writer(std::move(buffer));
+    writer.close();
+}
+
+element_list read_elements(const std::string &filename) {
+    element_list result;
+    osmium::io::Reader reader{filename};
+    while (const auto buffer = reader.read()) {
+        for (const auto &object: buffer.select<osmium::OSMObject>()) {
+            result.emplace_back(object.type(), object.id());
+        }
+    }
+    reader.close();
+    return result;
+}
+
+BOOST_AUTO_TEST_CASE( sort_by_type_and_id ) {
+    write_unordered("reorder_file_sort.opl");
+    reorder_file(osmium::io::File{"reorder_file_sort.opl"}, osmium::io::Header(), true, false);
+
+    const element_list expected{{osmium::item_type::node, 1}, {osmium::item_type::node, 2}, {osmium::item_type::way, 10},
+                                {osmium::item_type::way, 11}, {osmium::item_type::relation, 20}};
+    BOOST_CHECK(read_elements("reorder_file_sort.opl") == expected);
+}
+
+BOOST_AUTO_TEST_CASE( relations_first ) {
+    write_unordered("reorder_file_relations_first.opl");
+    reorder_file(osmium::io::File{"reorder_file_relations_first.opl"}, osmium::io::Header(), false, true);
+
+    // the order within each type is kept unless it is sorted as well
+    const element_list expected{{osmium::item_type::relation, 20}, {osmium::item_type::way, 11}, {osmium::item_type::way, 10},
+                                {osmium::item_type::node, 2}, {osmium::item_type::node, 1}};
+    BOOST_CHECK(read_elements("reorder_file_relations_first.opl") == expected);
+
+    reorder_file(osmium::io::File{"reorder_file_relations_first.opl"}, osmium::io::Header(), true, true);
+    const element_list sorted{{osmium::item_type::relation, 20}, {osmium::item_type::way, 10}, {osmium::item_type::way, 11},
+                              {osmium::item_type::node, 1}, {osmium::item_type::node, 2}};
+    BOOST_CHECK(read_elements("reorder_file_relations_first.opl") == sorted);
+}
+
+BOOST_AUTO_TEST_SUITE_END()
@@ -1,12 +1,68 @@
 #include <boost/test/unit_test.hpp>
 
+#include <algorithm>
+#include <filesystem>
+#include <memory>
+#include <numeric>
+#include <sstream>
+#include <string>
+#include <utility>
+#include <vector>
+
+#include <gdal_priv.h>
+
 #include <osmium/memory/buffer.hpp>
 #include <osmium/visitor.hpp>
 
+#include "firstpass_handler.h"
+#include "required_keys_filter.h"
 #include "rewrite_handler.h"
 
 #include "test_utils.h"
 
+// services without geotiffs and area mappings and an empty input for the tests of single options, the tests add the
+// elements and their valid ids, load the geotiffs they need and call run
+struct RewriteFixture {
+    std::string geo_type{"wkt"};
+    std::string prefix{"mapping_"};
+    std::unique_ptr<osmium::index::map::Map<osmium::unsigned_object_id_type, osmium::Location>> location_index{
+            osmium::index::MapFactory<osmium::unsigned_object_id_type, osmium::Location>::instance().create_map("flex_mem")};
+    LocationElevationService location_elevation_service{1 << 20, false};
+    LocationAreaService location_area_service{false, 0, 2, geo_type, true, prefix};
+    osmium::nwr_array<osmium::index::IdSetDense<osmium::unsigned_object_id_type>> valid_ids;
+    osmium::nwr_array<osmium::index::IdSetSmall<osmium::unsigned_object_id_type>> no_elevation;
+    boost::regex remove_tag_regex{"fixme", boost::regex::icase};
+    osmium::memory::Buffer input{1 << 10, osmium::memory::Buffer::auto_grow::yes};
+    osmium::memory::Buffer output{1 << 10, osmium::memory::Buffer::auto_grow::yes};
+
+    RewriteHandler make_handler(const bool interpolate = false, const osmium::object_id_type next_node_id = 1000000000) {
+        return RewriteHandler(next_node_id, location_index, location_elevation_service, location_area_service, remove_tag_regex,
+                              valid_ids, no_elevation, interpolate, 0.5);
+    }
+
+    // nodes and ways are written to the same output buffer
+    void run(RewriteHandler &handler) {
+        input.commit();
+        handler.set_buffers(&output, &output);
+        osmium::apply(input, handler);
+    }
+
+    template <typename T>
+    const T *find(const osmium::object_id_type id) {
+        for (const auto &object: output.select<T>()) {
+            if (object.id() == id) {
+                return &object;
+            }
+        }
+        return nullptr;
+    }
+};
+
+// locations within and outside of files/limburg_an_der_lahn.tif
+static const osmium::Location kInside1{8.0515393, 50.3873984};
+static const osmium::Location kInside2{8.0505023, 50.3868868};
+static const osmium::Location kOutside{0.0, 0.0};
+
 BOOST_AUTO_TEST_SUITE( test_rewrite_pass )
 BOOST_AUTO_TEST_CASE (interpolation_0_5) {
     auto debug_mode = false;
@@ -21,7 +77,7 @@ BOOST_AUTO_TEST_CASE (interpolation_0_5) {
     location_elevation_service.load({"files/limburg_an_der_lahn.tif"});
 
     LocationAreaService location_area_service(debug_mode, 0, 2, geo_type, true, prefix);
-    location_area_service.load("test/mapping_test.csv");
+    location_area_service.load({"test/mapping_test.csv"});
 
     osmium::nwr_array<osmium::index::IdSetDense<osmium::unsigned_object_id_type>> valid_ids;
     osmium::nwr_array<osmium::index::IdSetSmall<osmium::unsigned_object_id_type>> no_elevation;
@@ -75,7 +131,7 @@ BOOST_AUTO_TEST_CASE (interpolation_1_0) {
     location_elevation_service.load({"files/limburg_an_der_lahn.tif"});
 
     LocationAreaService location_area_service(debug_mode, 0, 2, geo_type, true, prefix);
-    location_area_service.load("test/mapping_test.csv");
+    location_area_service.load({"test/mapping_test.csv"});
 
     osmium::nwr_array<osmium::index::IdSetDense<osmium::unsigned_object_id_type>> valid_ids;
     osmium::nwr_array<osmium::index::IdSetSmall<osmium::unsigned_object_id_type>> no_elevation;
@@ -123,7 +179,7 @@ BOOST_AUTO_TEST_CASE (interpolation_10_0) {
     location_elevation_service.load({"files/limburg_an_der_lahn.tif"});
 
     LocationAreaService location_area_service(debug_mode, 0, 2, geo_type, true, prefix);
-    location_area_service.load("test/mapping_test.csv");
+    location_area_service.load({"test/mapping_test.csv"});
 
     osmium::nwr_array<osmium::index::IdSetDense<osmium::unsigned_object_id_type>> valid_ids;
     osmium::nwr_array<osmium::index::IdSetSmall<osmium::unsigned_object_id_type>> no_elevation;
@@ -169,7 +225,7 @@ BOOST_AUTO_TEST_CASE (rewrite_full_pass) {
 
     LocationElevationService location_elevation_service(1 << 20, debug_mode);
     LocationAreaService location_area_service(debug_mode, 0, 2, geo_type, true, prefix);
-    location_area_service.load("test/mapping_test.csv");
+    location_area_service.load({"test/mapping_test.csv"});
 
     osmium::nwr_array<osmium::index::IdSetDense<osmium::unsigned_object_id_type>> valid_ids;
     osmium::nwr_array<osmium::index::IdSetSmall<osmium::unsigned_object_id_type>> no_elevation;
@@ -289,4 +345,1223 @@ BOOST_AUTO_TEST_CASE (rewrite_full_pass) {
 
 }
 
+BOOST_AUTO_TEST_CASE (normalize_oneway_direction) {
+    auto debug_mode = false;
+    std::string geo_type{"wkt"};
+    std::string prefix{"mapping_"};
+    std::string index_type{"flex_mem"};
+
+    const auto& map_factory = osmium::index::MapFactory<osmium::unsigned_object_id_type, osmium::Location>::instance();
+    auto location_index = map_factory.create_map(index_type);
+
+    LocationElevationService location_elevation_service(1 << 20, debug_mode);
+    LocationAreaService location_area_service(debug_mode, 0, 2, geo_type, true, prefix);
+
+    osmium::nwr_array<osmium::index::IdSetDense<osmium::unsigned_object_id_type>> valid_ids;
+    osmium::nwr_array<osmium::index::IdSetSmall<osmium::unsigned_object_id_type>> no_elevation;
+    boost::regex remove_tag_regex("(.*:)?source(:.*)?|(.*:)?note(:.*)?|url|created_by|fixme|wikipedia", boost::regex::icase);
+    RewriteHandler handler(1000000000, location_index, location_elevation_service, location_area_service, remove_tag_regex, valid_ids, no_elevation, false, 0.5);
+    handler.normalize_oneway_direction_ = true;
+
+    osmium::memory::Buffer input{1 << 10, osmium::memory::Buffer::auto_grow::yes};
+    add_way(input, 10, {{"highway", "primary"}, {"oneway", "-1"}}, {101, 102, 103});
+    valid_ids.ways().set(10);
+    add_way(input, 20, {{"highway", "primary"}, {"oneway", "yes"}}, {201, 202});
+    valid_ids.ways().set(20);
+    input.commit();
+
+    osmium::memory::Buffer output_nodes{1 << 10, osmium::memory::Buffer::auto_grow::yes};
+    osmium::memory::Buffer output_ways{1 << 10, osmium::memory::Buffer::auto_grow::yes};
+    handler.set_buffers(&output_ways, &output_nodes);
+    osmium::apply(input, handler);
+
+    auto ways = output_ways.select<osmium::Way>();
+    BOOST_CHECK_EQUAL(ways.size(), 2);
+    auto item = ways.begin();
+    {
+        const auto& way = (*item++);
+        BOOST_CHECK_EQUAL(way.id(), 10);
+        BOOST_CHECK_EQUAL(way.tags().get_value_by_key("oneway", ""), "yes");
+        BOOST_CHECK_EQUAL(way.nodes().size(), 3);
+        BOOST_CHECK_EQUAL(way.nodes()[0].ref(), 103);
+        BOOST_CHECK_EQUAL(way.nodes()[1].ref(), 102);
+        BOOST_CHECK_EQUAL(way.nodes()[2].ref(), 101);
+    }
+    {
+        const auto& way = (*item++);
+        BOOST_CHECK_EQUAL(way.id(), 20);
+        BOOST_CHECK_EQUAL(way.nodes()[0].ref(), 201);
+        BOOST_CHECK_EQUAL(way.nodes()[1].ref(), 202);
+    }
+    BOOST_CHECK_EQUAL(handler.ways_with_normalized_oneway_, 1);
+}
+
+BOOST_AUTO_TEST_CASE (split_long_ways) {
+    auto debug_mode = false;
+    std::string geo_type{"wkt"};
+    std::string prefix{"mapping_"};
+    std::string index_type{"flex_mem"};
+
+    const auto& map_factory = osmium::index::MapFactory<osmium::unsigned_object_id_type, osmium::Location>::instance();
+    auto location_index = map_factory.create_map(index_type);
+
+    LocationElevationService location_elevation_service(1 << 20, debug_mode);
+    LocationAreaService location_area_service(debug_mode, 0, 2, geo_type, true, prefix);
+
+    osmium::nwr_array<osmium::index::IdSetDense<osmium::unsigned_object_id_type>> valid_ids;
+    osmium::nwr_array<osmium::index::IdSetSmall<osmium::unsigned_object_id_type>> no_elevation;
+    boost::regex remove_tag_regex("(.*:)?source(:.*)?|(.*:)?note(:.*)?|url|created_by|fixme|wikipedia", boost::regex::icase);
+    RewriteHandler handler(1000000000, location_index, location_elevation_service, location_area_service, remove_tag_regex, valid_ids, no_elevation, false, 0.5);
+    handler.max_way_nodes_ = 3;
+
+    osmium::memory::Buffer input{1 << 10, osmium::memory::Buffer::auto_grow::yes};
+    add_way(input, 10, {{"highway", "primary"}, {"oneway", "yes"}}, {101, 102, 103, 104, 105, 106});
+    valid_ids.ways().set(10);
+    add_way(input, 20, {{"highway", "primary"}}, {201, 202, 203});
+    valid_ids.ways().set(20);
+    input.commit();
+
+    osmium::memory::Buffer output_nodes{1 << 10, osmium::memory::Buffer::auto_grow::yes};
+    osmium::memory::Buffer output_ways{1 << 10, osmium::memory::Buffer::auto_grow::yes};
+    handler.set_buffers(&output_ways, &output_nodes);
+    osmium::apply(input, handler);
+
+    auto ways = output_ways.select<osmium::Way>();
+    BOOST_CHECK_EQUAL(ways.size(), 4);
+    auto item = ways.begin();
+    {
+        const auto& way = (*item++);
+        BOOST_CHECK_EQUAL(way.id(), 10);
+        BOOST_CHECK_EQUAL(way.nodes().size(), 3);
+        BOOST_CHECK_EQUAL(way.nodes()[0].ref(), 101);
+        BOOST_CHECK_EQUAL(way.nodes()[2].ref(), 103);
+    }
+    {
+        const auto& way = (*item++);
+        BOOST_CHECK_EQUAL(way.id(), kHighestWayId + 1);
+        BOOST_CHECK_EQUAL(way.tags().get_value_by_key("oneway", ""), "yes");
+        BOOST_CHECK_EQUAL(way.nodes().size(), 3);
+        BOOST_CHECK_EQUAL(way.nodes()[0].ref(), 103);
+        BOOST_CHECK_EQUAL(way.nodes()[2].ref(), 105);
+    }
+    {
+        const auto& way = (*item++);
+        BOOST_CHECK_EQUAL(way.id(), kHighestWayId + 2);
+        BOOST_CHECK_EQUAL(way.nodes().size(), 2);
+        BOOST_CHECK_EQUAL(way.nodes()[0].ref(), 105);
+        BOOST_CHECK_EQUAL(way.nodes()[1].ref(), 106);
+    }
+    {
+        const auto& way = (*item++);
+        BOOST_CHECK_EQUAL(way.id(), 20);
+        BOOST_CHECK_EQUAL(way.nodes().size(), 3);
+    }
+    BOOST_CHECK_EQUAL(handler.ways_split_by_length_, 1);
+    BOOST_CHECK_EQUAL(handler.ways_added_by_length_split_, 2);
+}
+
+BOOST_AUTO_TEST_CASE (utm_zone) {
+    BOOST_CHECK_EQUAL(RewriteHandler::utm_zone(osmium::Location(-180.0, 10.0)), "1N");
+    BOOST_CHECK_EQUAL(RewriteHandler::utm_zone(osmium::Location(-174.0000001, 10.0)), "1N");
+    BOOST_CHECK_EQUAL(RewriteHandler::utm_zone(osmium::Location(-174.0, 10.0)), "2N");
+    BOOST_CHECK_EQUAL(RewriteHandler::utm_zone(osmium::Location(8.6756824, 49.4184793)), "32N");
+    BOOST_CHECK_EQUAL(RewriteHandler::utm_zone(osmium::Location(6.0, -0.0000001)), "32S");
+    BOOST_CHECK_EQUAL(RewriteHandler::utm_zone(osmium::Location(5.9999999, 0.0)), "31N");
+    BOOST_CHECK_EQUAL(RewriteHandler::utm_zone(osmium::Location(180.0, -45.0)), "60S");
+}
+
+BOOST_AUTO_TEST_CASE (normalize_multi_value) {
+    BOOST_CHECK_EQUAL(RewriteHandler::normalize_multi_value("asphalt"), "asphalt");
+    BOOST_CHECK_EQUAL(RewriteHandler::normalize_multi_value("paving_stones; asphalt;asphalt"), "asphalt;paving_stones");
+    BOOST_CHECK_EQUAL(RewriteHandler::normalize_multi_value("b;;a;"), "a;b");
+}
+
+BOOST_AUTO_TEST_CASE (min_node_spacing) {
+    auto debug_mode = false;
+    std::string geo_type{"wkt"};
+    std::string prefix{"mapping_"};
+    std::string index_type{"flex_mem"};
+
+    const auto& map_factory = osmium::index::MapFactory<osmium::unsigned_object_id_type, osmium::Location>::instance();
+    auto location_index = map_factory.create_map(index_type);
+
+    LocationElevationService location_elevation_service(1 << 20, debug_mode);
+    LocationAreaService location_area_service(debug_mode, 0, 2, geo_type, true, prefix);
+
+    osmium::nwr_array<osmium::index::IdSetDense<osmium::unsigned_object_id_type>> valid_ids;
+    osmium::nwr_array<osmium::index::IdSetSmall<osmium::unsigned_object_id_type>> no_elevation;
+    osmium::index::IdSetDense<osmium::unsigned_object_id_type> protected_nodes;
+    boost::regex remove_tag_regex("(.*:)?source(:.*)?|(.*:)?note(:.*)?|url|created_by|fixme|wikipedia", boost::regex::icase);
+    RewriteHandler handler(1000000000, location_index, location_elevation_service, location_area_service, remove_tag_regex, valid_ids, no_elevation, false, 0.5);
+    handler.min_node_spacing_ = 1.0;
+    handler.protected_nodes_ = &protected_nodes;
+
+    osmium::memory::Buffer input{1 << 10, osmium::memory::Buffer::auto_grow::yes};
+    add_node(input, 1, {}, 8.0, 49.0);
+    add_node(input, 2, {}, 8.000001, 49.0);
+    add_node(input, 3, {}, 8.001, 49.0);
+    add_node(input, 4, {}, 8.001001, 49.0);
+    // a closed way whose nodes all lie within the spacing
+    add_node(input, 5, {}, 9.0, 49.0);
+    add_node(input, 6, {}, 9.000001, 49.0);
+    add_node(input, 7, {}, 9.000001, 49.000001);
+    // node 9 lies next to node 2 of way 10, but only the nodes of its own way are considered
+    add_node(input, 8, {}, 8.0, 49.001);
+    add_node(input, 9, {}, 8.000001, 49.000001);
+    add_node(input, 11, {}, 8.0, 48.999);
+    add_way(input, 10, {{"highway", "primary"}}, {1, 2, 3, 4});
+    add_way(input, 20, {{"building", "yes"}}, {5, 6, 7, 5});
+    add_way(input, 30, {{"highway", "track"}}, {8, 9, 11});
+    input.commit();
+    for (const auto id: {1, 2, 3, 4, 5, 6, 7, 8, 9, 11}) {
+        valid_ids.nodes().set(id);
+    }
+    valid_ids.ways().set(10);
+    valid_ids.ways().set(20);
+    valid_ids.ways().set(30);
+    for (const auto id: {1, 4, 5, 8, 11}) {
+        protected_nodes.set(id);
+    }
+
+    osmium::memory::Buffer output_nodes{1 << 10, osmium::memory::Buffer::auto_grow::yes};
+    osmium::memory::Buffer output_ways{1 << 10, osmium::memory::Buffer::auto_grow::yes};
+    handler.set_buffers(&output_ways, &output_nodes);
+    osmium::apply(input, handler);
+
+    // the nodes are written before the ways decide which of them are dropped
+    BOOST_CHECK_EQUAL(output_nodes.select<osmium::Node>().size(), 10);
+    BOOST_CHECK_EQUAL(handler.nodes_dropped_by_spacing_, 1);
+    BOOST_CHECK_EQUAL(handler.accepted_nodes_, 9);
+    BOOST_CHECK(handler.dropped_nodes().get(2));
+    BOOST_CHECK_EQUAL(handler.dropped_nodes().size(), 1);
+
+    std::vector<std::vector<osmium::object_id_type>> ways;
+    for (const auto &way: output_ways.select<osmium::Way>()) {
+        auto &refs = ways.emplace_back();
+        for (const auto &ref: way.nodes()) {
+            refs.push_back(ref.ref());
+        }
+    }
+    BOOST_REQUIRE_EQUAL(ways.size(), 3);
+    BOOST_CHECK((ways[0] == std::vector<osmium::object_id_type>{1, 3, 4}));
+    BOOST_CHECK((ways[1] == std::vector<osmium::object_id_type>{5, 6, 7, 5}));
+    BOOST_CHECK((ways[2] == std::vector<osmium::object_id_type>{8, 9, 11}));
+}
+
+BOOST_AUTO_TEST_CASE (quote_csv) {
+    BOOST_CHECK_EQUAL(RewriteHandler::quote_csv("DEU"), "\"DEU\"");
+    BOOST_CHECK_EQUAL(RewriteHandler::quote_csv("DEU,FRA"), "\"DEU,FRA\"");
+    BOOST_CHECK_EQUAL(RewriteHandler::quote_csv("Côte d'Ivoire \"CIV\""), "\"Côte d'Ivoire \"\"CIV\"\"\"");
+    BOOST_CHECK_EQUAL(RewriteHandler::quote_csv(""), "\"\"");
+}
+
+BOOST_AUTO_TEST_CASE (format_elevation) {
+    BOOST_CHECK_EQUAL(RewriteHandler::format_elevation(123.5), "123.500000");
+    BOOST_CHECK_EQUAL(RewriteHandler::format_elevation(-12.25), "-12.250000");
+    BOOST_CHECK_EQUAL(RewriteHandler::format_elevation(0), "0.000000");
+    BOOST_CHECK_EQUAL(RewriteHandler::format_elevation(123.5, ','), "123,500000");
+}
+
+BOOST_AUTO_TEST_CASE (parse_elevation) {
+    double meters = 0;
+    BOOST_CHECK(RewriteHandler::parse_elevation("100", 1.0, meters));
+    BOOST_CHECK_CLOSE(meters, 100.0, 0.001);
+    BOOST_CHECK(RewriteHandler::parse_elevation(" 12.5 m", 1.0, meters));
+    BOOST_CHECK_CLOSE(meters, 12.5, 0.001);
+    BOOST_CHECK(RewriteHandler::parse_elevation("328 ft", 1.0, meters));
+    BOOST_CHECK_CLOSE(meters, 99.9744, 0.001);
+    BOOST_CHECK(RewriteHandler::parse_elevation("328'", 1.0, meters));
+    BOOST_CHECK_CLOSE(meters, 99.9744, 0.001);
+    BOOST_CHECK(RewriteHandler::parse_elevation("100", 0.3048, meters));
+    BOOST_CHECK_CLOSE(meters, 30.48, 0.001);
+    BOOST_CHECK(!RewriteHandler::parse_elevation("about 100", 1.0, meters));
+    BOOST_CHECK(!RewriteHandler::parse_elevation("100 yards", 1.0, meters));
+    BOOST_CHECK(!RewriteHandler::parse_elevation("", 1.0, meters));
+}
+
+BOOST_AUTO_TEST_CASE (tag_hash) {
+    const auto hash = RewriteHandler::tag_hash({{"highway", "primary"}, {"name", "Main Street"}});
+    BOOST_CHECK_EQUAL(hash.size(), 16);
+    BOOST_CHECK_EQUAL(RewriteHandler::tag_hash({{"name", "Main Street"}, {"highway", "primary"}}), hash);
+    BOOST_CHECK_NE(RewriteHandler::tag_hash({{"highway", "secondary"}, {"name", "Main Street"}}), hash);
+    BOOST_CHECK_NE(RewriteHandler::tag_hash({{"a", "bc"}}), RewriteHandler::tag_hash({{"ab", "c"}}));
+}
+
+BOOST_AUTO_TEST_CASE (signed_area) {
+    const std::vector<osmium::Location> counterclockwise{{8.0, 49.0}, {8.1, 49.0}, {8.1, 49.1}, {8.0, 49.1}, {8.0, 49.0}};
+    const std::vector<osmium::Location> clockwise(counterclockwise.rbegin(), counterclockwise.rend());
+    BOOST_CHECK_CLOSE(RewriteHandler::signed_area(counterclockwise), 0.02, 0.001);
+    BOOST_CHECK_CLOSE(RewriteHandler::signed_area(clockwise), -0.02, 0.001);
+}
+
+BOOST_AUTO_TEST_CASE (annotate_node_count) {
+    auto debug_mode = false;
+    std::string geo_type{"wkt"};
+    std::string prefix{"mapping_"};
+    std::string index_type{"flex_mem"};
+
+    const auto &map_factory = osmium::index::MapFactory<osmium::unsigned_object_id_type, osmium::Location>::instance();
+    auto location_index = map_factory.create_map(index_type);
+
+    LocationElevationService location_elevation_service(1 << 20, debug_mode);
+    location_elevation_service.load({"files/limburg_an_der_lahn.tif"});
+
+    LocationAreaService location_area_service(debug_mode, 0, 2, geo_type, true, prefix);
+    location_area_service.load({"test/mapping_test.csv"});
+
+    osmium::nwr_array<osmium::index::IdSetDense<osmium::unsigned_object_id_type>> valid_ids;
+    osmium::nwr_array<osmium::index::IdSetSmall<osmium::unsigned_object_id_type>> no_elevation;
+    boost::regex remove_tag_regex("(.*:)?source(:.*)?|(.*:)?note(:.*)?|url|created_by|fixme|wikipedia", boost::regex::icase);
+
+    osmium::memory::Buffer input{1 << 10, osmium::memory::Buffer::auto_grow::yes};
+    add_node(input, 101, {}, 8.0515393, 50.3873984);
+    add_node(input, 102, {}, 8.0505023, 50.3868868);
+    valid_ids.nodes().set(101);
+    valid_ids.nodes().set(102);
+    add_way(input, 10, {{"highway", "track"}}, {101, 102});
+    valid_ids.ways().set(10);
+    input.commit();
+
+    RewriteHandler handler(1000000000, location_index, location_elevation_service, location_area_service, remove_tag_regex, valid_ids, no_elevation, true, 0.5);
+    handler.annotate_node_count_ = true;
+    osmium::memory::Buffer output_nodes{1 << 10, osmium::memory::Buffer::auto_grow::yes};
+    osmium::memory::Buffer output_ways{1 << 10, osmium::memory::Buffer::auto_grow::yes};
+    handler.set_buffers(&output_ways, &output_nodes);
+    osmium::apply(input, handler);
+
+    const auto &way = *output_ways.select<osmium::Way>().begin();
+    BOOST_CHECK_EQUAL(way.nodes().size(), 11);
+    BOOST_CHECK_EQUAL(way.tags().get_value_by_key("_node_count", ""), "11");
+}
+
+BOOST_AUTO_TEST_CASE (clamp_location) {
+    osmium::Location location(200.5, -95.0);
+    BOOST_CHECK(!location.valid());
+    BOOST_CHECK(RewriteHandler::clamp_location(location));
+    BOOST_CHECK(location.valid());
+    BOOST_CHECK_EQUAL(location.lon(), 180.0);
+    BOOST_CHECK_EQUAL(location.lat(), -90.0);
+
+    osmium::Location undefined;
+    BOOST_CHECK(!RewriteHandler::clamp_location(undefined));
+
+    osmium::Location valid(8.69, 49.41);
+    BOOST_CHECK(RewriteHandler::clamp_location(valid));
+    BOOST_CHECK_EQUAL(valid, osmium::Location(8.69, 49.41));
+}
+
+BOOST_AUTO_TEST_CASE (annotate_sinuosity) {
+    auto debug_mode = false;
+    std::string geo_type{"wkt"};
+    std::string prefix{"mapping_"};
+    std::string index_type{"flex_mem"};
+
+    const auto &map_factory = osmium::index::MapFactory<osmium::unsigned_object_id_type, osmium::Location>::instance();
+    auto location_index = map_factory.create_map(index_type);
+
+    LocationElevationService location_elevation_service(1 << 20, debug_mode);
+    LocationAreaService location_area_service(debug_mode, 0, 2, geo_type, true, prefix);
+
+    osmium::nwr_array<osmium::index::IdSetDense<osmium::unsigned_object_id_type>> valid_ids;
+    osmium::nwr_array<osmium::index::IdSetSmall<osmium::unsigned_object_id_type>> no_elevation;
+    boost::regex remove_tag_regex("(.*:)?source(:.*)?|(.*:)?note(:.*)?|url|created_by|fixme|wikipedia", boost::regex::icase);
+
+    osmium::memory::Buffer input{1 << 10, osmium::memory::Buffer::auto_grow::yes};
+    add_node(input, 101, {}, 8.0, 0.0);
+    add_node(input, 102, {}, 8.01, 0.0);
+    add_node(input, 103, {}, 8.01, 0.01);
+    for (const osmium::object_id_type id: {101, 102, 103}) {
+        valid_ids.nodes().set(id);
+    }
+    add_way(input, 10, {{"highway", "path"}}, {101, 102, 103});
+    add_way(input, 11, {{"highway", "path"}}, {101, 102, 103, 101});
+    valid_ids.ways().set(10);
+    valid_ids.ways().set(11);
+    input.commit();
+
+    RewriteHandler handler(1000000000, location_index, location_elevation_service, location_area_service, remove_tag_regex, valid_ids, no_elevation, false, 0.5);
+    handler.annotate_sinuosity_ = true;
+    osmium::memory::Buffer output{1 << 10, osmium::memory::Buffer::auto_grow::yes};
+    handler.set_buffers(&output, &output);
+    osmium::apply(input, handler);
+
+    auto ways = output.select<osmium::Way>().begin();
+    // two legs of a right isosceles triangle at the equator against its hypotenuse
+    BOOST_CHECK_EQUAL(ways->tags().get_value_by_key("_sinuosity", ""), "1.414");
+    ++ways;
+    BOOST_CHECK(!ways->tags().has_key("_sinuosity"));
+    BOOST_CHECK_EQUAL(handler.ways_with_sinuosity_, 1);
+}
+
+BOOST_AUTO_TEST_CASE (annotate_bearing) {
+    auto debug_mode = false;
+    std::string geo_type{"wkt"};
+    std::string prefix{"mapping_"};
+    std::string index_type{"flex_mem"};
+
+    const auto &map_factory = osmium::index::MapFactory<osmium::unsigned_object_id_type, osmium::Location>::instance();
+    auto location_index = map_factory.create_map(index_type);
+
+    LocationElevationService location_elevation_service(1 << 20, debug_mode);
+    LocationAreaService location_area_service(debug_mode, 0, 2, geo_type, true, prefix);
+
+    osmium::nwr_array<osmium::index::IdSetDense<osmium::unsigned_object_id_type>> valid_ids;
+    osmium::nwr_array<osmium::index::IdSetSmall<osmium::unsigned_object_id_type>> no_elevation;
+    boost::regex remove_tag_regex("(.*:)?source(:.*)?|(.*:)?note(:.*)?|url|created_by|fixme|wikipedia", boost::regex::icase);
+
+    osmium::memory::Buffer input{1 << 10, osmium::memory::Buffer::auto_grow::yes};
+    add_node(input, 101, {}, 8.0, 0.0);
+    add_node(input, 102, {}, 8.01, 0.0);
+    add_node(input, 103, {}, 8.01, 0.01);
+    for (const osmium::object_id_type id: {101, 102, 103}) {
+        valid_ids.nodes().set(id);
+    }
+    add_way(input, 10, {{"highway", "path"}}, {101, 102, 103});
+    add_way(input, 11, {{"highway", "path"}}, {101, 102, 103, 101});
+    add_way(input, 12, {{"highway", "path"}}, {103, 102});
+    valid_ids.ways().set(10);
+    valid_ids.ways().set(11);
+    valid_ids.ways().set(12);
+    input.commit();
+
+    RewriteHandler handler(1000000000, location_index, location_elevation_service, location_area_service, remove_tag_regex, valid_ids, no_elevation, false, 0.5);
+    handler.annotate_bearing_ = true;
+    osmium::memory::Buffer output{1 << 10, osmium::memory::Buffer::auto_grow::yes};
+    handler.set_buffers(&output, &output);
+    osmium::apply(input, handler);
+
+    auto ways = output.select<osmium::Way>().begin();
+    // diagonal of a square at the equator
+    BOOST_CHECK_EQUAL(ways->tags().get_value_by_key("_bearing", ""), "45.0");
+    ++ways;
+    BOOST_CHECK(!ways->tags().has_key("_bearing"));
+    ++ways;
+    BOOST_CHECK_EQUAL(ways->tags().get_value_by_key("_bearing", ""), "180.0");
+    BOOST_CHECK_EQUAL(handler.ways_with_bearing_, 2);
+}
+
+BOOST_AUTO_TEST_CASE (sparse_index_unsorted_nodes) {
+    auto debug_mode = false;
+    std::string geo_type{"wkt"};
+    std::string prefix{"mapping_"};
+
+    const auto &map_factory = osmium::index::MapFactory<osmium::unsigned_object_id_type, osmium::Location>::instance();
+    auto location_index = map_factory.create_map("sparse_mem_array");
+
+    LocationElevationService location_elevation_service(1 << 20, debug_mode);
+    LocationAreaService location_area_service(debug_mode, 0, 2, geo_type, true, prefix);
+
+    osmium::nwr_array<osmium::index::IdSetDense<osmium::unsigned_object_id_type>> valid_ids;
+    osmium::nwr_array<osmium::index::IdSetSmall<osmium::unsigned_object_id_type>> no_elevation;
+    boost::regex remove_tag_regex("(.*:)?source(:.*)?|(.*:)?note(:.*)?|url|created_by|fixme|wikipedia", boost::regex::icase);
+
+    // the nodes are not sorted by id, so the index has to be sorted before the lookups
+    osmium::memory::Buffer input{1 << 10, osmium::memory::Buffer::auto_grow::yes};
+    add_node(input, 103, {}, 8.01, 0.01);
+    add_node(input, 101, {}, 8.0, 0.0);
+    add_node(input, 102, {}, 8.01, 0.0);
+    for (const osmium::object_id_type id: {101, 102, 103}) {
+        valid_ids.nodes().set(id);
+    }
+    add_way(input, 10, {{"highway", "path"}}, {101, 102, 103});
+    valid_ids.ways().set(10);
+    input.commit();
+
+    RewriteHandler handler(1000000000, location_index, location_elevation_service, location_area_service, remove_tag_regex, valid_ids, no_elevation, false, 0.5);
+    handler.annotate_bearing_ = true;
+    osmium::memory::Buffer output{1 << 10, osmium::memory::Buffer::auto_grow::yes};
+    handler.set_buffers(&output, &output);
+    osmium::apply(input, handler);
+
+    auto ways = output.select<osmium::Way>().begin();
+    BOOST_CHECK_EQUAL(ways->tags().get_value_by_key("_bearing", ""), "45.0");
+    BOOST_CHECK_EQUAL(handler.ways_with_bearing_, 1);
+}
+
+BOOST_AUTO_TEST_CASE (id_ranges) {
+    auto debug_mode = false;
+    std::string geo_type{"wkt"};
+    std::string prefix{"mapping_"};
+
+    const auto &map_factory = osmium::index::MapFactory<osmium::unsigned_object_id_type, osmium::Location>::instance();
+    auto location_index = map_factory.create_map("flex_mem");
+
+    LocationElevationService location_elevation_service(1 << 20, debug_mode);
+    LocationAreaService location_area_service(debug_mode, 0, 2, geo_type, true, prefix);
+
+    osmium::nwr_array<osmium::index::IdSetDense<osmium::unsigned_object_id_type>> valid_ids;
+    osmium::nwr_array<osmium::index::IdSetSmall<osmium::unsigned_object_id_type>> no_elevation;
+    boost::regex remove_tag_regex("(.*:)?source(:.*)?|(.*:)?note(:.*)?|url|created_by|fixme|wikipedia", boost::regex::icase);
+
+    // the ids of elements which are not accepted count as well, relations are not present
+    osmium::memory::Buffer input{1 << 10, osmium::memory::Buffer::auto_grow::yes};
+    add_node(input, 105, {}, 8.0, 50.0);
+    add_node(input, 101, {}, 8.01, 50.0);
+    add_node(input, 230, {}, 8.02, 50.0);
+    add_way(input, 12, {{"highway", "path"}}, {101, 105});
+    add_way(input, 7, {{"highway", "path"}}, {105, 230});
+    input.commit();
+    valid_ids.nodes().set(101);
+    valid_ids.nodes().set(105);
+    valid_ids.ways().set(12);
+
+    RewriteHandler handler(1000000000, location_index, location_elevation_service, location_area_service, remove_tag_regex, valid_ids, no_elevation, false, 0.5);
+    osmium::memory::Buffer output{1 << 10, osmium::memory::Buffer::auto_grow::yes};
+    handler.set_buffers(&output, &output);
+    osmium::apply(input, handler);
+
+    BOOST_CHECK_EQUAL(handler.min_ids_[0], 101);
+    BOOST_CHECK_EQUAL(handler.max_ids_[0], 230);
+    BOOST_CHECK_EQUAL(handler.min_ids_[1], 7);
+    BOOST_CHECK_EQUAL(handler.max_ids_[1], 12);
+    BOOST_CHECK_EQUAL(handler.min_ids_[2], 0);
+    BOOST_CHECK_EQUAL(handler.max_ids_[2], 0);
+    BOOST_CHECK_EQUAL(handler.accepted_nodes_, 2);
+    BOOST_CHECK_EQUAL(handler.accepted_ways_, 1);
+}
+
+// writes a 4x1 pixel WGS84 geotiff covering 8-12 E, 50-51 N with the values 100, 200, 100, 300 from west to east
+void write_profile_tiff(const std::string &filename) {
+    auto *driver = GetGDALDriverManager()->GetDriverByName("GTiff");
+    const auto dataset = GDALDatasetUniquePtr(driver->Create(filename.c_str(), 4, 1, 1, GDT_Float32, nullptr));
+    double transform[6] = {8.0, 1.0, 0.0, 51.0, 0.0, -1.0};
+    dataset->SetGeoTransform(transform);
+    OGRSpatialReference reference;
+    reference.SetWellKnownGeogCS("WGS84");
+    dataset->SetSpatialRef(&reference);
+    float values[4] = {100.0f, 200.0f, 100.0f, 300.0f};
+    BOOST_REQUIRE(dataset->GetRasterBand(1)->RasterIO(GF_Write, 0, 0, 4, 1, values, 4, 1, GDT_Float32, 0, 0) == CE_None);
+}
+
+BOOST_AUTO_TEST_CASE (elevation_profile) {
+    auto debug_mode = false;
+    std::string geo_type{"wkt"};
+    std::string prefix{"mapping_"};
+    const auto dir = std::filesystem::temp_directory_path() / "osm-transform-elevation-profile";
+
+    // the number of refs and the profile of each written way
+    using way_list = std::vector<std::pair<std::size_t, std::string>>;
+    const auto run = [&](const bool split, const bool annotate, const std::uint32_t max_way_nodes) {
+        const auto &map_factory = osmium::index::MapFactory<osmium::unsigned_object_id_type, osmium::Location>::instance();
+        auto location_index = map_factory.create_map("flex_mem");
+
+        // registers the gdal drivers
+        LocationElevationService location_elevation_service(1 << 20, debug_mode);
+        std::filesystem::create_directories(dir);
+        write_profile_tiff((dir / "dem.tif").string());
+        location_elevation_service.load({dir.string()});
+        LocationAreaService location_area_service(debug_mode, 0, 2, geo_type, true, prefix);
+
+        osmium::nwr_array<osmium::index::IdSetDense<osmium::unsigned_object_id_type>> valid_ids;
+        osmium::nwr_array<osmium::index::IdSetSmall<osmium::unsigned_object_id_type>> no_elevation;
+        boost::regex remove_tag_regex("fixme", boost::regex::icase);
+
+        // the segments are sampled in steps of one pixel: 8.5 (100), 9.5 (200), 10.4 (100) and 10.4, 11.4 (300), 11.5 (300)
+        osmium::memory::Buffer input{1 << 10, osmium::memory::Buffer::auto_grow::yes};
+        add_node(input, 101, {}, 8.5, 50.5);
+        add_node(input, 102, {}, 10.4, 50.5);
+        add_node(input, 103, {}, 11.5, 50.5);
+        add_way(input, 10, {{"highway", "path"}}, {101, 102, 103});
+        input.commit();
+        for (const osmium::object_id_type id: {101, 102, 103}) {
+            valid_ids.nodes().set(id);
+        }
+        valid_ids.ways().set(10);
+
+        RewriteHandler handler(1000000000, location_index, location_elevation_service, location_area_service, remove_tag_regex, valid_ids, no_elevation, true, 0.5);
+        handler.split_interpolated_ = split;
+        handler.annotate_elevation_profile_ = annotate;
+        handler.max_way_nodes_ = max_way_nodes;
+        osmium::memory::Buffer output_ways{1 << 10, osmium::memory::Buffer::auto_grow::yes};
+        osmium::memory::Buffer output_nodes{1 << 10, osmium::memory::Buffer::auto_grow::yes};
+        handler.set_buffers(&output_ways, &output_nodes);
+        osmium::apply(input, handler);
+        std::filesystem::remove_all(dir);
+
+        way_list result;
+        for (const auto &way: output_ways.select<osmium::Way>()) {
+            result.emplace_back(way.nodes().size(), way.tags().get_value_by_key("_ele_profile", ""));
+        }
+        BOOST_CHECK_EQUAL(handler.ways_with_elevation_profile_, annotate ? result.size() : 0);
+        return result;
+    };
+
+    // split inserts a node at 9.5 and one at 11.4, the positions in the profile are those of the way nodes
+    BOOST_CHECK(run(true, false, 0) == way_list({{5, ""}}));
+    BOOST_CHECK(run(false, true, 0) == way_list({{3, "100,200,100,300,300"}}));
+    BOOST_CHECK(run(true, true, 0) == way_list({{5, "100,200,100,300,300"}}));
+    // each part of a split way gets the profile of its section, including the shared node
+    BOOST_CHECK(run(true, true, 3) == way_list({{3, "100,200,100"}, {3, "100,300,300"}}));
+    BOOST_CHECK(run(false, true, 2) == way_list({{2, "100,200,100"}, {2, "100,300,300"}}));
+}
+
+BOOST_AUTO_TEST_CASE (elevation_profile_truncation) {
+    std::vector<std::pair<std::size_t, double>> profile;
+    for (std::size_t position = 0; position < 600; position++) {
+        profile.emplace_back(position, position % 2 == 0 ? 1000.4 : 999.6);
+    }
+    bool truncated;
+    BOOST_CHECK_EQUAL(RewriteHandler::format_elevation_profile(profile, 2, 4, truncated), "1000,1000,1000");
+    BOOST_CHECK(!truncated);
+    // 205 values of 4 digits and the commas between them fill the maximum length of 1024 bytes
+    const auto values = RewriteHandler::format_elevation_profile(profile, 0, 599, truncated);
+    BOOST_CHECK(truncated);
+    BOOST_CHECK_EQUAL(values.size(), 1024);
+    BOOST_CHECK_EQUAL(std::count(values.begin(), values.end(), ','), 204);
+}
+
+BOOST_AUTO_TEST_CASE (report_stripped_elements) {
+    auto debug_mode = false;
+    std::string geo_type{"wkt"};
+    std::string prefix{"mapping_"};
+    std::string index_type{"flex_mem"};
+
+    const auto &map_factory = osmium::index::MapFactory<osmium::unsigned_object_id_type, osmium::Location>::instance();
+    auto location_index = map_factory.create_map(index_type);
+
+    LocationElevationService location_elevation_service(1 << 20, debug_mode);
+    LocationAreaService location_area_service(debug_mode, 0, 2, geo_type, true, prefix);
+
+    osmium::nwr_array<osmium::index::IdSetDense<osmium::unsigned_object_id_type>> valid_ids;
+    osmium::nwr_array<osmium::index::IdSetSmall<osmium::unsigned_object_id_type>> no_elevation;
+    boost::regex remove_tag_regex("(.*:)?source(:.*)?|(.*:)?note(:.*)?|url|created_by|fixme|wikipedia", boost::regex::icase);
+
+    osmium::memory::Buffer input{1 << 10, osmium::memory::Buffer::auto_grow::yes};
+    add_node(input, 101, {}, 8.0, 0.0);
+    add_node(input, 102, {}, 8.01, 0.0);
+    for (const osmium::object_id_type id: {101, 102}) {
+        valid_ids.nodes().set(id);
+    }
+    add_way(input, 10, {{"source", "survey"}, {"note", "check"}}, {101, 102});
+    add_way(input, 11, {{"highway", "path"}, {"source", "survey"}}, {101, 102});
+    add_way(input, 12, {}, {101, 102});
+    add_relation(input, 20, {{"created_by", "editor"}}, {{osmium::item_type::way, 10, ""}});
+    add_relation(input, 21, {{"type", "route"}}, {{osmium::item_type::way, 11, ""}});
+    for (const osmium::object_id_type id: {10, 11, 12}) {
+        valid_ids.ways().set(id);
+    }
+    valid_ids.relations().set(20);
+    valid_ids.relations().set(21);
+    input.commit();
+
+    RewriteHandler handler(1000000000, location_index, location_elevation_service, location_area_service, remove_tag_regex, valid_ids, no_elevation, false, 0.5);
+    handler.count_stripped_elements_ = true;
+    osmium::memory::Buffer output{1 << 10, osmium::memory::Buffer::auto_grow::yes};
+    handler.set_buffers(&output, &output);
+    osmium::apply(input, handler);
+
+    // ways without tags in the input don't count
+    BOOST_CHECK_EQUAL(handler.ways_stripped_of_tags_, 1);
+    BOOST_CHECK_EQUAL(handler.relations_stripped_of_tags_, 1);
+    BOOST_CHECK(output.select<osmium::Way>().begin()->tags().empty());
+}
+
+BOOST_AUTO_TEST_CASE (dedup_tags) {
+    auto debug_mode = false;
+    std::string geo_type{"wkt"};
+    std::string prefix{"mapping_"};
+
+    const auto &map_factory = osmium::index::MapFactory<osmium::unsigned_object_id_type, osmium::Location>::instance();
+    auto location_index = map_factory.create_map("flex_mem");
+
+    LocationElevationService location_elevation_service(1 << 20, debug_mode);
+    LocationAreaService location_area_service(debug_mode, 0, 2, geo_type, true, prefix);
+
+    osmium::nwr_array<osmium::index::IdSetDense<osmium::unsigned_object_id_type>> valid_ids;
+    osmium::nwr_array<osmium::index::IdSetSmall<osmium::unsigned_object_id_type>> no_elevation;
+    boost::regex remove_tag_regex("fixme", boost::regex::icase);
+
+    osmium::memory::Buffer input{1 << 10, osmium::memory::Buffer::auto_grow::yes};
+    {
+        osmium::builder::NodeBuilder builder{input};
+        builder.object().set_id(101);
+        builder.object().set_location(osmium::Location(8.0, 50.0));
+        osmium::builder::TagListBuilder tags{builder};
+        tags.add_tag("amenity", "bench");
+        tags.add_tag("name", "first");
+        tags.add_tag("name", "last");
+    }
+    input.commit();
+    valid_ids.nodes().set(101);
+
+    for (const auto mode: {RewriteHandler::dedup_tags_mode::first, RewriteHandler::dedup_tags_mode::last}) {
+        RewriteHandler handler(1000000000, location_index, location_elevation_service, location_area_service, remove_tag_regex, valid_ids, no_elevation, false, 0.5);
+        handler.dedup_tags_ = mode;
+        osmium::memory::Buffer output{1 << 10, osmium::memory::Buffer::auto_grow::yes};
+        handler.set_buffers(&output, &output);
+        osmium::apply(input, handler);
+
+        const auto &node = *output.select<osmium::Node>().begin();
+        BOOST_CHECK_EQUAL(node.tags().size(), 2);
+        BOOST_CHECK_EQUAL(node.tags().get_value_by_key("name", ""), mode == RewriteHandler::dedup_tags_mode::first ? "first" : "last");
+        BOOST_CHECK_EQUAL(handler.duplicate_tags_removed_, 1);
+    }
+}
+
+BOOST_AUTO_TEST_CASE (preserve_original_ele) {
+    auto debug_mode = false;
+    std::string geo_type{"wkt"};
+    std::string prefix{"mapping_"};
+
+    const auto &map_factory = osmium::index::MapFactory<osmium::unsigned_object_id_type, osmium::Location>::instance();
+    auto location_index = map_factory.create_map("flex_mem");
+
+    LocationElevationService location_elevation_service(1 << 20, debug_mode);
+    LocationAreaService location_area_service(debug_mode, 0, 2, geo_type, true, prefix);
+
+    osmium::nwr_array<osmium::index::IdSetDense<osmium::unsigned_object_id_type>> valid_ids;
+    osmium::nwr_array<osmium::index::IdSetSmall<osmium::unsigned_object_id_type>> no_elevation;
+    boost::regex remove_tag_regex("fixme", boost::regex::icase);
+
+    osmium::memory::Buffer input{1 << 10, osmium::memory::Buffer::auto_grow::yes};
+    add_node(input, 101, {{"natural", "peak"}, {"ele", "2962"}, {"ele:osm", "outdated"}}, 8.0, 50.0);
+    add_node(input, 102, {{"natural", "peak"}, {"ele:osm", "kept"}}, 8.1, 50.0);
+    input.commit();
+    valid_ids.nodes().set(101);
+    valid_ids.nodes().set(102);
+
+    RewriteHandler handler(1000000000, location_index, location_elevation_service, location_area_service, remove_tag_regex, valid_ids, no_elevation, false, 0.5);
+    handler.add_elevation_ = true;
+    handler.preserve_ele_key_ = "ele:osm";
+    osmium::memory::Buffer output{1 << 10, osmium::memory::Buffer::auto_grow::yes};
+    handler.set_buffers(&output, &output);
+    osmium::apply(input, handler);
+
+    auto nodes = output.select<osmium::Node>().begin();
+    // no geotiffs are loaded, so the node gets no DEM elevation
+    BOOST_CHECK(!nodes->tags().has_key("ele"));
+    BOOST_CHECK_EQUAL(nodes->tags().get_value_by_key("ele:osm", ""), "2962");
+    BOOST_CHECK_EQUAL(nodes->tags().size(), 2);
+    ++nodes;
+    BOOST_CHECK_EQUAL(nodes->tags().get_value_by_key("ele:osm", ""), "kept");
+    BOOST_CHECK_EQUAL(handler.ele_tags_preserved_, 1);
+}
+
+BOOST_AUTO_TEST_CASE (required_keys_keep_way_nodes) {
+    auto debug_mode = false;
+    std::string geo_type{"wkt"};
+    std::string prefix{"mapping_"};
+
+    const auto &map_factory = osmium::index::MapFactory<osmium::unsigned_object_id_type, osmium::Location>::instance();
+    auto location_index = map_factory.create_map("flex_mem");
+
+    LocationElevationService location_elevation_service(1 << 20, debug_mode);
+    LocationAreaService location_area_service(debug_mode, 0, 2, geo_type, true, prefix);
+
+    osmium::nwr_array<osmium::index::IdSetDense<osmium::unsigned_object_id_type>> valid_ids;
+    osmium::nwr_array<osmium::index::IdSetSmall<osmium::unsigned_object_id_type>> no_elevation;
+    boost::regex remove_tag_regex("(.*:)?source(:.*)?|(.*:)?note(:.*)?|url|created_by|fixme|wikipedia", boost::regex::icase);
+    // the default types of require_keys
+    const RequiredKeysFilter filter({"highway", "name"}, osmium::osm_entity_bits::way);
+
+    osmium::memory::Buffer input{1 << 10, osmium::memory::Buffer::auto_grow::yes};
+    add_node(input, 101, {}, 8.0, 50.0);
+    add_node(input, 102, {}, 8.01, 50.0);
+    add_node(input, 103, {}, 8.02, 50.0);
+    add_way(input, 10, {{"highway", "residential"}, {"name", "Main Street"}}, {101, 102});
+    add_way(input, 11, {{"highway", "residential"}}, {102, 103});
+    input.commit();
+
+    FirstPassHandler first_pass(remove_tag_regex, valid_ids, no_elevation);
+    first_pass.element_filter_ = [&filter](const osmium::OSMObject &object) { return filter.accept(object); };
+    osmium::apply(input, first_pass);
+
+    RewriteHandler handler(1000000000, location_index, location_elevation_service, location_area_service, remove_tag_regex, valid_ids, no_elevation, false, 0.5);
+    handler.element_filter_ = [&filter](const osmium::OSMObject &object) { return filter.accept(object); };
+    osmium::memory::Buffer output{1 << 10, osmium::memory::Buffer::auto_grow::yes};
+    handler.set_buffers(&output, &output);
+    osmium::apply(input, handler);
+
+    std::vector<osmium::object_id_type> nodes;
+    for (const auto &node: output.select<osmium::Node>()) {
+        nodes.push_back(node.id());
+    }
+    BOOST_CHECK(nodes == std::vector<osmium::object_id_type>({101, 102}));
+    auto ways = output.select<osmium::Way>().begin();
+    BOOST_CHECK_EQUAL(ways->id(), 10);
+    BOOST_CHECK_EQUAL(ways->nodes().size(), 2);
+    BOOST_CHECK(++ways == output.select<osmium::Way>().end());
+}
+
+BOOST_AUTO_TEST_CASE (keep_invalid_coordinates) {
+    auto debug_mode = false;
+    std::string geo_type{"wkt"};
+    std::string prefix{"mapping_"};
+
+    const auto &map_factory = osmium::index::MapFactory<osmium::unsigned_object_id_type, osmium::Location>::instance();
+    auto location_index = map_factory.create_map("flex_mem");
+
+    LocationElevationService location_elevation_service(1 << 20, debug_mode);
+    LocationAreaService location_area_service(debug_mode, 0, 2, geo_type, true, prefix);
+
+    osmium::nwr_array<osmium::index::IdSetDense<osmium::unsigned_object_id_type>> valid_ids;
+    osmium::nwr_array<osmium::index::IdSetSmall<osmium::unsigned_object_id_type>> no_elevation;
+    boost::regex remove_tag_regex("fixme", boost::regex::icase);
+
+    osmium::memory::Buffer input{1 << 10, osmium::memory::Buffer::auto_grow::yes};
+    add_node(input, 101, {{"country", "DE"}, {"ele", "120"}, {"natural", "peak"}}, 200.0, 50.0);
+    input.commit();
+    valid_ids.nodes().set(101);
+
+    RewriteHandler handler(1000000000, location_index, location_elevation_service, location_area_service, remove_tag_regex, valid_ids, no_elevation, false, 0.5);
+    handler.add_elevation_ = true;
+    osmium::memory::Buffer output{1 << 10, osmium::memory::Buffer::auto_grow::yes};
+    handler.set_buffers(&output, &output);
+    osmium::apply(input, handler);
+
+    auto nodes = output.select<osmium::Node>().begin();
+    BOOST_CHECK_EQUAL(nodes->id(), 101);
+    BOOST_CHECK_EQUAL(nodes->tags().get_value_by_key("country", ""), "DE");
+    BOOST_CHECK_EQUAL(nodes->tags().get_value_by_key("ele", ""), "120");
+    BOOST_CHECK_EQUAL(nodes->tags().size(), 3);
+    BOOST_CHECK_EQUAL(handler.invalid_coordinates_, 1);
+    BOOST_CHECK_EQUAL(handler.accepted_nodes_, 1);
+    BOOST_CHECK_EQUAL(handler.processed_elements_, 1);
+}
+
+BOOST_AUTO_TEST_CASE (split_node_tag_mode) {
+    auto debug_mode = false;
+    std::string geo_type{"wkt"};
+    std::string prefix{"mapping_"};
+
+    const auto &map_factory = osmium::index::MapFactory<osmium::unsigned_object_id_type, osmium::Location>::instance();
+
+    LocationElevationService location_elevation_service(1 << 20, debug_mode);
+    location_elevation_service.load({"files/limburg_an_der_lahn.tif"});
+    LocationAreaService location_area_service(debug_mode, 0, 2, geo_type, true, prefix);
+
+    osmium::nwr_array<osmium::index::IdSetDense<osmium::unsigned_object_id_type>> valid_ids;
+    osmium::nwr_array<osmium::index::IdSetSmall<osmium::unsigned_object_id_type>> no_elevation;
+    boost::regex remove_tag_regex("fixme", boost::regex::icase);
+
+    osmium::memory::Buffer input{1 << 10, osmium::memory::Buffer::auto_grow::yes};
+    add_node(input, 101, {}, 8.0515393, 50.3873984);
+    add_node(input, 102, {}, 8.0505023, 50.3868868);
+    valid_ids.nodes().set(101);
+    valid_ids.nodes().set(102);
+    add_way(input, 10, {{"highway", "path"}}, {101, 102});
+    valid_ids.ways().set(10);
+    input.commit();
+
+    for (const auto mode: {RewriteHandler::split_node_tags::marker, RewriteHandler::split_node_tags::none}) {
+        auto location_index = map_factory.create_map("flex_mem");
+        RewriteHandler handler(1000000000, location_index, location_elevation_service, location_area_service, remove_tag_regex, valid_ids, no_elevation, true, 0.5);
+        handler.split_node_tags_ = mode;
+        osmium::memory::Buffer output_nodes{1 << 10, osmium::memory::Buffer::auto_grow::yes};
+        osmium::memory::Buffer output_ways{1 << 10, osmium::memory::Buffer::auto_grow::yes};
+        handler.set_buffers(&output_ways, &output_nodes);
+        osmium::apply(input, handler);
+
+        BOOST_CHECK(handler.nodes_added_by_interpolation_ > 0);
+        for (const auto &node: output_nodes.select<osmium::Node>()) {
+            if (node.id() < 1000000000) {
+                continue;
+            }
+            if (mode == RewriteHandler::split_node_tags::marker) {
+                BOOST_CHECK_EQUAL(node.tags().get_value_by_key("_generated", ""), "yes");
+                BOOST_CHECK(node.tags().has_key("ele"));
+            } else {
+                BOOST_CHECK(node.tags().empty());
+            }
+        }
+    }
+}
+
+BOOST_AUTO_TEST_CASE (keep_metadata_fields) {
+    auto debug_mode = false;
+    std::string geo_type{"wkt"};
+    std::string prefix{"mapping_"};
+
+    const auto &map_factory = osmium::index::MapFactory<osmium::unsigned_object_id_type, osmium::Location>::instance();
+    auto location_index = map_factory.create_map("flex_mem");
+
+    LocationElevationService location_elevation_service(1 << 20, debug_mode);
+    LocationAreaService location_area_service(debug_mode, 0, 2, geo_type, true, prefix);
+
+    osmium::nwr_array<osmium::index::IdSetDense<osmium::unsigned_object_id_type>> valid_ids;
+    osmium::nwr_array<osmium::index::IdSetSmall<osmium::unsigned_object_id_type>> no_elevation;
+    boost::regex remove_tag_regex("fixme", boost::regex::icase);
+
+    osmium::memory::Buffer input{1 << 10, osmium::memory::Buffer::auto_grow::yes};
+    {
+        osmium::builder::NodeBuilder builder{input};
+        builder.object().set_id(101);
+        builder.object().set_location(osmium::Location(8.0, 50.0));
+        builder.set_version(3);
+        builder.set_changeset(42);
+        builder.set_uid(7);
+        builder.set_user("mapper");
+    }
+    input.commit();
+    valid_ids.nodes().set(101);
+
+    RewriteHandler handler(1000000000, location_index, location_elevation_service, location_area_service, remove_tag_regex, valid_ids, no_elevation, false, 0.5);
+    handler.keep_metadata_fields_ = osmium::metadata_options{"version+user"};
+    osmium::memory::Buffer output{1 << 10, osmium::memory::Buffer::auto_grow::yes};
+    handler.set_buffers(&output, &output);
+    osmium::apply(input, handler);
+
+    const auto &node = *output.select<osmium::Node>().begin();
+    BOOST_CHECK_EQUAL(node.version(), 3);
+    BOOST_CHECK_EQUAL(node.changeset(), 0);
+    BOOST_CHECK_EQUAL(node.uid(), 0);
+    BOOST_CHECK_EQUAL(std::string(node.user()), "mapper");
+}
+
+BOOST_AUTO_TEST_CASE (normalize_winding_oneways) {
+    auto debug_mode = false;
+    std::string geo_type{"wkt"};
+    std::string prefix{"mapping_"};
+
+    const auto &map_factory = osmium::index::MapFactory<osmium::unsigned_object_id_type, osmium::Location>::instance();
+    auto location_index = map_factory.create_map("flex_mem");
+
+    LocationElevationService location_elevation_service(1 << 20, debug_mode);
+    LocationAreaService location_area_service(debug_mode, 0, 2, geo_type, true, prefix);
+
+    osmium::nwr_array<osmium::index::IdSetDense<osmium::unsigned_object_id_type>> valid_ids;
+    osmium::nwr_array<osmium::index::IdSetSmall<osmium::unsigned_object_id_type>> no_elevation;
+    boost::regex remove_tag_regex("fixme", boost::regex::icase);
+
+    osmium::memory::Buffer input{1 << 10, osmium::memory::Buffer::auto_grow::yes};
+    // a clockwise square
+    add_node(input, 1, {}, 8.0, 50.0);
+    add_node(input, 2, {}, 8.0, 50.1);
+    add_node(input, 3, {}, 8.1, 50.1);
+    add_node(input, 4, {}, 8.1, 50.0);
+    for (const auto id: {1, 2, 3, 4}) {
+        valid_ids.nodes().set(id);
+    }
+    const std::vector<std::string> values{"yes", "true", "1", "-1", "no", "reversible", "alternating"};
+    for (std::size_t i = 0; i < values.size(); i++) {
+        add_way(input, 10 + i, {{"highway", "service"}, {"oneway", values[i]}}, {1, 2, 3, 4, 1});
+        valid_ids.ways().set(10 + i);
+    }
+    input.commit();
+
+    RewriteHandler handler(1000000000, location_index, location_elevation_service, location_area_service, remove_tag_regex, valid_ids, no_elevation, false, 0.5);
+    handler.normalize_winding_ = true;
+    osmium::memory::Buffer output{1 << 10, osmium::memory::Buffer::auto_grow::yes};
+    handler.set_buffers(&output, &output);
+    osmium::apply(input, handler);
+
+    BOOST_CHECK_EQUAL(handler.ways_with_reversed_winding_, 3);
+    for (const auto &way: output.select<osmium::Way>()) {
+        const bool oneway = way.id() < 14;
+        BOOST_TEST_INFO("way " << way.id());
+        BOOST_CHECK_EQUAL(way.nodes()[1].ref(), oneway ? 2 : 4);
+    }
+}
+
+BOOST_FIXTURE_TEST_CASE (exclude_changesets, RewriteFixture) {
+    for (const auto &[id, changeset]: {std::pair{101, 42}, std::pair{102, 43}}) {
+        osmium::builder::NodeBuilder builder{input};
+        builder.object().set_id(id);
+        builder.object().set_location(kInside1);
+        builder.set_changeset(changeset);
+        valid_ids.nodes().set(id);
+    }
+
+    auto handler = make_handler();
+    handler.exclude_changesets_ = {42};
+    run(handler);
+
+    BOOST_CHECK_EQUAL(handler.nodes_excluded_by_changeset_, 1);
+    BOOST_CHECK(find<osmium::Node>(101) == nullptr);
+    BOOST_CHECK(find<osmium::Node>(102) != nullptr);
+}
+
+BOOST_FIXTURE_TEST_CASE (drop_invisible, RewriteFixture) {
+    for (const auto id: {101, 102}) {
+        osmium::builder::NodeBuilder builder{input};
+        builder.object().set_id(id);
+        builder.object().set_location(kInside1);
+        builder.object().set_visible(id == 102);
+        valid_ids.nodes().set(id);
+    }
+
+    auto handler = make_handler();
+    handler.drop_invisible_ = true;
+    run(handler);
+
+    BOOST_CHECK_EQUAL(handler.invisible_nodes_, 1);
+    BOOST_CHECK(find<osmium::Node>(101) == nullptr);
+    BOOST_CHECK(find<osmium::Node>(102) != nullptr);
+}
+
+BOOST_FIXTURE_TEST_CASE (remove_metadata_from, RewriteFixture) {
+    {
+        osmium::builder::NodeBuilder builder{input};
+        builder.object().set_id(101);
+        builder.object().set_location(kInside1);
+        builder.set_version(3);
+    }
+    {
+        osmium::builder::WayBuilder builder{input};
+        builder.object().set_id(10);
+        builder.set_version(5);
+        osmium::builder::WayNodeListBuilder{builder}.add_node_ref(101);
+    }
+    valid_ids.nodes().set(101);
+    valid_ids.ways().set(10);
+
+    // metadata removed from the nodes only
+    auto handler = make_handler();
+    handler.keep_metadata_ = osmium::osm_entity_bits::way | osmium::osm_entity_bits::relation;
+    run(handler);
+
+    BOOST_CHECK_EQUAL(find<osmium::Node>(101)->version(), 0);
+    BOOST_CHECK_EQUAL(find<osmium::Way>(10)->version(), 5);
+}
+
+BOOST_FIXTURE_TEST_CASE (output_only_modified, RewriteFixture) {
+    location_elevation_service.load({"files/limburg_an_der_lahn.tif"});
+    add_node(input, 101, {}, kInside1.lon(), kInside1.lat());
+    add_node(input, 102, {}, kInside2.lon(), kInside2.lat());
+    add_node(input, 103, {}, kOutside.lon(), kOutside.lat());
+    add_way(input, 10, {{"highway", "track"}}, {101, 102});
+    add_way(input, 11, {{"highway", "track"}, {"oneway", "-1"}}, {101, 102});
+    add_relation(input, 20, {{"type", "route"}}, {{osmium::item_type::way, 10, ""}});
+    for (const auto id: {101, 102, 103}) {
+        valid_ids.nodes().set(id);
+    }
+    valid_ids.ways().set(10);
+    valid_ids.ways().set(11);
+    valid_ids.relations().set(20);
+
+    auto handler = make_handler();
+    handler.add_elevation_ = true;
+    handler.output_only_modified_ = true;
+    handler.normalize_oneway_direction_ = true;
+    run(handler);
+
+    // the node without elevation, the unchanged way and the relation
+    BOOST_CHECK_EQUAL(handler.unmodified_elements_skipped_, 3);
+    BOOST_CHECK(find<osmium::Node>(101) != nullptr);
+    BOOST_CHECK(find<osmium::Node>(102) != nullptr);
+    BOOST_CHECK(find<osmium::Node>(103) == nullptr);
+    BOOST_CHECK(find<osmium::Way>(10) == nullptr);
+    BOOST_CHECK(find<osmium::Way>(11) != nullptr);
+    BOOST_CHECK(find<osmium::Relation>(20) == nullptr);
+}
+
+BOOST_FIXTURE_TEST_CASE (elevation_csv, RewriteFixture) {
+    location_elevation_service.load({"files/limburg_an_der_lahn.tif"});
+    add_node(input, 101, {}, kInside1.lon(), kInside1.lat());
+    add_node(input, 103, {}, kOutside.lon(), kOutside.lat());
+    valid_ids.nodes().set(101);
+    valid_ids.nodes().set(103);
+
+    std::ostringstream csv;
+    auto handler = make_handler();
+    handler.add_elevation_ = true;
+    handler.elevation_csv_ = &csv;
+    run(handler);
+
+    // only nodes with an elevation are written
+    const auto line = csv.str();
+    BOOST_CHECK_EQUAL(std::count(line.begin(), line.end(), '\n'), 1);
+    BOOST_CHECK(line.starts_with("101,8.05"));
+    const auto ele = location_elevation_service.elevation(kInside1, false);
+    std::ostringstream expected;
+    expected << ele << "\n";
+    BOOST_CHECK(line.ends_with("," + expected.str()));
+}
+
+BOOST_FIXTURE_TEST_CASE (no_ele_tag, RewriteFixture) {
+    location_elevation_service.load({"files/limburg_an_der_lahn.tif"});
+    add_node(input, 101, {{"ele", "999"}}, kInside1.lon(), kInside1.lat());
+    add_node(input, 102, {}, kInside2.lon(), kInside2.lat());
+    valid_ids.nodes().set(101);
+    valid_ids.nodes().set(102);
+
+    std::ostringstream csv;
+    auto handler = make_handler();
+    handler.add_elevation_ = true;
+    handler.ele_tag_ = false;
+    handler.elevation_csv_ = &csv;
+    run(handler);
+
+    // the existing ele tags are kept and the elevations are only written to the csv
+    BOOST_CHECK_EQUAL(std::string(find<osmium::Node>(101)->tags().get_value_by_key("ele", "")), "999");
+    BOOST_CHECK(!find<osmium::Node>(102)->tags().has_key("ele"));
+    BOOST_CHECK_EQUAL(handler.nodes_with_elevation_, 2);
+    const auto lines = csv.str();
+    BOOST_CHECK_EQUAL(std::count(lines.begin(), lines.end(), '\n'), 2);
+}
+
+BOOST_FIXTURE_TEST_CASE (elevation_only_tagged, RewriteFixture) {
+    location_elevation_service.load({"files/limburg_an_der_lahn.tif"});
+    add_node(input, 101, {{"amenity", "bench"}}, kInside1.lon(), kInside1.lat());
+    add_node(input, 102, {}, kInside2.lon(), kInside2.lat());
+    add_node(input, 103, {{"amenity", "bench"}}, kOutside.lon(), kOutside.lat());
+    // only tags removed by the regex
+    add_node(input, 104, {{"fixme", "position"}}, kInside2.lon(), kInside2.lat());
+    for (const auto id: {101, 102, 103, 104}) {
+        valid_ids.nodes().set(id);
+    }
+
+    auto handler = make_handler();
+    handler.add_elevation_ = true;
+    handler.elevation_only_tagged_ = true;
+    handler.tag_elevation_status_ = true;
+    run(handler);
+
+    BOOST_CHECK_EQUAL(handler.untagged_nodes_without_elevation_, 2);
+    BOOST_CHECK_EQUAL(handler.nodes_with_elevation_, 1);
+    BOOST_CHECK_EQUAL(handler.nodes_with_elevation_not_found_, 1);
+    const auto *tagged = find<osmium::Node>(101);
+    BOOST_CHECK(tagged->tags().has_key("ele"));
+    BOOST_CHECK(!tagged->tags().has_key("ele:status"));
+    for (const auto id: {102, 104}) {
+        const auto *untagged = find<osmium::Node>(id);
+        BOOST_CHECK(!untagged->tags().has_key("ele"));
+        BOOST_CHECK_EQUAL(std::string(untagged->tags().get_value_by_key("ele:status", "")), "skipped");
+    }
+    BOOST_CHECK_EQUAL(std::string(find<osmium::Node>(103)->tags().get_value_by_key("ele:status", "")), "nodata");
+}
+
+BOOST_FIXTURE_TEST_CASE (output_bbox, RewriteFixture) {
+    add_node(input, 101, {}, 8.0, 50.0);
+    add_node(input, 102, {}, 8.2, 50.1);
+    // not accepted by the first pass
+    add_node(input, 103, {}, 9.0, 51.0);
+    valid_ids.nodes().set(101);
+    valid_ids.nodes().set(102);
+
+    auto handler = make_handler();
+    run(handler);
+
+    BOOST_CHECK(handler.output_bbox_.bottom_left() == osmium::Location(8.0, 50.0));
+    BOOST_CHECK(handler.output_bbox_.top_right() == osmium::Location(8.2, 50.1));
+}
+
+BOOST_FIXTURE_TEST_CASE (min_way_length, RewriteFixture) {
+    // ca. 11 m and 111 m from the first node
+    add_node(input, 1, {}, 8.0, 50.0);
+    add_node(input, 2, {}, 8.0, 50.0001);
+    add_node(input, 3, {}, 8.0, 50.001);
+    for (const auto id: {1, 2, 3}) {
+        valid_ids.nodes().set(id);
+    }
+    add_way(input, 10, {{"highway", "service"}}, {1, 2});
+    add_way(input, 11, {{"highway", "service"}}, {1, 3});
+    valid_ids.ways().set(10);
+    valid_ids.ways().set(11);
+
+    auto handler = make_handler();
+    handler.min_way_length_ = 50;
+    run(handler);
+
+    BOOST_CHECK_EQUAL(handler.ways_dropped_by_length_, 1);
+    BOOST_CHECK_EQUAL(handler.accepted_ways_, 1);
+    BOOST_CHECK(find<osmium::Way>(10) == nullptr);
+    BOOST_CHECK(find<osmium::Way>(11) != nullptr);
+}
+
+BOOST_FIXTURE_TEST_CASE (generated_id_base, RewriteFixture) {
+    location_elevation_service.load({"files/limburg_an_der_lahn.tif"});
+    add_node(input, 101, {}, kInside1.lon(), kInside1.lat());
+    add_node(input, 102, {}, kInside2.lon(), kInside2.lat());
+    valid_ids.nodes().set(101);
+    valid_ids.nodes().set(102);
+    add_way(input, 10, {{"highway", "track"}}, {101, 102});
+    valid_ids.ways().set(10);
+
+    auto handler = make_handler(true, 5000);
+    run(handler);
+
+    BOOST_CHECK(handler.nodes_added_by_interpolation_ > 0);
+    const auto &way = *find<osmium::Way>(10);
+    BOOST_CHECK_EQUAL(way.nodes().size(), handler.nodes_added_by_interpolation_ + 2);
+    // consecutive ids from the base between the way endpoints
+    for (std::size_t i = 1; i + 1 < way.nodes().size(); i++) {
+        BOOST_CHECK_EQUAL(way.nodes()[i].ref(), 5000 + static_cast<osmium::object_id_type>(i) - 1);
+        BOOST_CHECK(find<osmium::Node>(way.nodes()[i].ref()) != nullptr);
+    }
+}
+
+BOOST_FIXTURE_TEST_CASE (count_nodes_by_resolution, RewriteFixture) {
+    location_elevation_service.load({"files/limburg_an_der_lahn.tif"});
+    add_node(input, 101, {}, kInside1.lon(), kInside1.lat());
+    add_node(input, 102, {}, kInside2.lon(), kInside2.lat());
+    valid_ids.nodes().set(101);
+    valid_ids.nodes().set(102);
+    add_way(input, 10, {{"highway", "track"}}, {101, 102});
+    valid_ids.ways().set(10);
+
+    auto handler = make_handler(true);
+    handler.count_nodes_by_resolution_ = true;
+    run(handler);
+
+    BOOST_CHECK(handler.nodes_added_by_interpolation_ > 0);
+    const auto counted = std::accumulate(handler.nodes_added_by_resolution_.begin(), handler.nodes_added_by_resolution_.end(), 0ULL,
+                                         [](const auto sum, const auto &entry) { return sum + entry.second; });
+    BOOST_CHECK_EQUAL(counted, handler.nodes_added_by_interpolation_);
+    // all interpolated nodes lie within the same geotiff, the pixel size only varies slightly with the latitude
+    for (const auto &[resolution, count]: handler.nodes_added_by_resolution_) {
+        BOOST_CHECK(std::abs(resolution - std::lround(location_elevation_service.resolution(kInside1))) <= 1);
+    }
+}
+
+BOOST_FIXTURE_TEST_CASE (annotate_way_grade, RewriteFixture) {
+    location_elevation_service.load({"files/limburg_an_der_lahn.tif"});
+    add_node(input, 101, {}, kInside1.lon(), kInside1.lat());
+    add_node(input, 102, {}, kInside2.lon(), kInside2.lat());
+    add_node(input, 103, {}, kOutside.lon(), kOutside.lat());
+    for (const auto id: {101, 102, 103}) {
+        valid_ids.nodes().set(id);
+    }
+    add_way(input, 10, {{"highway", "track"}}, {101, 102});
+    add_way(input, 11, {{"highway", "track"}}, {101, 103});
+    valid_ids.ways().set(10);
+    valid_ids.ways().set(11);
+
+    auto handler = make_handler();
+    handler.annotate_way_grade_ = true;
+    run(handler);
+
+    BOOST_CHECK_EQUAL(handler.ways_with_grade_, 1);
+    const auto &tags = find<osmium::Way>(10)->tags();
+    const auto start = location_elevation_service.elevation(kInside1, false);
+    const auto end = location_elevation_service.elevation(kInside2, false);
+    BOOST_CHECK_EQUAL(std::string(tags.get_value_by_key("ele:start", "")), RewriteHandler::format_elevation(start));
+    BOOST_CHECK_EQUAL(std::string(tags.get_value_by_key("ele:end", "")), RewriteHandler::format_elevation(end));
+    const auto length = osmium::geom::haversine::distance(osmium::geom::Coordinates(kInside1), osmium::geom::Coordinates(kInside2));
+    // rounded to one decimal
+    BOOST_CHECK_SMALL(std::stod(tags.get_value_by_key("grade", "0")) - (end - start) / length * 100, 0.051);
+    // the elevation of an endpoint is unknown
+    BOOST_CHECK(!find<osmium::Way>(11)->tags().has_key("ele:start"));
+    BOOST_CHECK(!find<osmium::Way>(11)->tags().has_key("grade"));
+}
+
+BOOST_FIXTURE_TEST_CASE (lift_node_tags, RewriteFixture) {
+    add_node(input, 1, {{"barrier", "gate"}}, 8.0, 50.0);
+    add_node(input, 2, {}, 8.0, 50.001);
+    add_node(input, 3, {{"barrier", "bollard"}}, 8.0, 50.002);
+    add_node(input, 4, {{"barrier", "gate"}}, 8.0, 50.003);
+    for (const auto id: {1, 2, 3, 4}) {
+        valid_ids.nodes().set(id);
+    }
+    add_way(input, 10, {{"highway", "service"}}, {1, 2, 3, 4});
+    add_way(input, 11, {{"highway", "service"}, {"barrier", "fence"}}, {2, 3});
+    add_way(input, 12, {{"highway", "service"}}, {2});
+    for (const auto id: {10, 11, 12}) {
+        valid_ids.ways().set(id);
+    }
+
+    auto handler = make_handler();
+    handler.lift_node_tags_ = {"barrier"};
+    run(handler);
+
+    BOOST_CHECK_EQUAL(handler.ways_with_lifted_tags_, 1);
+    // distinct values in way order
+    BOOST_CHECK_EQUAL(std::string(find<osmium::Way>(10)->tags().get_value_by_key("barrier", "")), "gate;bollard");
+    // the own value of the way is kept
+    const auto &tags = find<osmium::Way>(11)->tags();
+    BOOST_CHECK_EQUAL(std::count_if(tags.begin(), tags.end(), [](const osmium::Tag &tag) { return std::string(tag.key()) == "barrier"; }), 1);
+    BOOST_CHECK_EQUAL(std::string(tags.get_value_by_key("barrier", "")), "fence");
+    BOOST_CHECK(!find<osmium::Way>(12)->tags().has_key("barrier"));
+}
+
 BOOST_AUTO_TEST_SUITE_END()
@@ -0,0 +1,115 @@
+#include <boost/test/unit_test.hpp>
+
+#include <string>
+#include <tuple>
+#include <utility>
+#include <vector>
+
+#include <osmium/io/opl_input.hpp>
+#include <osmium/io/opl_output.hpp>
+#include <osmium/io/reader.hpp>
+#include <osmium/memory/buffer.hpp>
+#include <osmium/osm/node.hpp>
+#include <osmium/osm/way.hpp>
+
+#include "sharded_writer.h"
+
+#include "test_utils.h"
+
+BOOST_AUTO_TEST_SUITE( test_sharded_writer )
+
+BOOST_AUTO_TEST_CASE( shard_path ) {
+    BOOST_TEST(ShardedWriter::shard_path("out.pbf", 0) == "out.000.pbf");
+    BOOST_TEST(ShardedWriter::shard_path("dir/planet.ors.pbf", 12) == "dir/planet.ors.012.pbf");
+}
+
+BOOST_AUTO_TEST_CASE( chunks ) {
+    {
+        std::vector<osmium::io::File> files;
+        for (std::size_t i = 0; i < 2; i++) {
+            files.emplace_back(ShardedWriter::shard_path("sharded_writer_test.opl", i));
+        }
+        ShardedWriter writer{files, osmium::io::Header(), 2};
+        for (const osmium::object_id_type first: {1, 4}) {
+            osmium::memory::Buffer buffer{1024, osmium::memory::Buffer::auto_grow::yes};
+            for (osmium::object_id_type id = first; id < first + 3; id++) {
+                add_node(buffer, id, {}, 8.0, 49.0);
+            }
+            buffer.commit();
+            writer(std::move(buffer));
+        }
+        writer.close();
+        BOOST_TEST(writer.counts() == std::vector<unsigned long long>({4, 2}), boost::test_tools::per_element());
+    }
+
+    const auto ids = [](const std::string &filename) {
+        std::vector<osmium::object_id_type> result;
+        osmium::io::Reader reader{filename};
+        while (const auto buffer = reader.read()) {
+            for (const auto &node: buffer.select<osmium::Node>()) {
+                result.push_back(node.id());
+            }
+        }
+        reader.close();
+        return result;
+    };
+    BOOST_TEST(ids("sharded_writer_test.000.opl") == std::vector<osmium::object_id_type>({1, 2, 5, 6}), boost::test_tools::per_element());
+    BOOST_TEST(ids("sharded_writer_test.001.opl") == std::vector<osmium::object_id_type>({3, 4}), boost::test_tools::per_element());
+}
+
+BOOST_AUTO_TEST_CASE( boundary_way_policy ) {
+    using way_list = std::vector<std::pair<osmium::object_id_type, std::size_t>>;
+    // the ids and numbers of nodes of the ways in a shard
+    const auto ways = [](const std::string &filename) {
+        way_list result;
+        osmium::io::Reader reader{filename};
+        while (const auto buffer = reader.read()) {
+            for (const auto &way: buffer.select<osmium::Way>()) {
+                result.emplace_back(way.id(), way.nodes().size());
+            }
+        }
+        reader.close();
+        return result;
+    };
+    const auto split = [&](const ShardedWriter::boundary_way_policy policy) {
+        std::vector<osmium::io::File> files;
+        for (std::size_t i = 0; i < 2; i++) {
+            files.emplace_back(ShardedWriter::shard_path("boundary_way_test.opl", i));
+        }
+        ShardedWriter writer{files, osmium::io::Header(), 2};
+        writer.boundary_way_policy_ = policy;
+        osmium::memory::Buffer buffer{1024, osmium::memory::Buffer::auto_grow::yes};
+        // nodes 1, 2, 5, 6 go to the first shard, 3 and 4 to the second one
+        for (osmium::object_id_type id = 1; id <= 6; id++) {
+            add_node(buffer, id, {}, 8.0, 49.0);
+        }
+        add_way(buffer, 10, {{"highway", "residential"}}, {1, 2});
+        add_way(buffer, 11, {{"highway", "residential"}}, {2, 3, 4});
+        add_way(buffer, 12, {{"highway", "residential"}}, {4, 5, 6});
+        buffer.commit();
+        writer(std::move(buffer));
+        writer.close();
+        BOOST_TEST(writer.boundary_ways_ == (policy == ShardedWriter::boundary_way_policy::none ? 0u : 2u));
+        return std::make_pair(ways("boundary_way_test.000.opl"), ways("boundary_way_test.001.opl"));
+    };
+
+    auto [first, second] = split(ShardedWriter::boundary_way_policy::none);
+    // the ways continue the turns after the nodes
+    BOOST_CHECK(first == way_list({{12, 3}}));
+    BOOST_CHECK(second == way_list({{10, 2}, {11, 3}}));
+
+    std::tie(first, second) = split(ShardedWriter::boundary_way_policy::duplicate);
+    BOOST_CHECK(first == way_list({{10, 2}, {11, 3}, {12, 3}}));
+    BOOST_CHECK(second == way_list({{11, 3}, {12, 3}}));
+
+    std::tie(first, second) = split(ShardedWriter::boundary_way_policy::assign_to_first);
+    BOOST_CHECK(first == way_list({{10, 2}, {11, 3}}));
+    BOOST_CHECK(second == way_list({{12, 3}}));
+
+    // the parts with node 2 alone in the first shard and node 4 alone in the second one are left out
+    std::tie(first, second) = split(ShardedWriter::boundary_way_policy::clip);
+    BOOST_CHECK(first == way_list({{10, 2}, {12, 2}}));
+    BOOST_CHECK(second == way_list({{11, 2}}));
+}
+
+BOOST_AUTO_TEST_SUITE_END()
@@ -0,0 +1,67 @@
+#include <boost/test/unit_test.hpp>
+
+#include <osmium/memory/buffer.hpp>
+#include <osmium/osm/node.hpp>
+
+#include "tag_key_case_normalizer.h"
+
+#include "test_utils.h"
+
+BOOST_AUTO_TEST_SUITE( test_tag_key_case_normalizer )
+
+std::string tags_string(const osmium::TagList &tags) {
+    std::string result;
+    for (const auto &tag: tags) {
+        result += std::string(tag.key()) + "=" + tag.value() + " ";
+    }
+    return result;
+}
+
+const osmium::TagList &node_tags(osmium::memory::Buffer &buffer, const std::map<std::string, std::string> &tags) {
+    buffer.clear();
+    add_node(buffer, 1, tags, 8.0, 49.0);
+    buffer.commit();
+    return buffer.get<osmium::Node>(0).tags();
+}
+
+BOOST_AUTO_TEST_CASE( all_keys ) {
+    TagKeyCaseNormalizer normalizer({}, {"name:Latn"});
+    osmium::memory::Buffer buffer{1024, osmium::memory::Buffer::auto_grow::yes};
+
+    const auto &unchanged = node_tags(buffer, {{"highway", "primary"}, {"name:Latn", "Foo"}});
+    BOOST_CHECK(&normalizer.normalize(unchanged) == &unchanged);
+
+    const auto &tags = node_tags(buffer, {{"Highway", "secondary"}, {"SURFACE", "asphalt"}, {"highway", "primary"}, {"name:Latn", "Foo"}});
+    BOOST_CHECK_EQUAL(tags_string(normalizer.normalize(tags)), "highway=secondary;primary surface=asphalt name:Latn=Foo ");
+    BOOST_CHECK_EQUAL(normalizer.keys_lowercased(), 2);
+    BOOST_CHECK_EQUAL(normalizer.tags_merged(), 1);
+
+    const auto &same_value = node_tags(buffer, {{"Oneway", "yes"}, {"oneway", "yes"}});
+    BOOST_CHECK_EQUAL(tags_string(normalizer.normalize(same_value)), "oneway=yes ");
+    BOOST_CHECK_EQUAL(normalizer.tags_merged(), 2);
+}
+
+BOOST_AUTO_TEST_CASE( configured_keys ) {
+    TagKeyCaseNormalizer normalizer({"highway"}, {});
+    osmium::memory::Buffer buffer{1024, osmium::memory::Buffer::auto_grow::yes};
+
+    const auto &tags = node_tags(buffer, {{"HIGHWAY", "primary"}, {"Surface", "asphalt"}});
+    BOOST_CHECK_EQUAL(tags_string(normalizer.normalize(tags)), "highway=primary Surface=asphalt ");
+    BOOST_CHECK_EQUAL(normalizer.normalized_key("Highway"), "highway");
+    BOOST_CHECK_EQUAL(normalizer.normalized_key("Surface"), "Surface");
+}
+
+BOOST_AUTO_TEST_CASE( lowercase_keys_unchanged ) {
+    TagKeyCaseNormalizer normalizer({"highway"}, {});
+    osmium::memory::Buffer buffer{1024, osmium::memory::Buffer::auto_grow::yes};
+
+    const auto &lowercase = node_tags(buffer, {{"highway", "primary"}, {"name:de", "Straße"}, {"maxspeed", "50"}});
+    BOOST_CHECK(&normalizer.normalize(lowercase) == &lowercase);
+
+    // uppercase keys which are not configured are kept as well
+    const auto &other = node_tags(buffer, {{"highway", "primary"}, {"Name", "Foo"}});
+    BOOST_CHECK(&normalizer.normalize(other) == &other);
+    BOOST_CHECK_EQUAL(normalizer.keys_lowercased(), 0);
+}
+
+BOOST_AUTO_TEST_SUITE_END()
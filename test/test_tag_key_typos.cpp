@@ -0,0 +1,35 @@
+#include <boost/test/unit_test.hpp>
+
+#include <sstream>
+
+#include <osmium/memory/buffer.hpp>
+#include <osmium/osm/way.hpp>
+
+#include "tag_key_typos.h"
+
+#include "test_utils.h"
+
+BOOST_AUTO_TEST_SUITE( test_tag_key_typos )
+
+BOOST_AUTO_TEST_CASE( report ) {
+    osmium::memory::Buffer input{1 << 10, osmium::memory::Buffer::auto_grow::yes};
+    add_way(input, 10, {{"hihgway", "residential"}, {"name", "Hauptstraße"}}, {101, 102});
+    add_way(input, 11, {{"hihgway", "service"}, {"oneway:bicyle", "no"}, {"name:de", "Weg"}}, {102, 103});
+    add_way(input, 12, {{"highway", "track"}, {"tracktype", "grade2"}, {"ref", "K1"}, {"survey:date", "2024"}}, {103, 104});
+
+    TagKeyTypos typos;
+    for (const auto &way: input.select<osmium::Way>()) {
+        typos.add(way.tags(), osmium::item_type::way, way.id());
+    }
+    BOOST_TEST(typos.size() == 2);
+
+    std::ostringstream out;
+    typos.print(out, 10);
+    BOOST_TEST(out.str() == "  hihgway (highway?): 2, e.g. w10\n  oneway:bicyle (bicycle?): 1, e.g. w11\n");
+
+    std::ostringstream limited;
+    typos.print(limited, 1);
+    BOOST_TEST(limited.str() == "  hihgway (highway?): 2, e.g. w10\n  ... 1 more\n");
+}
+
+BOOST_AUTO_TEST_SUITE_END()
@@ -0,0 +1,36 @@
+#include <boost/test/unit_test.hpp>
+
+#include <sstream>
+
+#include "tile_density.h"
+
+BOOST_AUTO_TEST_SUITE( test_tile_density )
+
+BOOST_AUTO_TEST_CASE( counts ) {
+    TileDensity density;
+    density.add_node(osmium::Location(8.68, 49.41));
+    density.add_node(osmium::Location(8.69, 49.42));
+    density.add_node(osmium::Location(-0.5, 51.5));
+    density.add_node(osmium::Location(180.0, 90.0));
+    density.add_node(osmium::Location());
+    density.add_way(osmium::Location(8.68, 49.41));
+    BOOST_TEST(density.size() == 3);
+
+    std::ostringstream out;
+    density.write_csv(out);
+    BOOST_TEST(out.str() == "tile_lon,tile_lat,node_count,way_count\n-1,51,1,0\n8,49,2,1\n179,89,1,0\n");
+}
+
+BOOST_AUTO_TEST_CASE( grid_bbox ) {
+    TileDensity density{GridBBox{5, 45, 10, 50}};
+    density.add_node(osmium::Location(8.68, 49.41));
+    density.add_node(osmium::Location(10.0, 50.0));
+    density.add_node(osmium::Location(-0.5, 51.5));
+    density.add_node(osmium::Location(4.99, 47.0));
+
+    std::ostringstream out;
+    density.write_csv(out);
+    BOOST_TEST(out.str() == "tile_lon,tile_lat,node_count,way_count\n8,49,1,0\n9,49,1,0\n");
+}
+
+BOOST_AUTO_TEST_SUITE_END()
@@ -0,0 +1,39 @@
+#include <boost/test/unit_test.hpp>
+
+#include "unit_normalizer.h"
+
+BOOST_AUTO_TEST_SUITE( test_unit_normalizer )
+
+BOOST_AUTO_TEST_CASE( lengths ) {
+    BOOST_TEST(UnitNormalizer::to_metric("maxheight", "10'6\"").value_or("") == "3.2");
+    BOOST_TEST(UnitNormalizer::to_metric("maxheight", "10' 6\"").value_or("") == "3.2");
+    BOOST_TEST(UnitNormalizer::to_metric("maxheight", "10 ft 6 in").value_or("") == "3.2");
+    BOOST_TEST(UnitNormalizer::to_metric("maxwidth", "7'").value_or("") == "2.13");
+    BOOST_TEST(UnitNormalizer::to_metric("width", "12 feet").value_or("") == "3.66");
+    BOOST_TEST(UnitNormalizer::to_metric("width", "30 in").value_or("") == "0.76");
+    BOOST_TEST(!UnitNormalizer::to_metric("maxheight", "3.5"));
+    BOOST_TEST(!UnitNormalizer::to_metric("maxheight", "3.5 m"));
+    BOOST_TEST(!UnitNormalizer::to_metric("maxheight", "default"));
+    BOOST_TEST(!UnitNormalizer::to_metric("width", "10 yards"));
+    BOOST_TEST(!UnitNormalizer::to_metric("width", "6\"10'"));
+    BOOST_TEST(!UnitNormalizer::to_metric("width", "-3'"));
+}
+
+BOOST_AUTO_TEST_CASE( speeds ) {
+    BOOST_TEST(UnitNormalizer::to_metric("maxspeed", "30 mph").value_or("") == "48");
+    BOOST_TEST(UnitNormalizer::to_metric("maxspeed:forward", "55mph").value_or("") == "89");
+    BOOST_TEST(!UnitNormalizer::to_metric("maxspeed", "50"));
+    BOOST_TEST(!UnitNormalizer::to_metric("maxspeed", "none"));
+    BOOST_TEST(!UnitNormalizer::to_metric("maxspeed", "30 knots"));
+    BOOST_TEST(!UnitNormalizer::to_metric("maxspeed", "10'"));
+}
+
+BOOST_AUTO_TEST_CASE( configured_keys ) {
+    UnitNormalizer normalizer({"maxheight"});
+    BOOST_TEST(normalizer.normalize("maxheight", "10'6\"").value_or("") == "3.2");
+    BOOST_TEST(!normalizer.normalize("maxheight", "3.2"));
+    BOOST_TEST(!normalizer.normalize("width", "12 feet"));
+    BOOST_TEST(normalizer.conversions() == 1);
+}
+
+BOOST_AUTO_TEST_SUITE_END()
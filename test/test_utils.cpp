@@ -28,3 +28,14 @@ void add_way(osmium::memory::Buffer& buffer, osmium::object_id_type id, std::map
     util_add_tags(builder, tags);
     util_add_refs(builder, refs);
 }
+
+void add_relation(osmium::memory::Buffer& buffer, osmium::object_id_type id, std::map<std::string, std::string> tags, std::vector<test_member> members) {
+    osmium::builder::RelationBuilder builder(buffer);
+    osmium::Relation& obj = builder.object();
+    obj.set_id(id);
+    util_add_tags(builder, tags);
+    osmium::builder::RelationMemberListBuilder member_builder{builder};
+    for (const auto& member : members) {
+        member_builder.add_member(member.type, member.ref, member.role.c_str());
+    }
+}
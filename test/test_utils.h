@@ -13,4 +13,13 @@ void add_node(osmium::memory::Buffer &buffer, osmium::object_id_type id, std::ma
 
 void add_way(osmium::memory::Buffer& buffer, osmium::object_id_type id, std::map<std::string, std::string> tags, std::vector<osmium::object_id_type> refs);
 
+// members are given as type, id and role, e.g. {osmium::item_type::way, 12, "outer"}
+struct test_member {
+    osmium::item_type type;
+    osmium::object_id_type ref;
+    std::string role;
+};
+
+void add_relation(osmium::memory::Buffer& buffer, osmium::object_id_type id, std::map<std::string, std::string> tags, std::vector<test_member> members);
+
 #endif//OSM_TRANSFORM_TEST_UTILS_H
@@ -0,0 +1,65 @@
+#include <boost/test/unit_test.hpp>
+
+#include <filesystem>
+#include <fstream>
+#include <iostream>
+#include <sstream>
+#include <string>
+#include <vector>
+
+#include "quiet_output.h"
+#include "warnings.h"
+
+BOOST_AUTO_TEST_SUITE( test_warnings )
+
+BOOST_AUTO_TEST_CASE( quiet_output ) {
+    std::ostringstream out;
+    auto *buffer = std::cout.rdbuf(out.rdbuf());
+    {
+        QuietOutput quiet_output;
+        BOOST_CHECK(WarningLog::instance().quiet());
+        Warning("test") << "suppressed";
+    }
+    BOOST_CHECK(!WarningLog::instance().quiet());
+    Warning("test") << "printed";
+    std::cout.rdbuf(buffer);
+
+    BOOST_CHECK(out.str() == "WARNING: printed\n");
+}
+
+BOOST_AUTO_TEST_CASE( quiet_output_restore ) {
+    QuietOutput quiet_output;
+    quiet_output.restore();
+    BOOST_CHECK(!WarningLog::instance().quiet());
+    quiet_output.restore();
+    BOOST_CHECK(!WarningLog::instance().quiet());
+}
+
+BOOST_AUTO_TEST_CASE( limit_per_category ) {
+    const auto path = std::filesystem::temp_directory_path() / "osm_transform_test_warnings.jsonl";
+    auto &log = WarningLog::instance();
+    log.open(path.string(), 2);
+    for (int i = 1; i <= 5; ++i) {
+        log.record("missing_elevation", "no elevation found", osmium::item_type::node, i);
+    }
+    log.record("invalid_ref", "1 invalid node refs", osmium::item_type::way, 7);
+    BOOST_CHECK(log.counts().at("missing_elevation") == 5);
+    BOOST_CHECK(log.counts().at("invalid_ref") == 1);
+    log.close();
+    BOOST_CHECK(!log.is_open());
+
+    std::ifstream in(path);
+    std::vector<std::string> lines;
+    for (std::string line; std::getline(in, line);) {
+        lines.push_back(line);
+    }
+    std::filesystem::remove(path);
+
+    BOOST_REQUIRE(lines.size() == 4);
+    BOOST_CHECK(lines[0] == R"({"category": "missing_elevation", "type": "node", "id": 1, "message": "no elevation found"})");
+    BOOST_CHECK(lines[1] == R"({"category": "missing_elevation", "type": "node", "id": 2, "message": "no elevation found"})");
+    BOOST_CHECK(lines[2] == R"({"category": "invalid_ref", "type": "way", "id": 7, "message": "1 invalid node refs"})");
+    BOOST_CHECK(lines[3] == R"({"category": "missing_elevation", "omitted": 3, "message": "3 further warnings omitted"})");
+}
+
+BOOST_AUTO_TEST_SUITE_END()
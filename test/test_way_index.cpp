@@ -0,0 +1,51 @@
+#include <boost/test/unit_test.hpp>
+
+#include <algorithm>
+#include <iterator>
+#include <sstream>
+#include <stdexcept>
+#include <vector>
+
+#include "way_index.h"
+
+BOOST_AUTO_TEST_SUITE( test_way_index )
+
+osmium::Box box(const double min_lon, const double min_lat, const double max_lon, const double max_lat) {
+    return {osmium::Location(min_lon, min_lat), osmium::Location(max_lon, max_lat)};
+}
+
+BOOST_AUTO_TEST_CASE( write_and_read ) {
+    WayIndex index;
+    index.add(1, box(8.0, 49.0, 8.1, 49.1));
+    index.add(2, box(9.0, 50.0, 9.2, 50.1));
+    index.add(3, box(8.05, 49.05, 8.2, 49.2));
+    index.add(4, osmium::Box());
+    BOOST_CHECK_EQUAL(index.size(), 3);
+
+    std::stringstream file;
+    index.write(file);
+    BOOST_CHECK_EQUAL(file.str().size(), std::string(WayIndex::kSignature).size() + 1 + 3 * 24);
+
+    const auto tree = WayIndex::read(file);
+    BOOST_CHECK_EQUAL(tree.size(), 3);
+    std::vector<WayIndex::entry> found;
+    tree.query(boost::geometry::index::intersects(WayIndex::point(8.09, 49.09)), std::back_inserter(found));
+    std::vector<osmium::object_id_type> ids;
+    for (const auto &[b, id]: found) {
+        ids.push_back(id);
+    }
+    std::sort(ids.begin(), ids.end());
+    BOOST_CHECK(ids == std::vector<osmium::object_id_type>({1, 3}));
+    found.clear();
+    tree.query(boost::geometry::index::intersects(WayIndex::point(9.1, 50.05)), std::back_inserter(found));
+    BOOST_REQUIRE_EQUAL(found.size(), 1);
+    BOOST_CHECK_EQUAL(found[0].second, 2);
+    BOOST_CHECK_CLOSE(found[0].first.max_corner().get<0>(), 9.2, 0.0001);
+}
+
+BOOST_AUTO_TEST_CASE( read_invalid ) {
+    std::stringstream file("osm-transform way index 0\n");
+    BOOST_CHECK_THROW(WayIndex::read(file), std::runtime_error);
+}
+
+BOOST_AUTO_TEST_SUITE_END()